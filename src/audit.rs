@@ -0,0 +1,156 @@
+use axum::{
+    extract::{rejection::QueryRejection, Extension, Query},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use scylla::frame::value::Timestamp;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, info};
+
+use crate::{from_cql_timestamp, to_cql_timestamp, AppError, AppState};
+
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1000;
+
+/// Ações administrativas registradas na trilha de auditoria
+#[derive(Clone, Copy)]
+pub enum Action {
+    Create,
+    Update,
+    Delete,
+}
+
+impl Action {
+    fn as_str(self) -> &'static str {
+        match self {
+            Action::Create => "create",
+            Action::Update => "update",
+            Action::Delete => "delete",
+        }
+    }
+}
+
+/// Registra uma ação em `audit_log` em segundo plano, sem atrasar a resposta.
+///
+/// `owner` é a API key que executou a ação (`None` sem autenticação) e `detail`
+/// descreve a mudança (ex: o destino criado ou o antigo e o novo destino).
+/// Falhas só vão para o log: a trilha não bloqueia a operação em si.
+pub fn record(state: &Arc<AppState>, action: Action, short_url: &str, owner: Option<&str>, detail: String) {
+    info!(
+        target: "audit",
+        action = action.as_str(),
+        short_url,
+        owner,
+        detail = detail.as_str(),
+        "Audit event"
+    );
+
+    let state = Arc::clone(state);
+    let values = (
+        short_url.to_string(),
+        action.as_str(),
+        owner.map(str::to_string),
+        to_cql_timestamp(Utc::now()),
+        detail,
+    );
+    let background = state.background.clone();
+    background.spawn(async move {
+        if let Err(e) = state
+            .cassandra_breaker
+            .run(state.cassandra.execute(&state.statements.insert_audit, values))
+            .await
+        {
+            error!("Cassandra audit log error: {}", e);
+        }
+    });
+}
+
+#[derive(Deserialize)]
+pub struct AuditParams {
+    short_url: String,
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct AuditEntry {
+    action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    owner: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct AuditHistory {
+    short_url: String,
+    /// Mais recentes primeiro
+    entries: Vec<AuditEntry>,
+}
+
+// GET /api/audit?short_url=&limit=
+pub async fn audit_history(
+    params: Result<Query<AuditParams>, QueryRejection>,
+    Extension(state): Extension<Arc<AppState>>,
+) -> Result<Json<AuditHistory>, AppError> {
+    let Query(params) = params.map_err(|e| AppError::BadRequest(e.body_text()))?;
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT);
+    if limit == 0 || limit > MAX_LIMIT {
+        return Err(AppError::BadRequest(format!(
+            "limit must be between 1 and {}",
+            MAX_LIMIT
+        )));
+    }
+
+    // A partição é o próprio código e o timeuuid ordena as entradas (mais novas primeiro)
+    let mut rows = state
+        .cassandra_breaker
+        .run(state.cassandra.execute_iter(
+            state.statements.select_audit.clone(),
+            (params.short_url.clone(), limit as i32),
+        ))
+        .await?
+        .into_typed::<(String, Option<String>, Option<Timestamp>, Option<String>)>();
+
+    let mut entries = Vec::new();
+    while let Some(row) = rows.next().await {
+        let (action, owner, at, detail) =
+            row.map_err(|e| AppError::Internal(format!("audit_log row: {}", e)))?;
+        entries.push(AuditEntry {
+            action,
+            owner,
+            at: at.map(from_cql_timestamp),
+            detail,
+        });
+    }
+
+    Ok(Json(AuditHistory {
+        short_url: params.short_url,
+        entries,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn response_schemas_match_structs() {
+        use crate::openapi::schema_fields::assert_response;
+        let entry = AuditEntry {
+            action: String::new(),
+            owner: None,
+            at: None,
+            detail: None,
+        };
+        assert_response("AuditHistory/properties/entries/items", &entry);
+        let history = AuditHistory {
+            short_url: String::new(),
+            entries: Vec::new(),
+        };
+        assert_response("AuditHistory", &history);
+    }
+}
@@ -0,0 +1,102 @@
+use axum::{
+    extract::{Extension, Request},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{AppError, AppState};
+
+/// Identificador da API key que autenticou a requisição, anexado às extensions
+#[derive(Clone, Debug)]
+pub struct ApiKeyId(pub String);
+
+/// API keys aceitas nas rotas de escrita, lidas de `API_KEYS`.
+///
+/// Cada entrada é `identificador:chave` ou só `chave`; no segundo caso o
+/// identificador é derivado do hash, para que a chave nunca apareça em logs.
+/// As chaves são guardadas e comparadas pelo hash blake3.
+#[derive(Default)]
+pub struct ApiKeys {
+    by_hash: HashMap<[u8; 32], String>,
+}
+
+impl ApiKeys {
+    pub fn parse(spec: &str) -> Self {
+        let by_hash = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (id, key) = match entry.split_once(':') {
+                    Some((id, key)) => (id.to_string(), key),
+                    None => (format!("key-{}", &hash_hex(entry)[..8]), entry),
+                };
+                (*blake3::hash(key.as_bytes()).as_bytes(), id)
+            })
+            .collect();
+        ApiKeys { by_hash }
+    }
+
+    /// Sem chaves configuradas, as rotas de escrita ficam abertas (ex: desenvolvimento local)
+    pub fn is_enabled(&self) -> bool {
+        !self.by_hash.is_empty()
+    }
+
+    fn identify(&self, key: &str) -> Option<&str> {
+        self.by_hash
+            .get(blake3::hash(key.as_bytes()).as_bytes())
+            .map(String::as_str)
+    }
+}
+
+fn hash_hex(value: &str) -> String {
+    blake3::hash(value.as_bytes()).to_hex().to_string()
+}
+
+/// Exige `Authorization: Bearer <key>`: 401 sem header, 403 para chave inválida
+pub async fn require_api_key(
+    Extension(state): Extension<Arc<AppState>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let api_keys = &state.config.api_keys;
+    if !api_keys.is_enabled() {
+        return Ok(next.run(request).await);
+    }
+
+    let key = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .ok_or(AppError::Unauthorized)?;
+
+    let id = api_keys.identify(key).ok_or(AppError::Forbidden)?.to_string();
+    request.extensions_mut().insert(ApiKeyId(id));
+
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identifies_named_and_anonymous_keys() {
+        let keys = ApiKeys::parse("acme:s3cret, bare-key ,");
+        assert!(keys.is_enabled());
+        assert_eq!(keys.identify("s3cret"), Some("acme"));
+        assert!(keys.identify("bare-key").unwrap().starts_with("key-"));
+        assert_eq!(keys.identify("acme:s3cret"), None);
+        assert_eq!(keys.identify("other"), None);
+    }
+
+    #[test]
+    fn empty_spec_disables_auth() {
+        assert!(!ApiKeys::parse("").is_enabled());
+    }
+}
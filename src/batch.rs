@@ -0,0 +1,497 @@
+use axum::{
+    extract::{rejection::JsonRejection, Extension},
+    Json,
+};
+use chrono::{DateTime, SubsecRound, Utc};
+use scylla::batch::Batch;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::{atomic::Ordering, Arc};
+use tracing::{error, info, warn};
+
+use crate::audit;
+use crate::auth::ApiKeyId;
+use crate::quota;
+use crate::rate_limit::{self, RateLimitWindow};
+use crate::variants;
+use crate::tags;
+use crate::tombstone;
+use crate::shortcode::{decode_code, is_reserved, is_valid_alias};
+use crate::validation::{is_self_link, normalize_long_url};
+use crate::{
+    cache_key, canonical_code, click_limit, effective_cache_ttl, short_url_for_id, stored_code,
+    parse_insert_outcome, to_cql_timestamp, AppError, AppState, CachedLink, InsertOutcome, UrlRow,
+    SELF_LINK_ERROR,
+};
+
+#[derive(Deserialize)]
+pub struct BatchRequest {
+    urls: Vec<String>,
+}
+
+/// Resultado de um item do lote, na mesma posição da URL enviada
+#[derive(Serialize)]
+pub struct BatchItem {
+    long_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    short_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    short_link: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl BatchItem {
+    fn failed(long_url: String, error: String) -> Self {
+        BatchItem {
+            long_url,
+            short_url: None,
+            short_link: None,
+            created_at: None,
+            error: Some(error),
+        }
+    }
+}
+
+// POST /shorten/batch
+pub async fn create_shorten_batch(
+    Extension(state): Extension<Arc<AppState>>,
+    api_key: Option<Extension<ApiKeyId>>,
+    rate_limit: Option<Extension<RateLimitWindow>>,
+    payload: Result<Json<BatchRequest>, JsonRejection>,
+) -> Result<Json<Vec<BatchItem>>, AppError> {
+    state.read_only.ensure_writable()?;
+    let Json(payload) = payload?;
+    let max_urls = state.config.batch_max_urls;
+    if payload.urls.len() > max_urls {
+        return Err(AppError::BadRequest(format!(
+            "a batch accepts at most {} urls",
+            max_urls
+        )));
+    }
+    // O middleware já contou a requisição como uma criação; cada URL a mais também conta
+    let extra = (payload.urls.len() as u64).saturating_sub(1);
+    rate_limit::charge(&state, rate_limit.as_ref().map(|Extension(window)| window), extra).await?;
+
+    // 1. Valida cada URL; falhas são reportadas por item sem derrubar o lote
+    let validated: Vec<Result<String, String>> = payload
+        .urls
+        .iter()
+        .map(|url| {
+            let long_url = normalize_long_url(url, state.config.max_url_len, &state.config.allowed_schemes)?;
+            if state.config.blocked_domains.is_blocked(&long_url) {
+                return Err("the destination domain is blocked".to_string());
+            }
+            if is_self_link(&long_url, &state.config.public_base_url) {
+                return Err(SELF_LINK_ERROR.to_string());
+            }
+            Ok(long_url)
+        })
+        .collect();
+    let valid_count = validated.iter().filter(|v| v.is_ok()).count() as u64;
+
+    if valid_count == 0 {
+        let items = payload
+            .urls
+            .into_iter()
+            .zip(validated)
+            .map(|(raw, result)| BatchItem::failed(raw, result.unwrap_err()))
+            .collect();
+        return Ok(Json(items));
+    }
+
+    // 2. Cota do dono para o lote inteiro (tudo ou nada; itens que falham na gravação
+    // devolvem a sua vaga) e todos os IDs de uma vez (um único INCRBY no contador do Redis)
+    let mut redis_conn = state.redis.get();
+    let owner = api_key.map(|Extension(ApiKeyId(id))| id);
+    quota::reserve(&state, &mut redis_conn, owner.as_deref(), valid_count).await?;
+    let mut ids = match state.ids.reserve(&mut redis_conn, valid_count).await {
+        Ok(ids) => ids.into_iter(),
+        Err(e) => {
+            quota::release(&mut redis_conn, owner.as_deref(), valid_count).await;
+            return Err(e.into());
+        }
+    };
+
+    let created_at = Utc::now().trunc_subsecs(3);
+    let mut items = Vec::with_capacity(payload.urls.len());
+    let mut rows = Vec::new();
+    let config = &state.config;
+    let code_for = |id| {
+        short_url_for_id(
+            id,
+            config.id_offset,
+            config.id_limit,
+            &config.code_mode,
+            &config.code_seed,
+            &config.code_alphabet,
+            config.min_code_len,
+        )
+    };
+    // Códigos que não cabem em `CODE_WIDTH` ou IDs avulsos que falham devolvem a
+    // cota do lote inteiro, como as falhas do insert
+    let assigned = async {
+        for (raw, result) in payload.urls.into_iter().zip(validated) {
+            match result {
+                Ok(long_url) => {
+                    let id = ids.next().expect("one id reserved per valid url");
+                    let mut short_url = code_for(id)?;
+                    // Códigos que colidem com rotas do serviço são trocados por um ID avulso
+                    while is_reserved(&short_url) {
+                        let id = state.ids.next(&mut redis_conn).await?;
+                        short_url = code_for(id)?;
+                    }
+                    rows.push((items.len(), short_url.clone(), long_url.clone()));
+                    items.push(BatchItem {
+                        long_url,
+                        short_link: Some(state.config.short_link(&short_url)),
+                        short_url: Some(short_url),
+                        created_at: Some(created_at),
+                        error: None,
+                    });
+                }
+                Err(error) => items.push(BatchItem::failed(raw, error)),
+            }
+        }
+        Ok::<(), AppError>(())
+    }
+    .await;
+    if let Err(e) = assigned {
+        quota::release(&mut redis_conn, owner.as_deref(), valid_count).await;
+        return Err(e);
+    }
+
+    // 3. Grava cada item com sua própria LWT (`IF NOT EXISTS`), em paralelo: LWT em
+    // batch só é aceito dentro de uma mesma partição. Um código já em uso (um alias
+    // criado antes com o mesmo código, por exemplo) nunca é sobrescrito, e uma
+    // falha do Cassandra derruba só o seu item
+    let timestamp = to_cql_timestamp(created_at);
+    let (state, code_for, owner) = (&state, &code_for, &owner);
+    let writes = rows.iter().map(|(_, short_url, long_url)| {
+        let next_code = move || async move {
+            let mut redis_conn = state.redis.get();
+            loop {
+                let short_url = code_for(state.ids.next(&mut redis_conn).await?)?;
+                if !is_reserved(&short_url) {
+                    break Ok::<_, AppError>(short_url);
+                }
+            }
+        };
+        let write = move |short_url: String| async move {
+            let result = state
+                .cassandra_breaker
+                .run_retrying(|| {
+                    state.cassandra.execute(
+                        &state.statements.insert_url,
+                        (
+                            short_url.clone(),
+                            long_url.clone(),
+                            timestamp,
+                            None::<bool>,
+                            None::<i64>,
+                            None::<String>,
+                            owner.clone(),
+                            None::<Vec<String>>,
+                            0i32,
+                        ),
+                    )
+                })
+                .await?;
+            // Mesmo destino e dono: uma tentativa anterior (com timeout) deste item
+            // gravou o link, como em `POST /shorten`
+            match parse_insert_outcome(result) {
+                Some(InsertOutcome::Applied) => Ok::<_, AppError>(true),
+                Some(InsertOutcome::AlreadyExists {
+                    long_url: existing_url,
+                    owner: existing_owner,
+                }) => Ok(existing_url.as_ref() == Some(long_url) && existing_owner == *owner),
+                None => Err(AppError::Internal(format!("unexpected LWT result for '{}'", short_url))),
+            }
+        };
+        write_item(short_url.clone(), next_code, write)
+    });
+    let written = futures::future::join_all(writes).await;
+
+    let mut created = Vec::with_capacity(rows.len());
+    for ((idx, _, long_url), result) in rows.into_iter().zip(written) {
+        let item = &mut items[idx];
+        let error = match result {
+            Ok(Some(short_url)) => {
+                item.short_link = Some(state.config.short_link(&short_url));
+                item.short_url = Some(short_url.clone());
+                created.push((short_url, long_url));
+                continue;
+            }
+            Ok(None) => "short url collision",
+            Err(e) => {
+                error!("Cassandra insert error in batch: {}", e);
+                "database error"
+            }
+        };
+        item.short_url = None;
+        item.short_link = None;
+        item.created_at = None;
+        item.error = Some(error.to_string());
+    }
+    let failed = valid_count - created.len() as u64;
+    if failed > 0 {
+        quota::release(&mut redis_conn, owner.as_deref(), failed).await;
+    }
+
+    state
+        .metrics
+        .shortens
+        .fetch_add(created.len() as u64, Ordering::Relaxed);
+    for (short_url, long_url) in &created {
+        audit::record(state, audit::Action::Create, short_url, owner.as_deref(), long_url.clone());
+    }
+
+    // 4. Popula o cache em um único pipeline
+    let cache_ttl = effective_cache_ttl(state.config.cache_ttl_secs, None);
+    let mut pipe = redis::pipe();
+    for (short_url, long_url) in &created {
+        let link = CachedLink {
+            long_url: long_url.clone(),
+            permanent: None,
+            max_clicks: None,
+            password_hash: None,
+            expires_at: None,
+            geo: None,
+            variants: None,
+        };
+        if let Ok(json) = serde_json::to_string(&link) {
+            pipe.set_ex(cache_key(short_url), json, cache_ttl).ignore();
+        }
+    }
+    let cache_result: redis::RedisResult<()> = pipe.query_async(&mut redis_conn).await;
+    if let Err(e) = cache_result {
+        warn!("Redis cache error: {}", e);
+    }
+
+    Ok(Json(items))
+}
+
+/// Códigos que um item do lote tenta antes de ser dado como falho
+const WRITE_ATTEMPTS: usize = 3;
+
+/// Grava um item com `IF NOT EXISTS` (`write` diz se o link gravado é deste item).
+/// Um código já em uso nunca é sobrescrito: o item passa para um código novo
+/// (`next_code`), e fica sem link (`None`) se todas as tentativas colidirem
+async fn write_item<N, NF, W, WF>(
+    mut short_url: String,
+    mut next_code: N,
+    mut write: W,
+) -> Result<Option<String>, AppError>
+where
+    N: FnMut() -> NF,
+    NF: Future<Output = Result<String, AppError>>,
+    W: FnMut(String) -> WF,
+    WF: Future<Output = Result<bool, AppError>>,
+{
+    let mut attempts = 1;
+    while !write(short_url.clone()).await? {
+        warn!("Short URL collision in batch: '{}' already exists", short_url);
+        if attempts == WRITE_ATTEMPTS {
+            return Ok(None);
+        }
+        attempts += 1;
+        short_url = next_code().await?;
+    }
+    Ok(Some(short_url))
+}
+
+#[derive(Deserialize)]
+pub struct BatchDeleteRequest {
+    short_urls: Vec<String>,
+}
+
+/// Resultado da remoção de um código, na mesma posição do código enviado
+#[derive(Serialize)]
+pub struct BatchDeleteItem {
+    short_url: String,
+    /// `deleted`, `not_found` ou `invalid` (formato que nenhum código pode ter)
+    status: &'static str,
+}
+
+// POST /api/urls/batch-delete
+pub async fn delete_batch(
+    Extension(state): Extension<Arc<AppState>>,
+    api_key: Option<Extension<ApiKeyId>>,
+    payload: Result<Json<BatchDeleteRequest>, JsonRejection>,
+) -> Result<Json<Vec<BatchDeleteItem>>, AppError> {
+    state.read_only.ensure_writable()?;
+    let Json(payload) = payload?;
+    let max_urls = state.config.batch_max_urls;
+    if payload.short_urls.len() > max_urls {
+        return Err(AppError::BadRequest(format!(
+            "a batch accepts at most {} short_urls",
+            max_urls
+        )));
+    }
+
+    // 1. Códigos que não são aliases válidos nem decodificam não vão ao banco. Os
+    // demais são levados ao código gravado (com CASE_INSENSITIVE, `Launch` é
+    // `launch`), e repetições são processadas uma vez só: uma vaga na cota e um
+    // registro de auditoria
+    let config = &state.config;
+    let codes = payload.short_urls.into_iter().map(|short_url| async {
+        let canonical = canonical_code(config, &short_url);
+        let decoded = decode_code(&config.code_mode, &config.code_seed, &config.code_alphabet, &canonical);
+        if decoded.is_none() && (config.code_mode.is_signed() || !is_valid_alias(&short_url)) {
+            return Ok((short_url, "invalid"));
+        }
+        Ok::<_, AppError>((stored_code(&state, short_url).await?, "not_found"))
+    });
+    let codes = futures::future::try_join_all(codes).await?;
+    let mut seen = HashSet::new();
+    let mut items: Vec<BatchDeleteItem> = codes
+        .iter()
+        .filter(|(short_url, _)| seen.insert(short_url.as_str()))
+        .map(|(short_url, status)| BatchDeleteItem {
+            short_url: short_url.clone(),
+            status,
+        })
+        .collect();
+    let short_urls: Vec<String> = codes.into_iter().map(|(short_url, _)| short_url).collect();
+
+    // 2. Lê os links em paralelo: o batch não usa LWT (partições diferentes), então
+    // é a leitura que separa `deleted` de `not_found` e informa o dono para a cota
+    let lookups = items
+        .iter()
+        .filter(|item| item.status != "invalid")
+        .map(|item| {
+            state
+                .cassandra_breaker
+                .run(state.cassandra.execute(&state.statements.select_url, (item.short_url.clone(),)))
+        });
+    let rows = futures::future::try_join_all(lookups).await?;
+    let mut existing = Vec::new();
+    for (item, result) in items.iter_mut().filter(|item| item.status != "invalid").zip(rows) {
+        let row = result
+            .maybe_first_row_typed::<UrlRow>()
+            .map_err(|e| AppError::Internal(format!("urls row: {}", e)))?;
+        if let Some(row) = row {
+            item.status = "deleted";
+            existing.push((item.short_url.clone(), row.long_url, row.owner, row.tags));
+        }
+    }
+    if existing.is_empty() {
+        return Ok(Json(in_request_order(short_urls, &items)));
+    }
+
+    // 3. Remove tudo em um único BatchStatement
+    let mut batch = Batch::default();
+    batch.set_consistency(state.config.consistency);
+    let keys: Vec<(String,)> = existing
+        .iter()
+        .flat_map(|(short_url, ..)| [(short_url.clone(),), (short_url.clone(),)])
+        .collect();
+    // Os destinos por país saem no mesmo batch, para que um alias recriado não os herde
+    for _ in &existing {
+        batch.append_statement(state.statements.delete_url_unconditional.clone());
+        batch.append_statement(state.statements.delete_url_geo.clone());
+    }
+    state.cassandra_breaker.run(state.cassandra.batch(&batch, &keys)).await?;
+
+    // Variantes e seus contadores ficam fora do batch: contadores não podem ser
+    // misturados com escritas comuns em um mesmo BatchStatement
+    futures::future::join_all(existing.iter().map(|(short_url, ..)| variants::delete(&state, short_url))).await;
+    futures::future::join_all(existing.iter().map(|(short_url, _, _, tags)| {
+        tags::delete(&state, short_url, tags.as_deref().unwrap_or_default())
+    }))
+    .await;
+    futures::future::join_all(existing.iter().map(|(short_url, ..)| tombstone::record(&state, short_url))).await;
+
+    // 4. Tira os links do cache (e os contadores de cliques) e devolve as vagas nas cotas
+    let mut redis_conn = state.redis.get();
+    let mut pipe = redis::pipe();
+    for (short_url, ..) in &existing {
+        pipe.del(&[cache_key(short_url), click_limit::counter_key(short_url)]).ignore();
+    }
+    let cache_result: redis::RedisResult<()> = pipe.query_async(&mut redis_conn).await;
+    if let Err(e) = cache_result {
+        warn!("Redis cache error: {}", e);
+    }
+    let actor = api_key.as_ref().map(|Extension(ApiKeyId(id))| id.as_str());
+    for (short_url, long_url, owner, _) in &existing {
+        quota::release(&mut redis_conn, owner.as_deref(), 1).await;
+        audit::record(&state, audit::Action::Delete, short_url, actor, long_url.clone());
+    }
+
+    info!("Batch deleted {} short URLs", existing.len());
+    Ok(Json(in_request_order(short_urls, &items)))
+}
+
+/// Um resultado por código enviado, na ordem do pedido: as repetições recebem o
+/// status da primeira ocorrência
+fn in_request_order(short_urls: Vec<String>, items: &[BatchDeleteItem]) -> Vec<BatchDeleteItem> {
+    let statuses: HashMap<&str, &'static str> =
+        items.iter().map(|item| (item.short_url.as_str(), item.status)).collect();
+    short_urls
+        .into_iter()
+        .map(|short_url| {
+            let status = statuses[short_url.as_str()];
+            BatchDeleteItem { short_url, status }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn write_item_never_overwrites_an_existing_code() {
+        // `urls` com um alias igual ao próximo código gerado
+        let urls = RefCell::new(HashMap::from([("2ty".to_string(), "https://alias.example/")]));
+        let fresh = RefCell::new(vec!["2tz".to_string(), "2ty".to_string()]);
+        let insert = |short_url: String| {
+            let inserted = match urls.borrow_mut().entry(short_url) {
+                std::collections::hash_map::Entry::Occupied(_) => false,
+                std::collections::hash_map::Entry::Vacant(entry) => {
+                    entry.insert("https://rust-lang.org/");
+                    true
+                }
+            };
+            async move { Ok(inserted) }
+        };
+        let next_code = || {
+            let short_url = fresh.borrow_mut().pop().expect("a fresh code");
+            async move { Ok(short_url) }
+        };
+
+        let written = futures::executor::block_on(write_item("2ty".to_string(), next_code, insert));
+        assert_eq!(written.unwrap().as_deref(), Some("2tz"));
+        assert_eq!(urls.borrow()["2ty"], "https://alias.example/");
+        assert_eq!(urls.borrow()["2tz"], "https://rust-lang.org/");
+    }
+
+    #[test]
+    fn write_item_gives_up_after_repeated_collisions() {
+        let attempts = RefCell::new(0);
+        let insert = |_: String| {
+            *attempts.borrow_mut() += 1;
+            async { Ok(false) }
+        };
+        let next_code = || async { Ok("2tz".to_string()) };
+
+        let written = futures::executor::block_on(write_item("2ty".to_string(), next_code, insert));
+        assert_eq!(written.unwrap(), None);
+        assert_eq!(*attempts.borrow(), WRITE_ATTEMPTS);
+    }
+
+    #[test]
+    fn response_schemas_match_structs() {
+        use crate::openapi::schema_fields::assert_response;
+        assert_response("BatchResponse/items", &BatchItem::failed(String::new(), String::new()));
+        let deleted = BatchDeleteItem {
+            short_url: String::new(),
+            status: "deleted",
+        };
+        assert_response("BatchDeleteResponse/items", &deleted);
+    }
+}
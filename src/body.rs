@@ -0,0 +1,92 @@
+use axum::{
+    async_trait,
+    body::{Body, Bytes},
+    extract::{FromRequest, Request},
+    http::{header, HeaderMap},
+    Form, Json,
+};
+use serde::de::DeserializeOwned;
+use url::form_urlencoded;
+
+use crate::AppError;
+
+/// Corpo em JSON ou, com `Content-Type: application/x-www-form-urlencoded`, em
+/// formulário (ex: um `<form>` HTML sem JavaScript), desserializado no mesmo tipo.
+///
+/// Formulários só representam campos simples: listas e mapas (`geo`, `variants`,
+/// `tags`) continuam exigindo JSON. Campos vazios, como os de inputs opcionais
+/// não preenchidos, são tratados como ausentes
+pub struct JsonOrForm<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for JsonOrForm<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, AppError> {
+        if !is_form(request.headers()) {
+            let Json(value) = Json::<T>::from_request(request, state).await?;
+            return Ok(JsonOrForm(value));
+        }
+
+        // O limite do corpo (`DefaultBodyLimit`) vale aqui como no JSON
+        let (parts, body) = request.into_parts();
+        let bytes = Bytes::from_request(Request::from_parts(parts.clone(), body), state)
+            .await
+            .map_err(|e| AppError::InvalidForm {
+                status: e.status(),
+                message: e.body_text(),
+            })?;
+        let body = Body::from(without_empty_fields(&bytes));
+        let Form(value) = Form::<T>::from_request(Request::from_parts(parts, body), state)
+            .await
+            .map_err(|e| AppError::InvalidForm {
+                status: e.status(),
+                message: e.body_text(),
+            })?;
+        Ok(JsonOrForm(value))
+    }
+}
+
+fn is_form(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/x-www-form-urlencoded"))
+}
+
+/// Remove os pares sem valor (`ttl_secs=&short_url=`), que um formulário envia
+/// para cada input vazio
+fn without_empty_fields(body: &[u8]) -> String {
+    form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(form_urlencoded::parse(body).filter(|(_, value)| !value.trim().is_empty()))
+        .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_form_bodies() {
+        let mut headers = HeaderMap::new();
+        assert!(!is_form(&headers));
+        headers.insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
+        assert!(!is_form(&headers));
+        headers.insert(
+            header::CONTENT_TYPE,
+            "Application/X-WWW-Form-Urlencoded; charset=UTF-8".parse().unwrap(),
+        );
+        assert!(is_form(&headers));
+    }
+
+    #[test]
+    fn drops_empty_form_fields() {
+        let body = b"long_url=https%3A%2F%2Frust-lang.org%2F%3Fa%3D1&short_url=&ttl_secs=+";
+        assert_eq!(without_empty_fields(body), "long_url=https%3A%2F%2Frust-lang.org%2F%3Fa%3D1");
+    }
+}
@@ -0,0 +1,52 @@
+/// Trechos de User-Agent dos bots de pré-visualização e crawlers mais comuns
+pub const DEFAULT_BOT_USER_AGENTS: &str = "bot,crawler,spider,slurp,facebookexternalhit,\
+facebookcatalog,slackbot,twitterbot,discordbot,telegrambot,whatsapp,linkedinbot,\
+skypeuripreview,embedly,pinterest,redditbot,applebot,googlebot,bingbot,yandex,\
+baiduspider,duckduckbot,headlesschrome,curl,wget,python-requests";
+
+/// Detecção de bots pelo User-Agent (`BOT_USER_AGENTS`).
+///
+/// Cada entrada é um trecho procurado no User-Agent, sem diferenciar
+/// maiúsculas. Acessos de bots são redirecionados normalmente, mas não entram
+/// no contador de cliques.
+pub struct BotDetector {
+    patterns: Vec<String>,
+}
+
+impl BotDetector {
+    /// Lista separada por vírgula; vazio desativa a detecção
+    pub fn parse(raw: &str) -> Self {
+        let patterns = raw
+            .split(',')
+            .map(|pattern| pattern.trim().to_ascii_lowercase())
+            .filter(|pattern| !pattern.is_empty())
+            .collect();
+        BotDetector { patterns }
+    }
+
+    pub fn is_bot(&self, user_agent: Option<&str>) -> bool {
+        let Some(user_agent) = user_agent.filter(|ua| !ua.is_empty()) else {
+            return false;
+        };
+        let user_agent = user_agent.to_ascii_lowercase();
+        self.patterns.iter().any(|pattern| user_agent.contains(pattern.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_preview_bots() {
+        let bots = BotDetector::parse(DEFAULT_BOT_USER_AGENTS);
+        assert!(bots.is_bot(Some("Slackbot-LinkExpanding 1.0 (+https://api.slack.com/robots)")));
+        assert!(bots.is_bot(Some("facebookexternalhit/1.1")));
+        assert!(bots.is_bot(Some("Twitterbot/1.0")));
+        assert!(!bots.is_bot(Some(
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0 Safari/537.36"
+        )));
+        assert!(!bots.is_bot(None));
+        assert!(!BotDetector::parse("").is_bot(Some("Twitterbot/1.0")));
+    }
+}
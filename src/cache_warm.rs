@@ -0,0 +1,98 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use futures::StreamExt;
+use scylla::frame::value::Counter;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::{load_link, AppError, AppState};
+
+/// Links carregados no cache ao mesmo tempo
+const CONCURRENCY: usize = 16;
+
+/// Carrega no Redis os `limit` links mais clicados, em segundo plano, para que o
+/// primeiro tráfego depois de um deploy não caia todo no Cassandra (`CACHE_WARM`)
+pub fn spawn(state: Arc<AppState>, limit: usize) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let start = Instant::now();
+        match warm(&state, limit).await {
+            Ok(loaded) => info!(
+                loaded,
+                elapsed_ms = start.elapsed().as_millis() as u64,
+                "Cache warmed with the {} most clicked links",
+                loaded
+            ),
+            Err(e) => error!("Cache warm failed: {}", e),
+        }
+    })
+}
+
+async fn warm(state: &AppState, limit: usize) -> Result<usize, AppError> {
+    // 1. Contadores não podem ser ordenados pelo Cassandra: varre `url_clicks`
+    // guardando só os `limit` maiores
+    let mut rows = state
+        .cassandra_breaker
+        .run(state.cassandra.execute_iter(state.statements.select_all_clicks.clone(), &[]))
+        .await?
+        .into_typed::<(String, Counter)>();
+    let mut counters = Vec::new();
+    while let Some(row) = rows.next().await {
+        let (short_url, Counter(hits)) =
+            row.map_err(|e| AppError::Internal(format!("url_clicks row: {}", e)))?;
+        counters.push((short_url, hits));
+        // Compacta de tempos em tempos, sem manter a tabela inteira em memória
+        if counters.len() >= limit * 4 {
+            counters = most_clicked(counters, limit);
+        }
+    }
+    let codes = most_clicked(counters, limit);
+
+    // 2. Lê cada link do Cassandra e grava no cache, como em um cache miss;
+    // códigos com contador mas já removidos entram no cache negativo
+    let loaded = futures::stream::iter(codes)
+        .map(|(short_url, _)| async move {
+            let mut redis_conn = state.redis.get();
+            matches!(load_link(state, &mut redis_conn, &short_url).await, Ok(Some(_)))
+        })
+        .buffer_unordered(CONCURRENCY)
+        .filter(|loaded| futures::future::ready(*loaded))
+        .count()
+        .await;
+    Ok(loaded)
+}
+
+/// Os `limit` códigos com mais cliques, do mais clicado para o menos
+fn most_clicked(counters: Vec<(String, i64)>, limit: usize) -> Vec<(String, i64)> {
+    let mut heap = BinaryHeap::with_capacity(limit + 1);
+    for (short_url, hits) in counters {
+        heap.push(Reverse((hits, short_url)));
+        if heap.len() > limit {
+            heap.pop();
+        }
+    }
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((hits, short_url))| (short_url, hits))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_most_clicked_codes() {
+        let counters = vec![
+            ("a".to_string(), 5),
+            ("b".to_string(), 50),
+            ("c".to_string(), 1),
+            ("d".to_string(), 20),
+        ];
+        let top: Vec<String> = most_clicked(counters, 2).into_iter().map(|(code, _)| code).collect();
+        assert_eq!(top, ["b", "d"]);
+        assert!(most_clicked(Vec::new(), 3).is_empty());
+    }
+}
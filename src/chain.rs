@@ -0,0 +1,79 @@
+use std::borrow::Cow;
+
+use url::Url;
+
+use crate::redirect::append_query;
+use crate::redis_pool::RedisConnection;
+use crate::validation::is_self_link;
+use crate::{find_short_link, AppError, AppState};
+
+/// Segue destinos que são short links do próprio serviço, até `MAX_CHAIN_HOPS`
+/// redirecionamentos no total, para que o cliente receba direto o destino final.
+///
+/// Com o padrão (1) nada é seguido. Um código já visitado ou uma cadeia mais longa
+/// que o limite viram `AppError::RedirectLoop` (508). A cadeia para em códigos
+/// inexistentes e em links com senha, que recebem o redirecionamento normal; dos
+/// intermediários só vale a `long_url` (sem cliques, `max_clicks`, país ou variante)
+pub async fn follow<'a>(
+    state: &AppState,
+    redis_conn: &mut RedisConnection,
+    short_url: &str,
+    destination: &'a str,
+) -> Result<Cow<'a, str>, AppError> {
+    let max_hops = state.config.max_chain_hops;
+    let base_url = &state.config.public_base_url;
+    if max_hops <= 1 || !is_self_link(destination, base_url) {
+        return Ok(Cow::Borrowed(destination));
+    }
+
+    let mut visited = vec![short_url.to_string()];
+    let mut current = destination.to_string();
+    for _ in 1..max_hops {
+        let Some((code, query)) = local_code(&current, base_url) else {
+            return Ok(Cow::Owned(current));
+        };
+        let Some((code, link, _)) = find_short_link(state, redis_conn, &code).await? else {
+            return Ok(Cow::Owned(current));
+        };
+        if visited.contains(&code) {
+            return Err(AppError::RedirectLoop(short_url.to_string()));
+        }
+        if link.password_hash.is_some() {
+            return Ok(Cow::Owned(current));
+        }
+        visited.push(code);
+        current = append_query(&link.long_url, query.as_deref());
+        if !is_self_link(&current, base_url) {
+            return Ok(Cow::Owned(current));
+        }
+    }
+    Err(AppError::RedirectLoop(short_url.to_string()))
+}
+
+/// Código e query de uma URL do próprio serviço: o único segmento do caminho
+/// depois do de `PUBLIC_BASE_URL`. `None` para outras rotas (`/api/...`)
+fn local_code(url: &str, public_base_url: &str) -> Option<(String, Option<String>)> {
+    let (url, base) = (Url::parse(url).ok()?, Url::parse(public_base_url).ok()?);
+    let code = url.path().strip_prefix(base.path().trim_end_matches('/'))?.strip_prefix('/')?;
+    if code.is_empty() || code.contains('/') {
+        return None;
+    }
+    Some((code.to_string(), url.query().map(str::to_string)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_local_codes() {
+        assert_eq!(local_code("https://sho.rt/2tx", "https://sho.rt"), Some(("2tx".to_string(), None)));
+        assert_eq!(
+            local_code("https://sho.rt/2tx?utm_source=qr", "https://sho.rt/"),
+            Some(("2tx".to_string(), Some("utm_source=qr".to_string())))
+        );
+        assert_eq!(local_code("https://sho.rt/s/2tx", "https://sho.rt/s"), Some(("2tx".to_string(), None)));
+        assert_eq!(local_code("https://sho.rt/", "https://sho.rt"), None);
+        assert_eq!(local_code("https://sho.rt/api/urls", "https://sho.rt"), None);
+    }
+}
@@ -0,0 +1,221 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use scylla::transport::errors::{DbError, QueryError};
+use tracing::warn;
+
+use crate::AppError;
+
+/// Tentativas de uma escrita em `run_retrying`, contando a primeira
+const WRITE_ATTEMPTS: u32 = 3;
+/// Espera antes da segunda tentativa; dobra a cada nova falha
+const RETRY_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Circuit breaker das queries ao Cassandra.
+///
+/// Depois de `threshold` falhas seguidas (cada uma a menos de `window` da
+/// anterior), o circuito abre por `cooldown`: as queries falham na hora com 503
+/// em vez de somar carga a um cluster já sobrecarregado. Passado o cooldown, as
+/// queries voltam a ser tentadas; qualquer sucesso zera a contagem.
+pub struct CircuitBreaker {
+    /// 0 desativa o circuit breaker
+    threshold: u32,
+    window_ms: u64,
+    cooldown_ms: u64,
+    /// Referência dos instantes guardados em milissegundos nos atômicos abaixo
+    started: Instant,
+    failures: AtomicU32,
+    last_failure_ms: AtomicU64,
+    /// Até quando o circuito fica aberto (0 = fechado)
+    open_until_ms: AtomicU64,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, window: Duration, cooldown: Duration) -> Self {
+        CircuitBreaker {
+            threshold,
+            window_ms: window.as_millis() as u64,
+            cooldown_ms: cooldown.as_millis() as u64,
+            started: Instant::now(),
+            failures: AtomicU32::new(0),
+            last_failure_ms: AtomicU64::new(0),
+            open_until_ms: AtomicU64::new(0),
+        }
+    }
+
+    /// Executa a query se o circuito estiver fechado, registrando o resultado
+    pub async fn run<T>(&self, query: impl Future<Output = Result<T, QueryError>>) -> Result<T, AppError> {
+        self.check()?;
+        let result = crate::timing::cassandra(query).await;
+        self.record(&result);
+        result.map_err(Into::into)
+    }
+
+    /// Como `run`, mas repete a query (até `WRITE_ATTEMPTS` vezes, com backoff)
+    /// em falhas passageiras do cluster. Só para escritas que podem ser repetidas
+    /// sem efeito duplicado, como o `INSERT ... IF NOT EXISTS` dos links
+    pub async fn run_retrying<T, F, Fut>(&self, mut query: F) -> Result<T, AppError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, QueryError>>,
+    {
+        let mut backoff = RETRY_BACKOFF;
+        let mut attempt = 1;
+        loop {
+            self.check()?;
+            let result = crate::timing::cassandra(query()).await;
+            self.record(&result);
+            match result {
+                Err(e) if attempt < WRITE_ATTEMPTS && is_retryable(&e) => {
+                    warn!("Cassandra write failed (attempt {}/{}), retrying: {}", attempt, WRITE_ATTEMPTS, e);
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                result => return result.map_err(Into::into),
+            }
+        }
+    }
+
+    /// 503 na hora se o circuito estiver aberto
+    fn check(&self) -> Result<(), AppError> {
+        match self.open_for(self.now_ms()) {
+            Some(retry_after) => Err(AppError::Unavailable { retry_after }),
+            None => Ok(()),
+        }
+    }
+
+    fn record<T>(&self, result: &Result<T, QueryError>) {
+        match result {
+            Ok(_) => self.failures.store(0, Ordering::Relaxed),
+            Err(e) if is_overload(e) => self.record_failure(self.now_ms()),
+            Err(_) => {}
+        }
+    }
+
+    /// Segundos restantes de circuito aberto, se estiver aberto
+    fn open_for(&self, now_ms: u64) -> Option<u64> {
+        let open_until = self.open_until_ms.load(Ordering::Relaxed);
+        (now_ms < open_until).then(|| (open_until - now_ms).div_ceil(1000))
+    }
+
+    fn record_failure(&self, now_ms: u64) {
+        if self.threshold == 0 {
+            return;
+        }
+
+        // Falhas muito espaçadas não contam como sequência
+        let last = self.last_failure_ms.swap(now_ms, Ordering::Relaxed);
+        let failures = if now_ms.saturating_sub(last) > self.window_ms {
+            self.failures.store(1, Ordering::Relaxed);
+            1
+        } else {
+            self.failures.fetch_add(1, Ordering::Relaxed) + 1
+        };
+
+        if failures >= self.threshold {
+            self.failures.store(0, Ordering::Relaxed);
+            self.open_until_ms.store(now_ms + self.cooldown_ms, Ordering::Relaxed);
+            warn!(
+                "Cassandra circuit opened after {} consecutive failures, retrying in {} ms",
+                failures, self.cooldown_ms
+            );
+        }
+    }
+
+    fn now_ms(&self) -> u64 {
+        // +1 para que o instante 0 nunca coincida com "circuito fechado"
+        self.started.elapsed().as_millis() as u64 + 1
+    }
+}
+
+/// Falhas que indicam um cluster sobrecarregado ou inacessível. Erros da própria
+/// query (sintaxe, schema, permissão) não abrem o circuito
+pub fn is_overload(e: &QueryError) -> bool {
+    match e {
+        QueryError::DbError(db_error, _) => matches!(
+            db_error,
+            DbError::Overloaded
+                | DbError::Unavailable { .. }
+                | DbError::ReadTimeout { .. }
+                | DbError::WriteTimeout { .. }
+                | DbError::ReadFailure { .. }
+                | DbError::WriteFailure { .. }
+                | DbError::IsBootstrapping
+                | DbError::ServerError
+                | DbError::RateLimitReached { .. }
+        ),
+        QueryError::IoError(_)
+        | QueryError::TimeoutError
+        | QueryError::RequestTimeout(_)
+        | QueryError::TooManyOrphanedStreamIds(_)
+        | QueryError::UnableToAllocStreamId => true,
+        _ => false,
+    }
+}
+
+/// Falhas passageiras, em que a mesma query pode dar certo logo em seguida.
+/// Erros da query e falhas de escrita nas réplicas não são repetidos
+fn is_retryable(e: &QueryError) -> bool {
+    match e {
+        QueryError::DbError(db_error, _) => matches!(
+            db_error,
+            DbError::Overloaded
+                | DbError::Unavailable { .. }
+                | DbError::ReadTimeout { .. }
+                | DbError::WriteTimeout { .. }
+                | DbError::IsBootstrapping
+        ),
+        QueryError::IoError(_) | QueryError::TimeoutError | QueryError::RequestTimeout(_) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker() -> CircuitBreaker {
+        CircuitBreaker::new(3, Duration::from_secs(10), Duration::from_secs(30))
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breaker = breaker();
+        breaker.record_failure(1_000);
+        breaker.record_failure(2_000);
+        assert_eq!(breaker.open_for(2_000), None);
+
+        breaker.record_failure(3_000);
+        assert_eq!(breaker.open_for(3_000), Some(30));
+        assert_eq!(breaker.open_for(32_500), Some(1));
+        assert_eq!(breaker.open_for(33_000), None);
+    }
+
+    #[test]
+    fn spaced_out_failures_do_not_open() {
+        let breaker = breaker();
+        breaker.record_failure(1_000);
+        breaker.record_failure(2_000);
+        breaker.record_failure(20_000);
+        assert_eq!(breaker.open_for(20_000), None);
+    }
+
+    #[test]
+    fn retries_only_transient_errors() {
+        assert!(is_retryable(&QueryError::TimeoutError));
+        assert!(is_retryable(&QueryError::DbError(DbError::Overloaded, String::new())));
+        assert!(!is_retryable(&QueryError::DbError(DbError::SyntaxError, String::new())));
+        assert!(!is_retryable(&QueryError::DbError(DbError::ServerError, String::new())));
+    }
+
+    #[test]
+    fn zero_threshold_disables() {
+        let breaker = CircuitBreaker::new(0, Duration::from_secs(10), Duration::from_secs(30));
+        for now in 1..100 {
+            breaker.record_failure(now);
+        }
+        assert_eq!(breaker.open_for(100), None);
+    }
+}
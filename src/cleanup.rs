@@ -0,0 +1,153 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::StreamExt;
+use rand::Rng;
+use redis::Script;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::{cache_key, click_limit, AppError, AppState};
+
+/// Chave da trava que garante uma única limpeza por vez entre as réplicas
+const LOCK_KEY: &str = "cleanup:lock";
+
+/// Libera a trava só se ela ainda pertencer a esta instância (também usada em `stampede`)
+pub const RELEASE_SCRIPT: &str = r"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+  return redis.call('DEL', KEYS[1])
+end
+return 0
+";
+
+/// Quantidades removidas em uma execução
+#[derive(Default)]
+struct CleanupReport {
+    click_counters: u64,
+    dedup_entries: u64,
+}
+
+/// Executa a limpeza a cada `interval`, em segundo plano, até a task ser abortada
+pub fn spawn(state: Arc<AppState>, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // A primeira volta do `interval` é imediata; a limpeza espera um intervalo completo
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            run_once(&state, interval).await;
+        }
+    })
+}
+
+async fn run_once(state: &AppState, interval: Duration) {
+    let token = format!("{:016x}", rand::thread_rng().gen::<u64>());
+    let mut redis_conn = state.redis.get();
+
+    // A trava expira sozinha caso esta instância caia no meio da limpeza
+    let acquired: redis::RedisResult<Option<String>> = redis::cmd("SET")
+        .arg(LOCK_KEY)
+        .arg(&token)
+        .arg("NX")
+        .arg("EX")
+        .arg(interval.as_secs().max(60))
+        .query_async(&mut redis_conn)
+        .await;
+    match acquired {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            info!("Cleanup skipped: another instance holds the lock");
+            return;
+        }
+        Err(e) => {
+            warn!("Cleanup skipped: Redis lock error: {}", e);
+            return;
+        }
+    }
+
+    match purge_orphans(state).await {
+        Ok(report) => info!(
+            click_counters = report.click_counters,
+            dedup_entries = report.dedup_entries,
+            "Cleanup removed {} orphaned entries",
+            report.click_counters + report.dedup_entries
+        ),
+        Err(e) => error!("Cleanup failed: {}", e),
+    }
+
+    let released: redis::RedisResult<i64> = Script::new(RELEASE_SCRIPT)
+        .key(LOCK_KEY)
+        .arg(&token)
+        .invoke_async(&mut redis_conn)
+        .await;
+    if let Err(e) = released {
+        warn!("Cleanup lock release error: {}", e);
+    }
+}
+
+/// Remove os dados de links que já não existem em `urls` (expirados ou removidos):
+/// contadores de cliques, que não aceitam TTL, entradas do índice `url_by_long` e
+/// as chaves do Redis associadas
+async fn purge_orphans(state: &AppState) -> Result<CleanupReport, AppError> {
+    let mut report = CleanupReport::default();
+    let mut redis_conn = state.redis.get();
+
+    // 1. Contadores de cliques
+    let mut counters = state
+        .cassandra_breaker
+        .run(state.cassandra.execute_iter(state.statements.select_click_codes.clone(), &[]))
+        .await?
+        .into_typed::<(String,)>();
+    while let Some(row) = counters.next().await {
+        let (short_url,) = row.map_err(|e| AppError::Internal(format!("url_clicks row: {}", e)))?;
+        if link_exists(state, &short_url).await? {
+            continue;
+        }
+
+        state
+            .cassandra_breaker
+            .run(state.cassandra.execute(&state.statements.delete_clicks, (short_url.clone(),)))
+            .await?;
+        let cache_result: redis::RedisResult<()> = redis::AsyncCommands::del(
+            &mut redis_conn,
+            &[cache_key(&short_url), click_limit::counter_key(&short_url)],
+        )
+        .await;
+        if let Err(e) = cache_result {
+            warn!("Redis cache error: {}", e);
+        }
+        report.click_counters += 1;
+    }
+
+    // 2. Índice reverso da deduplicação
+    let mut dedup_entries = state
+        .cassandra_breaker
+        .run(state.cassandra.execute_iter(state.statements.select_all_url_by_long.clone(), &[]))
+        .await?
+        .into_typed::<(String, Option<String>)>();
+    while let Some(row) = dedup_entries.next().await {
+        let (long_hash, short_url) = row.map_err(|e| AppError::Internal(format!("url_by_long row: {}", e)))?;
+        if let Some(short_url) = &short_url {
+            if link_exists(state, short_url).await? {
+                continue;
+            }
+        }
+
+        state
+            .cassandra_breaker
+            .run(state.cassandra.execute(&state.statements.delete_url_by_long, (long_hash,)))
+            .await?;
+        report.dedup_entries += 1;
+    }
+
+    Ok(report)
+}
+
+async fn link_exists(state: &AppState, short_url: &str) -> Result<bool, AppError> {
+    Ok(state
+        .cassandra_breaker
+        .run(state.cassandra.execute(&state.statements.exists_url, (short_url.to_string(),)))
+        .await?
+        .rows
+        .is_some_and(|rows| !rows.is_empty()))
+}
@@ -0,0 +1,170 @@
+use axum::{
+    extract::{rejection::QueryRejection, Extension, Path, Query},
+    Json,
+};
+use chrono::{Days, NaiveDate, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::{stored_code, to_cql_timestamp, AppError, AppState};
+
+/// Dias devolvidos sem `from`
+const DEFAULT_RANGE_DAYS: u64 = 30;
+/// Maior intervalo aceito em uma consulta (uma partição por dia)
+const MAX_RANGE_DAYS: u64 = 366;
+
+/// Grava o clique em `click_events` em segundo plano, como `record_click`.
+///
+/// Com `CLICK_EVENTS_SAMPLE` abaixo de 1 só uma fração dos cliques é gravada, e
+/// cada evento leva o peso `1 / taxa` para que as somas por dia estimem o total
+pub fn record(state: &Arc<AppState>, short_url: &str, variant: Option<&str>, country: Option<&str>) {
+    let rate = state.config.click_events_sample;
+    if rate <= 0.0 || (rate < 1.0 && !rand::thread_rng().gen_bool(rate)) {
+        return;
+    }
+
+    let now = Utc::now();
+    let ttl = state.config.click_events_ttl_secs.min(i32::MAX as u64) as i32;
+    let values = (
+        short_url.to_string(),
+        now.date_naive(),
+        to_cql_timestamp(now),
+        1.0 / rate,
+        variant.map(str::to_string),
+        country.map(str::to_string),
+        ttl,
+    );
+    let state = Arc::clone(state);
+    let background = state.background.clone();
+    background.spawn(async move {
+        if let Err(e) = state
+            .cassandra_breaker
+            .run(state.cassandra.execute(&state.statements.insert_click_event, values))
+            .await
+        {
+            error!("Cassandra click event error: {}", e);
+        }
+    });
+}
+
+#[derive(Deserialize)]
+pub struct ClicksParams {
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+}
+
+#[derive(Serialize)]
+pub struct DailyClicks {
+    day: NaiveDate,
+    /// Cliques do dia (estimados a partir da amostra com `CLICK_EVENTS_SAMPLE` < 1)
+    clicks: u64,
+}
+
+#[derive(Serialize)]
+pub struct ClicksHistory {
+    short_url: String,
+    from: NaiveDate,
+    to: NaiveDate,
+    /// Um item por dia do intervalo, inclusive os dias sem cliques
+    days: Vec<DailyClicks>,
+}
+
+// GET /api/urls/:short_url/clicks?from=&to=
+pub async fn click_history(
+    Path(short): Path<String>,
+    params: Result<Query<ClicksParams>, QueryRejection>,
+    Extension(state): Extension<Arc<AppState>>,
+) -> Result<Json<ClicksHistory>, AppError> {
+    let Query(params) = params.map_err(|e| AppError::BadRequest(e.body_text()))?;
+    let short = stored_code(&state, short).await?;
+    let (from, to) =
+        day_range(params.from, params.to, Utc::now().date_naive()).map_err(AppError::BadRequest)?;
+
+    // Uma partição por (código, dia): as somas de cada dia são lidas em paralelo
+    let days: Vec<NaiveDate> = from.iter_days().take_while(|day| *day <= to).collect();
+    let sums = days.iter().map(|day| {
+        state
+            .cassandra_breaker
+            .run(state.cassandra.execute(&state.statements.sum_click_events, (short.clone(), *day)))
+    });
+    let mut history = Vec::with_capacity(days.len());
+    for (day, result) in days.iter().zip(futures::future::try_join_all(sums).await?) {
+        let clicks = result
+            .maybe_first_row_typed::<(Option<f64>,)>()
+            .map_err(|e| AppError::Internal(format!("click_events row: {}", e)))?
+            .and_then(|(sum,)| sum)
+            .unwrap_or_default();
+        history.push(DailyClicks {
+            day: *day,
+            clicks: clicks.round() as u64,
+        });
+    }
+
+    Ok(Json(ClicksHistory {
+        short_url: short,
+        from,
+        to,
+        days: history,
+    }))
+}
+
+/// Intervalo de dias da consulta: até hoje e nos últimos 30 dias por padrão, no
+/// máximo `MAX_RANGE_DAYS` dias
+fn day_range(
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    today: NaiveDate,
+) -> Result<(NaiveDate, NaiveDate), String> {
+    let to = to.unwrap_or(today);
+    let from = from.unwrap_or_else(|| to - Days::new(DEFAULT_RANGE_DAYS - 1));
+    if from > to {
+        return Err("from must not be after to".to_string());
+    }
+    if (to - from).num_days() as u64 >= MAX_RANGE_DAYS {
+        return Err(format!("the range can span at most {} days", MAX_RANGE_DAYS));
+    }
+    Ok((from, to))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn defaults_to_the_last_30_days() {
+        let today = date("2025-03-10");
+        assert_eq!(day_range(None, None, today).unwrap(), (date("2025-02-09"), today));
+        assert_eq!(
+            day_range(Some(date("2025-03-01")), Some(date("2025-03-01")), today).unwrap(),
+            (date("2025-03-01"), date("2025-03-01"))
+        );
+    }
+
+    #[test]
+    fn rejects_inverted_or_long_ranges() {
+        let today = date("2025-03-10");
+        assert!(day_range(Some(date("2025-03-11")), None, today).is_err());
+        assert!(day_range(Some(date("2024-01-01")), None, today).is_err());
+        assert!(day_range(Some(date("2024-03-10")), Some(date("2025-03-09")), today).is_ok());
+    }
+
+    #[test]
+    fn response_schemas_match_structs() {
+        use crate::openapi::schema_fields::assert_response;
+        let today = date("2025-03-10");
+        assert_response("ClicksHistory/properties/days/items", &DailyClicks { day: today, clicks: 0 });
+        let history = ClicksHistory {
+            short_url: String::new(),
+            from: today,
+            to: today,
+            days: Vec::new(),
+        };
+        assert_response("ClicksHistory", &history);
+    }
+}
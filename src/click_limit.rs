@@ -0,0 +1,40 @@
+use redis::Script;
+
+use crate::redis_pool::RedisConnection;
+
+/// Incrementa o contador de usos e, no primeiro uso, define a expiração.
+///
+/// O `INCR` já é atômico; o script só garante que contador e `EXPIRE` sejam
+/// aplicados juntos, sem deixar um contador eterno se a conexão cair no meio.
+const CONSUME_SCRIPT: &str = r"
+local uses = redis.call('INCR', KEYS[1])
+if uses == 1 and tonumber(ARGV[1]) > 0 then
+  redis.call('EXPIRE', KEYS[1], ARGV[1])
+end
+return uses
+";
+
+/// Por quanto tempo o contador de um link esgotado ainda é mantido (segundos)
+pub const EXHAUSTED_COUNTER_TTL_SECS: i64 = 60;
+
+/// Chave do contador de usos de um link com `max_clicks`
+pub fn counter_key(short_url: &str) -> String {
+    format!("uses:{}", short_url)
+}
+
+/// Registra um uso e retorna quantos usos o link já teve, incluindo este.
+///
+/// Requisições simultâneas recebem valores distintos, então no máximo
+/// `max_clicks` delas ficam dentro do limite. O contador expira junto com o
+/// link (`link_ttl_secs`); links sem expiração mantêm o contador até serem removidos.
+pub async fn consume(
+    redis_conn: &mut RedisConnection,
+    short_url: &str,
+    link_ttl_secs: Option<u64>,
+) -> redis::RedisResult<u64> {
+    Script::new(CONSUME_SCRIPT)
+        .key(counter_key(short_url))
+        .arg(link_ttl_secs.unwrap_or(0))
+        .invoke_async(redis_conn)
+        .await
+}
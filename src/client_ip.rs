@@ -0,0 +1,113 @@
+use axum::http::HeaderMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// Contexto do `blake3::derive_key` da chave dos IPs anonimizados (`LOG_CLIENT_IP=hash`)
+const IP_HASH_CONTEXT: &str = "url-shortener client ip v1";
+
+/// IP do cliente: o último endereço de `X-Forwarded-For` quando o serviço roda
+/// atrás de um proxy confiável, ou o endereço da conexão TCP. O último é o que o
+/// proxy acrescentou; os anteriores vêm do próprio cliente e podem ser inventados
+pub fn client_ip(headers: &HeaderMap, peer: SocketAddr, trust_forwarded_for: bool) -> IpAddr {
+    if trust_forwarded_for {
+        let forwarded = headers
+            .get("x-forwarded-for")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|ip| ip.trim().parse().ok());
+        if let Some(ip) = forwarded {
+            return ip;
+        }
+    }
+    peer.ip()
+}
+
+/// Como o IP do cliente aparece no log dos redirecionamentos (`LOG_CLIENT_IP`)
+pub enum IpLogging {
+    /// Não registra o IP (padrão)
+    Off,
+    /// Só a rede: `/24` no IPv4 e `/48` no IPv6
+    Truncate,
+    /// Hash com chave derivada da `SECRET_KEY`: identifica o mesmo cliente sem revelar o IP
+    Hash { key: [u8; 32] },
+    /// IP completo (dado pessoal: avalie a LGPD/GDPR antes de ativar)
+    Full,
+}
+
+impl IpLogging {
+    pub fn parse(value: &str, secret_key: &str) -> Result<Self, String> {
+        match value.trim() {
+            "off" => Ok(Self::Off),
+            "truncate" => Ok(Self::Truncate),
+            "hash" => Ok(Self::Hash {
+                key: blake3::derive_key(IP_HASH_CONTEXT, secret_key.as_bytes()),
+            }),
+            "full" => Ok(Self::Full),
+            other => Err(format!("Invalid LOG_CLIENT_IP '{}': use off, truncate, hash or full", other)),
+        }
+    }
+
+    /// O IP como deve ir para o log, `None` se não deve ser registrado
+    pub fn render(&self, ip: IpAddr) -> Option<String> {
+        match self {
+            Self::Off => None,
+            Self::Truncate => Some(match ip {
+                IpAddr::V4(ip) => {
+                    let [a, b, c, _] = ip.octets();
+                    format!("{}/24", Ipv4Addr::new(a, b, c, 0))
+                }
+                IpAddr::V6(ip) => {
+                    let [a, b, c, ..] = ip.segments();
+                    format!("{}/48", Ipv6Addr::new(a, b, c, 0, 0, 0, 0, 0))
+                }
+            }),
+            Self::Hash { key } => {
+                Some(blake3::keyed_hash(key, ip.to_string().as_bytes()).to_hex()[..16].to_string())
+            }
+            Self::Full => Some(ip.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignores_forwarded_for_unless_trusted() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "198.51.100.1, 203.0.113.7".parse().unwrap());
+        let peer: SocketAddr = "10.0.0.1:5000".parse().unwrap();
+
+        assert_eq!(client_ip(&headers, peer, false), peer.ip());
+        assert_eq!(client_ip(&headers, peer, true), "203.0.113.7".parse::<IpAddr>().unwrap());
+        assert_eq!(client_ip(&HeaderMap::new(), peer, true), peer.ip());
+    }
+
+    #[test]
+    fn spoofed_forwarded_for_entries_do_not_change_the_ip() {
+        let peer: SocketAddr = "10.0.0.1:5000".parse().unwrap();
+        for spoofed in ["198.51.100.1", "198.51.100.2, 192.0.2.9", "not-an-ip"] {
+            let mut headers = HeaderMap::new();
+            let value = format!("{}, 203.0.113.7", spoofed);
+            headers.insert("x-forwarded-for", value.parse().unwrap());
+            assert_eq!(client_ip(&headers, peer, true), "203.0.113.7".parse::<IpAddr>().unwrap());
+        }
+    }
+
+    #[test]
+    fn anonymizes_logged_ips() {
+        let v4: IpAddr = "203.0.113.7".parse().unwrap();
+        let v6: IpAddr = "2001:db8:85a3::8a2e:370:7334".parse().unwrap();
+        assert_eq!(IpLogging::Off.render(v4), None);
+        assert_eq!(IpLogging::Truncate.render(v4).unwrap(), "203.0.113.0/24");
+        assert_eq!(IpLogging::Truncate.render(v6).unwrap(), "2001:db8:85a3::/48");
+        assert_eq!(IpLogging::Full.render(v4).unwrap(), "203.0.113.7");
+
+        let hash = IpLogging::parse("hash", "s3cr3t").unwrap();
+        let hashed = hash.render(v4).unwrap();
+        assert_eq!(hashed.len(), 16);
+        assert_eq!(hash.render(v4).unwrap(), hashed);
+        assert_ne!(IpLogging::parse("hash", "other").unwrap().render(v4).unwrap(), hashed);
+        assert!(IpLogging::parse("raw", "s3cr3t").is_err());
+    }
+}
@@ -0,0 +1,749 @@
+use std::{net::SocketAddr, num::NonZeroUsize, str::FromStr, time::Duration};
+
+use axum::http::HeaderName;
+use scylla::statement::Consistency;
+use scylla::transport::session::PoolSize;
+
+use crate::auth::ApiKeys;
+use crate::bots::{BotDetector, DEFAULT_BOT_USER_AGENTS};
+use crate::client_ip::IpLogging;
+use crate::cors::CorsOrigins;
+use crate::geo::DEFAULT_COUNTRY_HEADER;
+use crate::idempotency;
+use crate::ids::{IdStrategy, MAX_NODE_ID};
+use crate::quota::LinkQuotas;
+use crate::shortcode::{
+    alphabet_seed, case_folded_alphabet, fixed_width_range, validate_alphabet, AlphabetVersion, CodeMode,
+    BASE62_ALPHABET, DEFAULT_ID_OFFSET, SIGNATURE_LEN,
+};
+use crate::validation::{AllowedSchemes, DomainBlocklist};
+
+/// `robots.txt` padrão: a raiz pode ser indexada, os short links não
+const DEFAULT_ROBOTS_TXT: &str = "User-agent: *\nAllow: /$\nDisallow: /\n";
+
+/// `SECRET_KEY` usada quando a variável não é definida (só fora de produção)
+const DEFAULT_SECRET_KEY: &str = "default_secret";
+
+/// Configuração da aplicação, lida das variáveis de ambiente na inicialização
+pub struct Config {
+    pub redis_url: String,
+    pub cassandra_nodes: Vec<String>,
+    pub cassandra_keyspace: String,
+    /// Chave que embaralha o alfabeto dos códigos (`SECRET_KEY`)
+    pub secret_key: String,
+    /// Semente do embaralhamento, derivada da `SECRET_KEY` conforme `ALPHABET_VERSION`
+    pub code_seed: [u8; 32],
+    /// Códigos simples ou assinados (`CODE_MODE`)
+    pub code_mode: CodeMode,
+    /// Usuário e senha do `PasswordAuthenticator`, se configurados
+    pub cassandra_credentials: Option<(String, String)>,
+    /// Mapa de replicação em CQL, ex: `{'class': 'SimpleStrategy', 'replication_factor': 1}`
+    pub replication: String,
+    /// Consistência das leituras e escritas de links
+    pub consistency: Consistency,
+    /// Consistência dos contadores de cliques, que toleram valores menos precisos
+    pub analytics_consistency: Consistency,
+    /// Datacenter local do Cassandra (`LOCAL_DC`): as queries preferem as réplicas dele
+    pub local_dc: Option<String>,
+    pub bind_addr: SocketAddr,
+    pub startup_retries: u32,
+    pub cache_ttl_secs: u64,
+    /// TTL (segundos) das entradas de 404 no cache; 0 desativa o cache negativo
+    pub negative_cache_ttl_secs: u64,
+    /// Por quanto tempo um código removido responde 410 antes de voltar a 404 (segundos; 0 = sempre)
+    pub tombstone_ttl_secs: u64,
+    /// Fração dos cliques gravada em `click_events` (`CLICK_EVENTS_SAMPLE`, 0 desativa)
+    pub click_events_sample: f64,
+    /// Por quanto tempo os eventos de clique são mantidos (segundos, 0 = para sempre)
+    pub click_events_ttl_secs: u64,
+    /// Conexões do driver por shard (Scylla) ou por nó (`CASSANDRA_POOL_SIZE`)
+    pub cassandra_pool_size: PoolSize,
+    /// Conexões multiplexadas ao Redis (`REDIS_POOL_SIZE`)
+    pub redis_pool_size: usize,
+    /// Tempo máximo da conexão e de cada comando ao Redis (`REDIS_TIMEOUT_MS`)
+    pub redis_timeout: Duration,
+    /// Tempo máximo de cada query ao Cassandra (`QUERY_TIMEOUT_MS`)
+    pub query_timeout: Duration,
+    /// Falhas seguidas do Cassandra que abrem o circuit breaker; 0 desativa
+    pub circuit_failure_threshold: u32,
+    pub circuit_window_secs: u64,
+    pub circuit_cooldown_secs: u64,
+    /// Intervalo da limpeza de dados órfãos (`CLEANUP_INTERVAL_SECS`); 0 desativa
+    pub cleanup_interval_secs: u64,
+    /// Carrega os links mais clicados no Redis ao iniciar (`CACHE_WARM`)
+    pub cache_warm: bool,
+    /// Quantos links o aquecimento carrega (`CACHE_WARM_LIMIT`)
+    pub cache_warm_limit: usize,
+    pub max_url_len: usize,
+    pub id_strategy: IdStrategy,
+    /// Tamanho mínimo dos códigos gerados (aliases não são afetados)
+    pub min_code_len: usize,
+    /// Somado a cada ID antes da codificação (`ID_OFFSET`); só deve aumentar
+    pub id_offset: u64,
+    /// Largura fixa dos códigos gerados (`CODE_WIDTH`), `None` sem largura fixa
+    pub code_width: Option<u32>,
+    /// Com `CODE_WIDTH`, primeiro ID deslocado que já teria um dígito a mais
+    pub id_limit: Option<u64>,
+    /// Alfabeto dos códigos gerados, antes do embaralhamento (`CODE_ALPHABET`)
+    pub code_alphabet: String,
+    /// Códigos sem diferenciar maiúsculas (`CASE_INSENSITIVE`)
+    pub case_insensitive: bool,
+    pub blocked_domains: DomainBlocklist,
+    /// Esquemas aceitos nas URLs de destino (`ALLOWED_SCHEMES`)
+    pub allowed_schemes: AllowedSchemes,
+    pub batch_max_urls: usize,
+    /// Tamanho máximo do corpo em `POST /shorten` (bytes)
+    pub max_body_bytes: usize,
+    /// Tamanho máximo do corpo em `POST /shorten/batch` (bytes)
+    pub batch_max_body_bytes: usize,
+    pub shorten_rate_limit: u64,
+    pub rate_limit_prefix: String,
+    pub trust_forwarded_for: bool,
+    /// IP do cliente no log dos redirecionamentos (`LOG_CLIENT_IP`, desligado por padrão)
+    pub log_client_ip: IpLogging,
+    /// Registra o `Referer` (sem query string) no log dos redirecionamentos (`LOG_REFERER`)
+    pub log_referer: bool,
+    pub api_keys: ApiKeys,
+    /// Máximo de links ativos por API key (`LINK_QUOTA`, `LINK_QUOTAS`)
+    pub link_quotas: LinkQuotas,
+    /// Por quanto tempo uma resposta fica guardada por `Idempotency-Key` (segundos)
+    pub idempotency_ttl_secs: u64,
+    /// Chave do hash dos corpos guardados por `Idempotency-Key`, derivada da `SECRET_KEY`
+    pub idempotency_key: [u8; 32],
+    pub cors_origins: CorsOrigins,
+    pub redirect_permanent: bool,
+    /// User-Agents que não contam como clique (`BOT_USER_AGENTS`)
+    pub bots: BotDetector,
+    /// `max-age` (segundos) dos redirecionamentos permanentes; 0 desativa o cache
+    pub redirect_cache_max_age: u64,
+    /// Inicia em modo somente leitura (`READ_ONLY`)
+    pub read_only: bool,
+    /// Não registra as rotas de criação (`DISABLE_SHORTEN`)
+    pub disable_shorten: bool,
+    /// Header com o país do cliente, para os destinos por país (`GEO_COUNTRY_HEADER`)
+    pub geo_country_header: HeaderName,
+    /// Parâmetros adicionados a todo destino que ainda não os define (`DEFAULT_UTM`)
+    pub default_query_params: Vec<(String, String)>,
+    pub html_not_found: bool,
+    /// Comprime as respostas (gzip ou brotli) conforme o `Accept-Encoding` (`COMPRESSION`)
+    pub compression: bool,
+    /// Serve o Swagger UI em `/swagger` (`SWAGGER_UI`)
+    pub swagger_ui: bool,
+    /// Requisições acima deste tempo (ms) geram um log com o tempo por backend; 0 desativa
+    pub slow_request_ms: u64,
+    /// Esquema + host em que os short links são servidos publicamente, sem `/` final
+    pub public_base_url: String,
+    /// Destino de `GET /` (`ROOT_REDIRECT`); sem ele, a raiz serve uma página própria
+    pub root_redirect: Option<String>,
+    /// Redirecionamentos seguidos em cadeias de short links próprios (`MAX_CHAIN_HOPS`, 1 = nenhum)
+    pub max_chain_hops: u32,
+    /// Conteúdo de `GET /robots.txt` (`ROBOTS_TXT`)
+    pub robots_txt: String,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Self, String> {
+        let redis_url = redis_url(
+            &env_or("REDIS_URL", "redis://redis:6379/"),
+            std::env::var("REDIS_USERNAME").ok().as_deref(),
+            std::env::var("REDIS_PASSWORD").ok().as_deref(),
+        )?;
+
+        // Lista de nós separada por vírgula, ex: "scylla-1:9042,scylla-2:9042"
+        let cassandra_nodes: Vec<String> = env_or("CASSANDRA_NODES", "cassandra:9042")
+            .split(',')
+            .map(|node| node.trim().to_string())
+            .filter(|node| !node.is_empty())
+            .collect();
+        if cassandra_nodes.is_empty() {
+            return Err("CASSANDRA_NODES must list at least one node".to_string());
+        }
+
+        let cassandra_credentials = match (
+            std::env::var("CASSANDRA_USER").ok().filter(|v| !v.is_empty()),
+            std::env::var("CASSANDRA_PASSWORD").ok().filter(|v| !v.is_empty()),
+        ) {
+            (Some(user), Some(password)) => Some((user, password)),
+            (None, None) => None,
+            _ => return Err("CASSANDRA_USER and CASSANDRA_PASSWORD must be set together".to_string()),
+        };
+
+        // O driver só fala TLS com a feature `ssl`, que depende do OpenSSL e não faz
+        // parte deste build: melhor falhar na inicialização do que conectar sem TLS
+        if std::env::var("CASSANDRA_CA_CERT").is_ok_and(|path| !path.is_empty()) {
+            return Err(
+                "CASSANDRA_CA_CERT is set, but this build has no TLS support for Cassandra; \
+                 terminate TLS in a local proxy (e.g. stunnel) and point CASSANDRA_NODES at it"
+                    .to_string(),
+            );
+        }
+
+        let secret_key = secret_key(
+            &env_or("APP_ENV", "development"),
+            std::env::var("SECRET_KEY").ok().as_deref(),
+        )?;
+        // 0 mantém os códigos das instalações existentes; 1 separa a semente de outros usos da chave
+        let alphabet_version = match env_or("ALPHABET_VERSION", "0").trim() {
+            "0" => AlphabetVersion::Legacy,
+            "1" => AlphabetVersion::V1,
+            other => return Err(format!("Invalid ALPHABET_VERSION '{}': use 0 or 1", other)),
+        };
+        let code_seed = alphabet_seed(&secret_key, alphabet_version);
+        let code_mode = match env_or("CODE_MODE", "plain").trim() {
+            "plain" => CodeMode::Plain,
+            "signed" => CodeMode::signed(&secret_key),
+            other => return Err(format!("Invalid CODE_MODE '{}': use plain or signed", other)),
+        };
+
+        let cassandra_keyspace = env_or("CASSANDRA_KEYSPACE", "shortener");
+        if !is_valid_keyspace(&cassandra_keyspace) {
+            return Err(format!(
+                "Invalid CASSANDRA_KEYSPACE '{}': use up to 48 letters, digits or '_'",
+                cassandra_keyspace
+            ));
+        }
+
+        // REPLICATION sobrescreve o mapa inteiro; caso contrário é montado a partir de
+        // REPLICATION_STRATEGY e REPLICATION_FACTOR
+        let replication = match std::env::var("REPLICATION") {
+            Ok(raw) if !raw.trim().is_empty() => raw.trim().to_string(),
+            _ => replication_map(
+                &env_or("REPLICATION_STRATEGY", "SimpleStrategy"),
+                &env_or("REPLICATION_FACTOR", "1"),
+            )?,
+        };
+
+        let consistency = parse_consistency(
+            "CASSANDRA_CONSISTENCY",
+            &env_or("CASSANDRA_CONSISTENCY", "LOCAL_QUORUM"),
+        )?;
+        let analytics_consistency = match std::env::var("ANALYTICS_CONSISTENCY") {
+            Ok(value) => parse_consistency("ANALYTICS_CONSISTENCY", &value)?,
+            Err(_) => consistency,
+        };
+        let local_dc = std::env::var("LOCAL_DC")
+            .ok()
+            .map(|dc| dc.trim().to_string())
+            .filter(|dc| !dc.is_empty());
+
+        let bind_host = env_or("BIND_ADDR", "0.0.0.0");
+        let port: u16 = env_parse("PORT", 3000)?;
+        let bind_addr = SocketAddr::from_str(&format!("{}:{}", bind_host, port))
+            .or_else(|_| SocketAddr::from_str(&format!("[{}]:{}", bind_host, port)))
+            .map_err(|e| format!("Invalid BIND_ADDR '{}': {}", bind_host, e))?;
+
+        let startup_retries = env_parse("STARTUP_RETRIES", 10)?;
+        let cache_ttl_secs = env_parse("CACHE_TTL_SECS", 3600)?;
+        let negative_cache_ttl_secs = env_parse("NEGATIVE_CACHE_TTL", 60)?;
+        let tombstone_ttl_secs = env_parse("TOMBSTONE_TTL", 7_776_000)?;
+        let click_events_sample: f64 = env_parse("CLICK_EVENTS_SAMPLE", 1.0)?;
+        if !(0.0..=1.0).contains(&click_events_sample) {
+            return Err("CLICK_EVENTS_SAMPLE must be between 0 and 1".to_string());
+        }
+        let click_events_ttl_secs = env_parse("CLICK_EVENTS_TTL", 7_776_000)?;
+        let cassandra_pool_size = parse_pool_size(&env_or("CASSANDRA_POOL_SIZE", "1"))?;
+        let redis_pool_size: usize = env_parse("REDIS_POOL_SIZE", 1)?;
+        if !(1..=64).contains(&redis_pool_size) {
+            return Err(format!("REDIS_POOL_SIZE must be between 1 and 64, got {}", redis_pool_size));
+        }
+        let redis_timeout_ms: u64 = env_parse("REDIS_TIMEOUT_MS", 500)?;
+        if redis_timeout_ms == 0 {
+            return Err("REDIS_TIMEOUT_MS must be greater than 0".to_string());
+        }
+        let redis_timeout = Duration::from_millis(redis_timeout_ms);
+        let query_timeout_ms: u64 = env_parse("QUERY_TIMEOUT_MS", 2000)?;
+        if query_timeout_ms == 0 {
+            return Err("QUERY_TIMEOUT_MS must be greater than 0".to_string());
+        }
+        let query_timeout = Duration::from_millis(query_timeout_ms);
+        let circuit_failure_threshold = env_parse("CIRCUIT_FAILURE_THRESHOLD", 5)?;
+        let circuit_window_secs = env_parse("CIRCUIT_WINDOW_SECS", 10)?;
+        let circuit_cooldown_secs = env_parse("CIRCUIT_COOLDOWN_SECS", 30)?;
+        let cleanup_interval_secs = env_parse("CLEANUP_INTERVAL_SECS", 3600)?;
+        let cache_warm = env_parse("CACHE_WARM", false)?;
+        let cache_warm_limit: usize = env_parse("CACHE_WARM_LIMIT", 1000)?;
+        if !(1..=100_000).contains(&cache_warm_limit) {
+            return Err(format!(
+                "CACHE_WARM_LIMIT must be between 1 and 100000, got {}",
+                cache_warm_limit
+            ));
+        }
+        let max_url_len = env_parse("MAX_URL_LEN", 2048)?;
+        let id_strategy = match env_or("ID_STRATEGY", "redis").trim() {
+            "redis" => IdStrategy::Redis,
+            "snowflake" => {
+                let node_id: u16 = env_parse("NODE_ID", 0)?;
+                if node_id > MAX_NODE_ID {
+                    return Err(format!("NODE_ID must be between 0 and {}, got {}", MAX_NODE_ID, node_id));
+                }
+                IdStrategy::Snowflake { node_id }
+            }
+            other => return Err(format!("Invalid ID_STRATEGY '{}': use redis or snowflake", other)),
+        };
+        let code_alphabet = env_or("CODE_ALPHABET", BASE62_ALPHABET).trim().to_string();
+        validate_alphabet(&code_alphabet).map_err(|e| format!("Invalid CODE_ALPHABET: {}", e))?;
+        // Sem diferenciar maiúsculas, o gerador só usa o alfabeto em minúsculas
+        let case_insensitive = env_parse("CASE_INSENSITIVE", false)?;
+        let code_alphabet = if case_insensitive {
+            let folded = case_folded_alphabet(&code_alphabet);
+            validate_alphabet(&folded)
+                .map_err(|e| format!("Invalid CODE_ALPHABET with CASE_INSENSITIVE: {}", e))?;
+            folded
+        } else {
+            code_alphabet
+        };
+        // Até 11: u64::MAX tem 11 dígitos em base62
+        let min_code_len = env_parse("MIN_CODE_LEN", 6)?;
+        if !(1..=11).contains(&min_code_len) {
+            return Err(format!("MIN_CODE_LEN must be between 1 and 11, got {}", min_code_len));
+        }
+        let id_offset = env_parse("ID_OFFSET", DEFAULT_ID_OFFSET)?;
+        // CODE_WIDTH calcula o deslocamento: o primeiro ID já tem `width` dígitos, e
+        // as criações falham quando o contador passaria para `width + 1`. No modo
+        // assinado a largura inclui a assinatura, então o ID fica com o restante
+        let code_width = Some(env_parse("CODE_WIDTH", 0u32)?).filter(|width| *width > 0);
+        let (min_code_len, id_offset, id_limit) = match code_width {
+            None => (min_code_len, id_offset, None),
+            Some(width) => {
+                if std::env::var("ID_OFFSET").is_ok() || std::env::var("MIN_CODE_LEN").is_ok() {
+                    return Err(
+                        "CODE_WIDTH sets the ID offset itself: unset ID_OFFSET and MIN_CODE_LEN".to_string()
+                    );
+                }
+                if matches!(id_strategy, IdStrategy::Snowflake { .. }) {
+                    return Err("CODE_WIDTH needs sequential IDs: use ID_STRATEGY=redis".to_string());
+                }
+                let signature_len = if code_mode.is_signed() { SIGNATURE_LEN as u32 } else { 0 };
+                let id_width = width.checked_sub(signature_len).filter(|w| *w > 0).ok_or_else(|| {
+                    format!(
+                        "CODE_WIDTH must be above {} with CODE_MODE=signed (signature characters)",
+                        SIGNATURE_LEN
+                    )
+                })?;
+                let base = code_alphabet.chars().count() as u64;
+                let (offset, limit) = fixed_width_range(base, id_width).ok_or_else(|| {
+                    format!(
+                        "CODE_WIDTH {} does not fit in 64-bit IDs with a {}-character alphabet",
+                        width, base
+                    )
+                })?;
+                (id_width as usize, offset, Some(limit))
+            }
+        };
+        let blocked_domains = DomainBlocklist::parse(&env_or("BLOCKED_DOMAINS", ""));
+        let allowed_schemes = AllowedSchemes::parse(&env_or("ALLOWED_SCHEMES", "http,https"))?;
+        let batch_max_urls = env_parse("BATCH_MAX_URLS", 500)?;
+        let max_body_bytes = env_parse("MAX_BODY_BYTES", 16 * 1024)?;
+        let batch_max_body_bytes = env_parse("BATCH_MAX_BODY_BYTES", 1024 * 1024)?;
+        // Criações por IP por minuto (0 desativa)
+        let shorten_rate_limit = env_parse("SHORTEN_RATE_LIMIT", 60)?;
+        let rate_limit_prefix = env_or("RATE_LIMIT_PREFIX", "rl");
+        // Só confie no X-Forwarded-For atrás de um proxy que acrescenta o IP da conexão ao header
+        let trust_forwarded_for = env_parse("TRUST_X_FORWARDED_FOR", false)?;
+        // IPs são dados pessoais: nada é registrado sem LOG_CLIENT_IP
+        let log_client_ip = IpLogging::parse(&env_or("LOG_CLIENT_IP", "off"), &secret_key)?;
+        let log_referer = env_parse("LOG_REFERER", false)?;
+        let api_keys = ApiKeys::parse(&env_or("API_KEYS", ""));
+        let link_quotas = LinkQuotas::parse(env_parse("LINK_QUOTA", 0)?, &env_or("LINK_QUOTAS", ""))?;
+        let idempotency_ttl_secs = env_parse("IDEMPOTENCY_TTL", 86_400)?;
+        if idempotency_ttl_secs == 0 {
+            return Err("IDEMPOTENCY_TTL must be at least 1 second".to_string());
+        }
+        let idempotency_key = idempotency::fingerprint_key(&secret_key);
+        // `*` por padrão (desenvolvimento); em produção, liste as origens do front-end
+        let cors_origins = CorsOrigins::parse(&env_or("CORS_ALLOWED_ORIGINS", "*"));
+        let redirect_permanent = env_parse("REDIRECT_PERMANENT", false)?;
+        let bots = BotDetector::parse(&env_or("BOT_USER_AGENTS", DEFAULT_BOT_USER_AGENTS));
+        let redirect_cache_max_age = env_parse("REDIRECT_CACHE_MAX_AGE", 3600)?;
+        let read_only = env_parse("READ_ONLY", false)?;
+        let disable_shorten = env_parse("DISABLE_SHORTEN", false)?;
+        let geo_country_header = env_or("GEO_COUNTRY_HEADER", DEFAULT_COUNTRY_HEADER);
+        let geo_country_header = HeaderName::from_bytes(geo_country_header.trim().as_bytes())
+            .map_err(|_| format!("Invalid GEO_COUNTRY_HEADER '{}'", geo_country_header))?;
+        let default_query_params = default_query_params(&env_or("DEFAULT_UTM", ""))?;
+        // Página HTML de 404 para navegadores (`Accept: text/html`); JSON caso contrário
+        let html_not_found = env_parse("HTML_NOT_FOUND", false)?;
+        // Desligue quando o proxy na frente da API já comprime as respostas
+        let compression = env_parse("COMPRESSION", true)?;
+        // A página carrega o Swagger UI de um CDN na origem do serviço: só com opt-in
+        let swagger_ui = env_parse("SWAGGER_UI", false)?;
+        let slow_request_ms = env_parse("SLOW_REQUEST_MS", 500)?;
+
+        let public_base_url = env_or("PUBLIC_BASE_URL", &format!("http://localhost:{}", port))
+            .trim()
+            .trim_end_matches('/')
+            .to_string();
+        match url::Url::parse(&public_base_url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") && url.has_host() => {}
+            _ => return Err(format!("Invalid PUBLIC_BASE_URL '{}'", public_base_url)),
+        }
+        let max_chain_hops = env_parse("MAX_CHAIN_HOPS", 1)?;
+        if !(1..=10).contains(&max_chain_hops) {
+            return Err(format!("MAX_CHAIN_HOPS must be between 1 and 10, got {}", max_chain_hops));
+        }
+        // Ex: o site institucional; vazio mantém a página padrão
+        let root_redirect = Some(env_or("ROOT_REDIRECT", "").trim().to_string())
+            .filter(|url| !url.is_empty());
+        if let Some(root_redirect) = &root_redirect {
+            match url::Url::parse(root_redirect) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") && url.has_host() => {}
+                _ => return Err(format!("Invalid ROOT_REDIRECT '{}'", root_redirect)),
+            }
+        }
+        let robots_txt = robots_txt(std::env::var("ROBOTS_TXT").ok().as_deref());
+
+        Ok(Config {
+            redis_url,
+            cassandra_nodes,
+            cassandra_keyspace,
+            secret_key,
+            code_seed,
+            code_mode,
+            cassandra_credentials,
+            replication,
+            consistency,
+            analytics_consistency,
+            local_dc,
+            bind_addr,
+            startup_retries,
+            cache_ttl_secs,
+            negative_cache_ttl_secs,
+            tombstone_ttl_secs,
+            click_events_sample,
+            click_events_ttl_secs,
+            cassandra_pool_size,
+            redis_pool_size,
+            redis_timeout,
+            query_timeout,
+            circuit_failure_threshold,
+            circuit_window_secs,
+            circuit_cooldown_secs,
+            cleanup_interval_secs,
+            cache_warm,
+            cache_warm_limit,
+            max_url_len,
+            id_strategy,
+            min_code_len,
+            id_offset,
+            code_width,
+            id_limit,
+            code_alphabet,
+            case_insensitive,
+            blocked_domains,
+            allowed_schemes,
+            batch_max_urls,
+            max_body_bytes,
+            batch_max_body_bytes,
+            shorten_rate_limit,
+            rate_limit_prefix,
+            trust_forwarded_for,
+            log_client_ip,
+            log_referer,
+            api_keys,
+            link_quotas,
+            idempotency_ttl_secs,
+            idempotency_key,
+            cors_origins,
+            redirect_permanent,
+            bots,
+            redirect_cache_max_age,
+            read_only,
+            disable_shorten,
+            geo_country_header,
+            default_query_params,
+            html_not_found,
+            compression,
+            swagger_ui,
+            slow_request_ms,
+            public_base_url,
+            root_redirect,
+            max_chain_hops,
+            robots_txt,
+        })
+    }
+
+    /// URL pública completa de um short link (`PUBLIC_BASE_URL` + código).
+    /// Única fonte para as respostas da API e os QR Codes
+    pub fn short_link(&self, short_url: &str) -> String {
+        format!("{}/{}", self.public_base_url, short_url)
+    }
+}
+
+/// Monta a URL do Redis, aplicando `REDIS_USERNAME`/`REDIS_PASSWORD` se definidas.
+///
+/// Credenciais também podem vir na própria URL (`redis://:senha@host:6379/`).
+/// `rediss://` exige uma feature de TLS do crate `redis` que não faz parte deste build.
+fn redis_url(raw: &str, username: Option<&str>, password: Option<&str>) -> Result<String, String> {
+    let mut url = url::Url::parse(raw.trim()).map_err(|e| format!("Invalid REDIS_URL: {}", e))?;
+    match url.scheme() {
+        "redis" => {}
+        "rediss" => {
+            return Err(
+                "REDIS_URL uses rediss://, but this build has no TLS support for Redis; \
+                 terminate TLS in a local proxy (e.g. stunnel) and use redis://"
+                    .to_string(),
+            )
+        }
+        other => return Err(format!("Invalid REDIS_URL: unsupported scheme '{}'", other)),
+    }
+
+    if let Some(username) = username.filter(|u| !u.is_empty()) {
+        url.set_username(username)
+            .map_err(|_| "Invalid REDIS_URL: cannot set username".to_string())?;
+    }
+    if let Some(password) = password.filter(|p| !p.is_empty()) {
+        url.set_password(Some(password))
+            .map_err(|_| "Invalid REDIS_URL: cannot set password".to_string())?;
+    }
+    Ok(url.into())
+}
+
+/// Resolve a `SECRET_KEY`. Com a chave padrão qualquer um reproduz o embaralhamento
+/// do alfabeto e enumera os códigos, então em produção (`APP_ENV=production`) ela é
+/// obrigatória; nos demais ambientes a chave padrão continua valendo
+fn secret_key(app_env: &str, value: Option<&str>) -> Result<String, String> {
+    let value = value.filter(|v| !v.is_empty());
+    if app_env.trim().eq_ignore_ascii_case("production")
+        && value.is_none_or(|v| v == DEFAULT_SECRET_KEY)
+    {
+        return Err("SECRET_KEY must be set to a non-default value when APP_ENV=production".to_string());
+    }
+    Ok(value.unwrap_or(DEFAULT_SECRET_KEY).to_string())
+}
+
+/// Conteúdo do `robots.txt`. Variáveis de ambiente com quebras de linha são
+/// incômodas, então `\n` literal no valor vira uma quebra de linha
+fn robots_txt(value: Option<&str>) -> String {
+    let mut robots = value
+        .filter(|v| !v.trim().is_empty())
+        .map(|v| v.replace("\\n", "\n"))
+        .unwrap_or_else(|| DEFAULT_ROBOTS_TXT.to_string());
+    if !robots.ends_with('\n') {
+        robots.push('\n');
+    }
+    robots
+}
+
+/// Nomes de keyspace do Cassandra: alfanuméricos e '_', até 48 caracteres
+fn is_valid_keyspace(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 48
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Monta o mapa de replicação em CQL.
+///
+/// `SimpleStrategy` aceita apenas um fator numérico. `NetworkTopologyStrategy` aceita um
+/// fator único ou fatores por datacenter no formato `dc1:3,dc2:2`.
+fn replication_map(strategy: &str, factor: &str) -> Result<String, String> {
+    let strategy = strategy.trim();
+    let factor = factor.trim();
+    let invalid = |e: String| format!("Invalid REPLICATION_FACTOR '{}': {}", factor, e);
+
+    match strategy {
+        "SimpleStrategy" => {
+            let rf: u32 = factor
+                .parse()
+                .map_err(|e: std::num::ParseIntError| invalid(e.to_string()))?;
+            Ok(format!(
+                "{{'class': 'SimpleStrategy', 'replication_factor': {}}}",
+                rf
+            ))
+        }
+        "NetworkTopologyStrategy" => {
+            if let Ok(rf) = factor.parse::<u32>() {
+                return Ok(format!(
+                    "{{'class': 'NetworkTopologyStrategy', 'replication_factor': {}}}",
+                    rf
+                ));
+            }
+            let mut map = String::from("{'class': 'NetworkTopologyStrategy'");
+            for entry in factor.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+                let (dc, rf) = entry
+                    .split_once(':')
+                    .ok_or_else(|| invalid(format!("expected 'dc:factor', got '{}'", entry)))?;
+                let dc = dc.trim();
+                if dc.is_empty()
+                    || !dc
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "_-.".contains(c))
+                {
+                    return Err(invalid(format!("invalid datacenter name '{}'", dc)));
+                }
+                let rf: u32 = rf
+                    .trim()
+                    .parse()
+                    .map_err(|e: std::num::ParseIntError| invalid(e.to_string()))?;
+                map.push_str(&format!(", '{}': {}", dc, rf));
+            }
+            map.push('}');
+            Ok(map)
+        }
+        other => Err(format!(
+            "Invalid REPLICATION_STRATEGY '{}': use SimpleStrategy or NetworkTopologyStrategy",
+            other
+        )),
+    }
+}
+
+/// Lê `CASSANDRA_POOL_SIZE`: `N` ou `per_shard:N` (conexões por shard, o
+/// recomendado para Scylla) ou `per_host:N` (conexões por nó)
+fn parse_pool_size(raw: &str) -> Result<PoolSize, String> {
+    let raw = raw.trim();
+    let (kind, count) = raw.split_once(':').unwrap_or(("per_shard", raw));
+    let count = count
+        .trim()
+        .parse::<NonZeroUsize>()
+        .map_err(|_| format!("Invalid CASSANDRA_POOL_SIZE '{}': expected a positive count", raw))?;
+    match kind.trim() {
+        "per_shard" => Ok(PoolSize::PerShard(count)),
+        "per_host" => Ok(PoolSize::PerHost(count)),
+        other => Err(format!(
+            "Invalid CASSANDRA_POOL_SIZE kind '{}': use per_shard or per_host",
+            other
+        )),
+    }
+}
+
+/// Lê `DEFAULT_UTM` no formato de query string, ex: `utm_source=shortlink&utm_medium=link`
+fn default_query_params(raw: &str) -> Result<Vec<(String, String)>, String> {
+    let raw = raw.trim().trim_start_matches('?');
+    let params: Vec<(String, String)> = url::form_urlencoded::parse(raw.as_bytes())
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    if params.iter().any(|(key, _)| key.is_empty()) {
+        return Err(format!("Invalid DEFAULT_UTM '{}': empty parameter name", raw));
+    }
+    Ok(params)
+}
+
+/// Converte o nome de um nível de consistência CQL (ex: `LOCAL_QUORUM`) no enum do driver
+fn parse_consistency(name: &str, value: &str) -> Result<Consistency, String> {
+    match value.trim().to_ascii_uppercase().as_str() {
+        "ANY" => Ok(Consistency::Any),
+        "ONE" => Ok(Consistency::One),
+        "TWO" => Ok(Consistency::Two),
+        "THREE" => Ok(Consistency::Three),
+        "QUORUM" => Ok(Consistency::Quorum),
+        "ALL" => Ok(Consistency::All),
+        "LOCAL_QUORUM" => Ok(Consistency::LocalQuorum),
+        "EACH_QUORUM" => Ok(Consistency::EachQuorum),
+        "LOCAL_ONE" => Ok(Consistency::LocalOne),
+        _ => Err(format!("Invalid {} '{}': unknown consistency level", name, value)),
+    }
+}
+
+/// Lê uma variável de ambiente, usando o valor padrão se não estiver definida
+fn env_or(name: &str, default: &str) -> String {
+    std::env::var(name).unwrap_or_else(|_| default.to_string())
+}
+
+/// Lê e converte uma variável de ambiente, usando o valor padrão se não estiver definida
+fn env_parse<T>(name: &str, default: T) -> Result<T, String>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    match std::env::var(name) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map_err(|e| format!("Invalid {} '{}': {}", name, value, e)),
+        Err(_) => Ok(default),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_pool_sizes() {
+        let two = NonZeroUsize::new(2).unwrap();
+        assert!(matches!(parse_pool_size("2"), Ok(PoolSize::PerShard(n)) if n == two));
+        assert!(matches!(parse_pool_size("per_host:2"), Ok(PoolSize::PerHost(n)) if n == two));
+        assert!(parse_pool_size("0").is_err());
+        assert!(parse_pool_size("per_node:2").is_err());
+    }
+
+    #[test]
+    fn parses_default_query_params() {
+        assert_eq!(default_query_params("").unwrap(), vec![]);
+        assert_eq!(
+            default_query_params("?utm_source=shortlink&utm_campaign=black%20friday").unwrap(),
+            vec![
+                ("utm_source".to_string(), "shortlink".to_string()),
+                ("utm_campaign".to_string(), "black friday".to_string()),
+            ]
+        );
+        assert!(default_query_params("=x").is_err());
+    }
+
+    #[test]
+    fn simple_strategy_default() {
+        assert_eq!(
+            replication_map("SimpleStrategy", "1").unwrap(),
+            "{'class': 'SimpleStrategy', 'replication_factor': 1}"
+        );
+        assert!(replication_map("SimpleStrategy", "dc1:3").is_err());
+    }
+
+    #[test]
+    fn network_topology_per_dc() {
+        assert_eq!(
+            replication_map("NetworkTopologyStrategy", "dc1:3, dc2:2").unwrap(),
+            "{'class': 'NetworkTopologyStrategy', 'dc1': 3, 'dc2': 2}"
+        );
+        assert!(replication_map("NetworkTopologyStrategy", "dc1").is_err());
+        assert!(replication_map("NetworkTopologyStrategy", "dc'1:3").is_err());
+        assert!(replication_map("LocalStrategy", "1").is_err());
+    }
+
+    #[test]
+    fn consistency_levels() {
+        assert_eq!(parse_consistency("X", "LOCAL_QUORUM").unwrap(), Consistency::LocalQuorum);
+        assert_eq!(parse_consistency("X", "local_one").unwrap(), Consistency::LocalOne);
+        assert!(parse_consistency("X", "SERIAL").is_err());
+    }
+
+    #[test]
+    fn redis_credentials() {
+        let url = redis_url("redis://redis:6379/", Some("app"), Some("p@ss:w/rd")).unwrap();
+        let info = redis::Client::open(url.as_str()).unwrap().get_connection_info().redis.clone();
+        assert_eq!(info.username.as_deref(), Some("app"));
+        assert_eq!(info.password.as_deref(), Some("p@ss:w/rd"));
+
+        // Sem variáveis, a URL é mantida (inclusive credenciais embutidas)
+        assert_eq!(
+            redis_url("redis://:secret@redis:6379/0", None, None).unwrap(),
+            "redis://:secret@redis:6379/0"
+        );
+        assert!(redis_url("rediss://redis:6380/", None, None).is_err());
+        assert!(redis_url("http://redis:6379/", None, None).is_err());
+    }
+
+    #[test]
+    fn reads_robots_txt() {
+        assert_eq!(robots_txt(None), DEFAULT_ROBOTS_TXT);
+        assert_eq!(robots_txt(Some(" ")), DEFAULT_ROBOTS_TXT);
+        assert_eq!(robots_txt(Some("User-agent: *\\nDisallow:")), "User-agent: *\nDisallow:\n");
+    }
+
+    #[test]
+    fn secret_key_required_in_production() {
+        assert_eq!(secret_key("development", None).unwrap(), DEFAULT_SECRET_KEY);
+        assert_eq!(secret_key("production", Some("s3cr3t")).unwrap(), "s3cr3t");
+        assert!(secret_key("production", None).is_err());
+        assert!(secret_key("Production", Some("")).is_err());
+        assert!(secret_key("production", Some(DEFAULT_SECRET_KEY)).is_err());
+    }
+
+    #[test]
+    fn keyspace_names() {
+        assert!(is_valid_keyspace("shortener"));
+        assert!(is_valid_keyspace("shortener_prod"));
+        assert!(!is_valid_keyspace("short-ener"));
+        assert!(!is_valid_keyspace("x; DROP"));
+        assert!(!is_valid_keyspace(""));
+    }
+}
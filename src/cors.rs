@@ -0,0 +1,92 @@
+use axum::http::{header, HeaderName, HeaderValue, Method};
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+const ALLOWED_METHODS: [Method; 3] = [Method::GET, Method::POST, Method::PUT];
+const ALLOWED_HEADERS: [HeaderName; 4] = [
+    header::CONTENT_TYPE,
+    header::AUTHORIZATION,
+    HeaderName::from_static("idempotency-key"),
+    header::IF_NONE_MATCH,
+];
+const EXPOSED_HEADERS: [HeaderName; 6] = [
+    header::RETRY_AFTER,
+    HeaderName::from_static("idempotent-replayed"),
+    header::ETAG,
+    HeaderName::from_static("x-ratelimit-limit"),
+    HeaderName::from_static("x-ratelimit-remaining"),
+    HeaderName::from_static("x-ratelimit-reset"),
+];
+/// Por quanto tempo o navegador pode reaproveitar um preflight
+const PREFLIGHT_MAX_AGE: Duration = Duration::from_secs(600);
+
+/// Origens autorizadas a chamar a API pelo navegador, lidas de `CORS_ALLOWED_ORIGINS`
+pub enum CorsOrigins {
+    /// `*`: qualquer origem (padrão, conveniente em desenvolvimento)
+    Any,
+    /// Lista explícita, ex: `https://app.example.com`
+    List(Vec<HeaderValue>),
+    /// Vazio: CORS desativado
+    None,
+}
+
+impl CorsOrigins {
+    pub fn parse(raw: &str) -> Self {
+        let origins: Vec<String> = raw
+            .split(',')
+            .map(|origin| origin.trim().trim_end_matches('/').to_ascii_lowercase())
+            .filter(|origin| !origin.is_empty())
+            .collect();
+
+        if origins.iter().any(|origin| origin == "*") {
+            CorsOrigins::Any
+        } else if origins.is_empty() {
+            CorsOrigins::None
+        } else {
+            // Navegadores enviam o Origin em minúsculas, como a lista normalizada
+            CorsOrigins::List(
+                origins
+                    .iter()
+                    .filter_map(|origin| HeaderValue::from_str(origin).ok())
+                    .collect(),
+            )
+        }
+    }
+
+    /// CORS das rotas JSON: responde os preflights (`OPTIONS`) direto e sempre envia
+    /// `Vary: Origin`, para que um cache compartilhado não sirva a resposta de uma
+    /// origem a outra. `None` com CORS desativado
+    pub fn layer(&self) -> Option<CorsLayer> {
+        let allow_origin = match self {
+            CorsOrigins::Any => AllowOrigin::any(),
+            CorsOrigins::List(origins) => AllowOrigin::list(origins.iter().cloned()),
+            CorsOrigins::None => return None,
+        };
+        Some(
+            CorsLayer::new()
+                .allow_origin(allow_origin)
+                .allow_methods(ALLOWED_METHODS)
+                .allow_headers(ALLOWED_HEADERS)
+                .expose_headers(EXPOSED_HEADERS)
+                .max_age(PREFLIGHT_MAX_AGE),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_origin_lists() {
+        assert!(matches!(CorsOrigins::parse("*"), CorsOrigins::Any));
+        assert!(matches!(CorsOrigins::parse(" "), CorsOrigins::None));
+        assert!(CorsOrigins::parse("").layer().is_none());
+
+        let parsed = CorsOrigins::parse("https://APP.example.com/, http://localhost:5173");
+        let CorsOrigins::List(origins) = parsed else {
+            panic!("origin list not parsed");
+        };
+        assert_eq!(origins, ["https://app.example.com", "http://localhost:5173"]);
+    }
+}
@@ -0,0 +1,100 @@
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::{from_cql_timestamp, AppError, AppState, UrlRow};
+
+/// Chave do índice reverso `url_by_long`: hash blake3 da URL já normalizada
+pub fn long_url_hash(normalized_long_url: &str) -> String {
+    blake3::hash(normalized_long_url.as_bytes()).to_hex().to_string()
+}
+
+/// Link já emitido para uma URL de destino
+pub struct ExistingLink {
+    pub short_url: String,
+    pub created_at: Option<DateTime<Utc>>,
+    /// Identificador da API key que criou o link
+    pub owner: Option<String>,
+}
+
+/// Procura um short_url já emitido para a mesma URL de destino.
+///
+/// O índice pode apontar para um link que foi removido ou expirou; nesse caso
+/// a entrada é ignorada e um novo código é gerado (sobrescrevendo o índice).
+pub async fn find_existing(
+    state: &Arc<AppState>,
+    long_url: &str,
+) -> Result<Option<ExistingLink>, AppError> {
+    let short_url = state
+        .cassandra_breaker
+        .run(state.cassandra.execute(&state.statements.select_url_by_long, (long_url_hash(long_url),)))
+        .await?
+        .maybe_first_row_typed::<(String,)>()
+        .map_err(|e| AppError::Internal(format!("url_by_long row: {}", e)))?
+        .map(|(short_url,)| short_url);
+
+    let Some(short_url) = short_url else {
+        return Ok(None);
+    };
+
+    let row = state
+        .cassandra_breaker
+        .run(state.cassandra.execute(&state.statements.select_url, (short_url.clone(),)))
+        .await?
+        .maybe_first_row_typed::<UrlRow>()
+        .map_err(|e| AppError::Internal(format!("urls row: {}", e)))?;
+
+    Ok(row
+        .filter(|row| row.long_url == long_url)
+        .map(|row| ExistingLink {
+            short_url,
+            created_at: row.created_at.map(from_cql_timestamp),
+            owner: row.owner,
+        }))
+}
+
+/// Registra o mapeamento no índice reverso; falhas só afetam a deduplicação futura
+pub async fn remember(state: &Arc<AppState>, long_url: &str, short_url: &str) {
+    if let Err(e) = state
+        .cassandra_breaker
+        .run(state.cassandra.execute(
+            &state.statements.insert_url_by_long,
+            (long_url_hash(long_url), short_url),
+        ))
+        .await
+    {
+        warn!("Cassandra url_by_long error: {}", e);
+    }
+}
+
+/// Acompanha a troca de destino de um link (`PUT /api/urls/:short_url`): se o
+/// índice da URL antiga aponta para o código, a entrada passa para a URL nova.
+/// Links que não estavam no índice (não deduplicáveis) continuam fora dele
+pub async fn reindex(state: &Arc<AppState>, old_long_url: &str, new_long_url: &str, short_url: &str) {
+    let indexed = state
+        .cassandra_breaker
+        .run(state.cassandra.execute(&state.statements.select_url_by_long, (long_url_hash(old_long_url),)))
+        .await
+        .and_then(|result| {
+            result
+                .maybe_first_row_typed::<(String,)>()
+                .map_err(|e| AppError::Internal(format!("url_by_long row: {}", e)))
+        });
+    match indexed {
+        Ok(Some((indexed,))) if indexed == short_url => {}
+        Ok(_) => return,
+        Err(e) => {
+            warn!("Cassandra url_by_long error: {}", e);
+            return;
+        }
+    }
+
+    if let Err(e) = state
+        .cassandra_breaker
+        .run(state.cassandra.execute(&state.statements.delete_url_by_long, (long_url_hash(old_long_url),)))
+        .await
+    {
+        warn!("Cassandra url_by_long error: {}", e);
+    }
+    remember(state, new_long_url, short_url).await;
+}
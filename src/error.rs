@@ -0,0 +1,274 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{rejection::JsonRejection, Request},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{Html, IntoResponse, Response},
+    Extension, Json,
+};
+use scylla::transport::errors::{DbError, QueryError};
+use tracing::error;
+
+use crate::validation::FieldError;
+use crate::AppState;
+
+/// Erros dos handlers, convertidos em respostas JSON `{"error": ..., "code": ...}`
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("Redis error: {0}")]
+    Redis(#[from] redis::RedisError),
+    #[error("Cassandra error: {0}")]
+    Cassandra(#[from] QueryError),
+    #[error("internal error: {0}")]
+    Internal(String),
+    #[error("URL not found")]
+    NotFound,
+    #[error("this link has reached its click limit")]
+    Gone,
+    /// Link removido de propósito (`DELETE /:short_url`), não apenas inexistente
+    #[error("this link was deleted")]
+    Deleted,
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("short URL already exists")]
+    Conflict,
+    #[error("short URL is reserved")]
+    ReservedAlias,
+    /// Outra requisição com o mesmo `Idempotency-Key` ainda está em andamento
+    #[error("a request with this Idempotency-Key is still in progress")]
+    IdempotencyInProgress,
+    /// `Idempotency-Key` já usado com outro corpo de requisição
+    #[error("this Idempotency-Key was already used with a different request body")]
+    IdempotencyMismatch,
+    #[error("missing API key")]
+    Unauthorized,
+    #[error("invalid API key")]
+    Forbidden,
+    /// Link com senha consultado sem a senha certa (`GET /api/resolve/:short_url`)
+    #[error("this link is password protected")]
+    PasswordRequired,
+    #[error("the destination domain is blocked")]
+    BlockedDomain,
+    /// Dono do link já tem o máximo de links ativos (`LINK_QUOTA`)
+    #[error("link quota of {limit} active links exceeded")]
+    QuotaExceeded { limit: u64 },
+    #[error("rate limit exceeded, retry in {retry_after} seconds")]
+    TooManyRequests { retry_after: u64 },
+    /// Cadeia de short links do próprio serviço em loop ou mais longa que `MAX_CHAIN_HOPS`
+    #[error("redirect chain from '{0}' loops or exceeds MAX_CHAIN_HOPS")]
+    RedirectLoop(String),
+    /// Circuit breaker do Cassandra aberto
+    #[error("database temporarily unavailable, retry in {retry_after} seconds")]
+    Unavailable { retry_after: u64 },
+    /// Modo somente leitura ativo (`READ_ONLY` ou `POST /admin/readonly`)
+    #[error("the service is in read-only mode")]
+    ReadOnly,
+    /// Um ou mais campos do corpo inválidos, todos listados na resposta
+    #[error("request validation failed")]
+    Validation(Vec<FieldError>),
+    /// Corpo da requisição ausente, malformado ou com `Content-Type` errado
+    #[error("{}", .0.body_text())]
+    InvalidBody(#[from] JsonRejection),
+    /// Como `InvalidBody`, para corpos `application/x-www-form-urlencoded`
+    #[error("{message}")]
+    InvalidForm { status: StatusCode, message: String },
+}
+
+impl AppError {
+    fn status(&self) -> StatusCode {
+        match self {
+            AppError::Cassandra(e) if is_timeout(e) => StatusCode::GATEWAY_TIMEOUT,
+            // `REDIS_TIMEOUT_MS` estourado: Redis lento ou travado, não um erro da requisição
+            AppError::Redis(e) if e.is_timeout() => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Redis(_) | AppError::Cassandra(_) | AppError::Internal(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            AppError::NotFound => StatusCode::NOT_FOUND,
+            AppError::Gone | AppError::Deleted => StatusCode::GONE,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Conflict | AppError::ReservedAlias | AppError::IdempotencyInProgress => {
+                StatusCode::CONFLICT
+            }
+            AppError::IdempotencyMismatch | AppError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Unauthorized | AppError::PasswordRequired => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden | AppError::BlockedDomain | AppError::QuotaExceeded { .. } => {
+                StatusCode::FORBIDDEN
+            }
+            AppError::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Unavailable { .. } | AppError::ReadOnly => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::RedirectLoop(_) => StatusCode::LOOP_DETECTED,
+            AppError::InvalidBody(rejection) => rejection.status(),
+            AppError::InvalidForm { status, .. } => *status,
+        }
+    }
+
+    /// Identificador estável do erro, para consumo por clientes
+    fn kind(&self) -> &'static str {
+        match self {
+            AppError::Redis(e) if e.is_timeout() => "redis_timeout",
+            AppError::Redis(_) => "redis_error",
+            AppError::Cassandra(e) if is_timeout(e) => "database_timeout",
+            AppError::Cassandra(_) => "database_error",
+            AppError::Internal(_) => "internal_error",
+            AppError::NotFound => "not_found",
+            AppError::Gone => "gone",
+            AppError::Deleted => "deleted",
+            AppError::BadRequest(_) => "bad_request",
+            AppError::Conflict => "conflict",
+            AppError::ReservedAlias => "reserved_alias",
+            AppError::IdempotencyInProgress => "idempotency_in_progress",
+            AppError::IdempotencyMismatch => "idempotency_key_reused",
+            AppError::Unauthorized => "unauthorized",
+            AppError::Forbidden => "forbidden",
+            AppError::PasswordRequired => "password_required",
+            AppError::BlockedDomain => "blocked_domain",
+            AppError::QuotaExceeded { .. } => "quota_exceeded",
+            AppError::TooManyRequests { .. } => "rate_limited",
+            AppError::Unavailable { .. } => "service_unavailable",
+            AppError::RedirectLoop(_) => "redirect_loop",
+            AppError::ReadOnly => "read_only",
+            AppError::Validation(_) => "validation_failed",
+            AppError::InvalidBody(rejection) if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE => {
+                "payload_too_large"
+            }
+            AppError::InvalidForm { status, .. } if *status == StatusCode::PAYLOAD_TOO_LARGE => {
+                "payload_too_large"
+            }
+            AppError::InvalidBody(_) | AppError::InvalidForm { .. } => "invalid_body",
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+
+        // Detalhes de falhas internas vão para o log, não para o cliente
+        let message = if status.is_server_error() {
+            // Com o circuito aberto ou em modo somente leitura cada requisição rejeitada
+            // geraria um log; a mudança de estado já foi logada
+            if !matches!(self, AppError::Unavailable { .. } | AppError::ReadOnly) {
+                error!("{}", self);
+            }
+            None
+        } else {
+            Some(self.to_string())
+        };
+
+        let mut body = serde_json::json!({
+            "error": self.kind(),
+            "code": status.as_u16(),
+        });
+        if let Some(message) = message {
+            body["message"] = message.into();
+        }
+        if let AppError::Validation(errors) = &self {
+            body["errors"] = serde_json::to_value(errors).unwrap_or_default();
+        }
+
+        let mut response = (status, Json(body)).into_response();
+        if let AppError::TooManyRequests { retry_after } | AppError::Unavailable { retry_after } = self {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+        response
+    }
+}
+
+/// Query que estourou o `QUERY_TIMEOUT_MS` no driver ou o timeout do próprio coordenador
+fn is_timeout(e: &QueryError) -> bool {
+    matches!(
+        e,
+        QueryError::RequestTimeout(_)
+            | QueryError::TimeoutError
+            | QueryError::DbError(DbError::ReadTimeout { .. } | DbError::WriteTimeout { .. }, _)
+    )
+}
+
+/// Fallback para rotas inexistentes, para que também respondam em JSON
+pub async fn not_found() -> AppError {
+    AppError::NotFound
+}
+
+const NOT_FOUND_PAGE: &str = "<!DOCTYPE html>
+<html lang=\"en\">
+<head><meta charset=\"utf-8\"><title>Not found</title></head>
+<body>
+<h1>404 &mdash; Link not found</h1>
+<p>This short link does not exist or has expired.</p>
+</body>
+</html>
+";
+
+/// Troca o 404 em JSON por uma página HTML quando o cliente é um navegador.
+///
+/// Só atua com `HTML_NOT_FOUND` ativo; clientes que não pedem `text/html` continuam
+/// recebendo JSON.
+pub async fn html_not_found(
+    Extension(state): Extension<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let wants_html = state.config.html_not_found && accepts_html(request.headers());
+    let response = next.run(request).await;
+
+    if wants_html && response.status() == StatusCode::NOT_FOUND {
+        return (StatusCode::NOT_FOUND, Html(NOT_FOUND_PAGE)).into_response();
+    }
+    response
+}
+
+/// `Accept` pede HTML explicitamente (navegadores enviam `text/html,...`)
+fn accepts_html(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| {
+            accept
+                .split(',')
+                .any(|media| media.split(';').next().unwrap_or("").trim() == "text/html")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accept(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn detects_browser_accept() {
+        assert!(accepts_html(&accept("text/html,application/xhtml+xml,*/*;q=0.8")));
+        assert!(accepts_html(&accept("text/html;q=0.9")));
+        assert!(!accepts_html(&accept("application/json")));
+        assert!(!accepts_html(&accept("*/*")));
+        assert!(!accepts_html(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn maps_query_timeouts_to_504() {
+        let timeout = AppError::Cassandra(QueryError::RequestTimeout("2s".to_string()));
+        assert_eq!(timeout.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(timeout.kind(), "database_timeout");
+
+        let other = AppError::Cassandra(QueryError::ProtocolError("bad frame"));
+        assert_eq!(other.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn maps_redis_timeouts_to_503() {
+        let timeout = std::io::Error::new(std::io::ErrorKind::TimedOut, "no response");
+        let timeout = AppError::Redis(redis::RedisError::from(timeout));
+        assert_eq!(timeout.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(timeout.kind(), "redis_timeout");
+
+        let refused = std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "refused");
+        assert_eq!(AppError::Redis(redis::RedisError::from(refused)).status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -0,0 +1,89 @@
+use axum::{
+    body::Body,
+    extract::Extension,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use bytes::Bytes;
+use chrono::SecondsFormat;
+use futures::{stream, StreamExt};
+use scylla::frame::value::Timestamp;
+use std::sync::Arc;
+use tracing::error;
+
+use crate::{from_cql_timestamp, AppError, AppState};
+
+/// Linhas buscadas por página na varredura da tabela
+const EXPORT_PAGE_SIZE: i32 = 1000;
+
+const CSV_HEADER: &str = "short_url,long_url,created_at,owner\n";
+
+// GET /api/export.csv
+pub async fn export_csv(Extension(state): Extension<Arc<AppState>>) -> Result<Response, AppError> {
+    // O driver busca as páginas sob demanda, conforme o corpo é enviado: a tabela
+    // inteira nunca fica em memória
+    let mut statement = state.statements.list_urls.clone();
+    statement.set_page_size(EXPORT_PAGE_SIZE);
+    let rows = state
+        .cassandra_breaker
+        .run(state.cassandra.execute_iter(statement, &[]))
+        .await?
+        .into_typed::<(String, String, Option<Timestamp>, Option<String>, Option<Vec<String>>)>();
+
+    let body = rows
+        .ready_chunks(EXPORT_PAGE_SIZE as usize)
+        .map(|chunk| {
+            let mut out = String::new();
+            for row in chunk {
+                let (short_url, long_url, created_at, owner, _tags) = row.map_err(|e| {
+                    // Com o corpo já em andamento, só resta interromper a resposta
+                    error!("CSV export aborted: {}", e);
+                    e
+                })?;
+                let created_at = created_at
+                    .map(|ts| from_cql_timestamp(ts).to_rfc3339_opts(SecondsFormat::Millis, true))
+                    .unwrap_or_default();
+                out.push_str(&csv_field(&short_url));
+                out.push(',');
+                out.push_str(&csv_field(&long_url));
+                out.push(',');
+                out.push_str(&created_at);
+                out.push(',');
+                out.push_str(&csv_field(owner.as_deref().unwrap_or_default()));
+                out.push('\n');
+            }
+            Ok::<_, scylla::transport::iterator::NextRowError>(Bytes::from(out))
+        });
+    let body = stream::once(async { Ok(Bytes::from_static(CSV_HEADER.as_bytes())) }).chain(body);
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"urls.csv\""),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
+
+/// Campo CSV (RFC 4180): entre aspas, com aspas duplicadas, se tiver vírgula,
+/// aspas ou quebra de linha
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_csv_fields() {
+        assert_eq!(csv_field("https://example.com/"), "https://example.com/");
+        assert_eq!(csv_field("https://example.com/?a=1,2"), "\"https://example.com/?a=1,2\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
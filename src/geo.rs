@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+
+use axum::http::{HeaderMap, HeaderName};
+use futures::StreamExt;
+use scylla::batch::Batch;
+use tracing::warn;
+
+use crate::validation::{is_self_link, normalize_long_url, AllowedSchemes, FieldError};
+use crate::{AppError, AppState, SELF_LINK_ERROR};
+
+/// Header com o país do cliente, preenchido pela CDN (`GEO_COUNTRY_HEADER`)
+pub const DEFAULT_COUNTRY_HEADER: &str = "CF-IPCountry";
+
+/// Máximo de países por link (há cerca de 250 códigos ISO 3166-1)
+const MAX_DESTINATIONS: usize = 250;
+
+/// Destinos por país de um link: código ISO 3166-1 alpha-2 (maiúsculo) → URL
+pub type GeoDestinations = BTreeMap<String, String>;
+
+/// Valida os destinos por país enviados na criação, com as mesmas regras da
+/// `long_url`. Países são aceitos em qualquer caixa e gravados em maiúsculas
+pub fn normalize_destinations(
+    raw: &GeoDestinations,
+    max_url_len: usize,
+    schemes: &AllowedSchemes,
+    public_base_url: &str,
+) -> Result<GeoDestinations, Vec<FieldError>> {
+    let mut errors = Vec::new();
+    if raw.is_empty() || raw.len() > MAX_DESTINATIONS {
+        errors.push(FieldError::new(
+            "geo",
+            format!("geo must map between 1 and {} countries", MAX_DESTINATIONS),
+        ));
+    }
+
+    let mut destinations = GeoDestinations::new();
+    for (country, url) in raw {
+        let Some(country) = normalize_country(country) else {
+            errors.push(FieldError::new(
+                "geo",
+                format!("'{}' is not a two-letter country code", country),
+            ));
+            continue;
+        };
+        match normalize_long_url(url, max_url_len, schemes) {
+            Ok(url) if is_self_link(&url, public_base_url) => {
+                errors.push(FieldError::new("geo", format!("{}: {}", country, SELF_LINK_ERROR)));
+            }
+            Ok(url) => {
+                destinations.insert(country, url);
+            }
+            Err(message) => errors.push(FieldError::new("geo", format!("{}: {}", country, message))),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(destinations)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Código de país de duas letras, em maiúsculas
+fn normalize_country(raw: &str) -> Option<String> {
+    let country = raw.trim();
+    (country.len() == 2 && country.bytes().all(|b| b.is_ascii_alphabetic()))
+        .then(|| country.to_ascii_uppercase())
+}
+
+/// País do cliente informado pela CDN. Valores fora do formato (como o `T1`
+/// do Tor no Cloudflare) não correspondem a nenhum destino
+pub fn client_country(headers: &HeaderMap, header: &HeaderName) -> Option<String> {
+    headers
+        .get(header)
+        .and_then(|value| value.to_str().ok())
+        .and_then(normalize_country)
+}
+
+/// Destino do país do cliente, se o link tiver um; caso contrário vale a `long_url`
+pub fn pick<'a>(destinations: Option<&'a GeoDestinations>, country: Option<&str>) -> Option<&'a str> {
+    destinations?.get(country?).map(String::as_str)
+}
+
+/// Lê os destinos por país de um link (`None` se ele não tiver nenhum)
+pub async fn load(state: &AppState, short_url: &str) -> Result<Option<GeoDestinations>, AppError> {
+    let mut rows = state
+        .cassandra_breaker
+        .run(
+            state
+                .cassandra
+                .execute_iter(state.statements.select_url_geo.clone(), (short_url.to_string(),)),
+        )
+        .await?
+        .into_typed::<(String, String)>();
+    let mut destinations = GeoDestinations::new();
+    while let Some(row) = rows.next().await {
+        let (country, long_url) = row.map_err(|e| AppError::Internal(format!("url_geo row: {}", e)))?;
+        destinations.insert(country, long_url);
+    }
+    Ok((!destinations.is_empty()).then_some(destinations))
+}
+
+/// Grava os destinos por país com o mesmo TTL do link (0 = sem expiração)
+pub async fn store(
+    state: &AppState,
+    short_url: &str,
+    destinations: &GeoDestinations,
+    ttl: i32,
+) -> Result<(), AppError> {
+    // Todas as linhas ficam na mesma partição: o batch não atravessa nós
+    let mut batch = Batch::default();
+    batch.set_consistency(state.config.consistency);
+    let rows: Vec<(String, String, String, i32)> = destinations
+        .iter()
+        .map(|(country, long_url)| (short_url.to_string(), country.clone(), long_url.clone(), ttl))
+        .collect();
+    for _ in &rows {
+        batch.append_statement(state.statements.insert_url_geo.clone());
+    }
+    state.cassandra_breaker.run(state.cassandra.batch(&batch, &rows)).await?;
+    Ok(())
+}
+
+/// Remove os destinos por país de um link removido, para que um alias recriado
+/// com o mesmo código não herde a configuração antiga
+pub async fn delete(state: &AppState, short_url: &str) {
+    if let Err(e) = state
+        .cassandra_breaker
+        .run(state.cassandra.execute(&state.statements.delete_url_geo, (short_url.to_string(),)))
+        .await
+    {
+        warn!("Cassandra url_geo delete error for '{}': {}", short_url, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn normalizes_destinations() {
+        let raw = GeoDestinations::from([
+            ("br".to_string(), "https://Example.com/pt".to_string()),
+            ("US".to_string(), "https://example.com/en".to_string()),
+        ]);
+        let destinations = normalize_destinations(&raw, 2048, &AllowedSchemes::default(), "https://sho.rt").unwrap();
+        assert_eq!(destinations["BR"], "https://example.com/pt");
+        assert_eq!(destinations["US"], "https://example.com/en");
+
+        let raw = GeoDestinations::from([
+            ("BRA".to_string(), "https://example.com/".to_string()),
+            ("DE".to_string(), "javascript:alert(1)".to_string()),
+        ]);
+        assert_eq!(normalize_destinations(&raw, 2048, &AllowedSchemes::default(), "https://sho.rt").unwrap_err().len(), 2);
+        assert!(normalize_destinations(&GeoDestinations::new(), 2048, &AllowedSchemes::default(), "https://sho.rt").is_err());
+    }
+
+    #[test]
+    fn picks_destination_for_client_country() {
+        let header = HeaderName::from_static("cf-ipcountry");
+        let mut headers = HeaderMap::new();
+        headers.insert(header.clone(), HeaderValue::from_static("br"));
+        let country = client_country(&headers, &header);
+        assert_eq!(country.as_deref(), Some("BR"));
+
+        let destinations = GeoDestinations::from([("BR".to_string(), "https://example.com/pt".to_string())]);
+        assert_eq!(pick(Some(&destinations), country.as_deref()), Some("https://example.com/pt"));
+        assert_eq!(pick(Some(&destinations), Some("US")), None);
+        assert_eq!(pick(None, country.as_deref()), None);
+
+        headers.insert(header.clone(), HeaderValue::from_static("T1"));
+        assert_eq!(client_country(&headers, &header), None);
+    }
+}
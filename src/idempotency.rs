@@ -0,0 +1,206 @@
+use axum::{
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::redis_pool::RedisConnection;
+use crate::AppError;
+
+/// Header enviado pelo cliente (estilo Stripe)
+pub const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+/// Header das respostas repetidas a partir de uma chave já usada
+const REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// Tamanho máximo aceito para a chave
+const MAX_KEY_LEN: usize = 255;
+
+/// Contexto do `blake3::derive_key` da chave de [`fingerprint`]
+const FINGERPRINT_CONTEXT: &str = "url-shortener idempotency fingerprint v1";
+
+/// Por quanto tempo uma requisição em andamento segura a chave (segundos).
+/// Se a instância cair no meio, a chave é liberada sozinha depois disso
+const IN_FLIGHT_TTL_SECS: u64 = 60;
+
+/// Entrada guardada no Redis para cada chave
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    /// Hash do corpo da requisição original
+    fingerprint: String,
+    /// Status e corpo da resposta; ausentes enquanto a requisição está em andamento
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<serde_json::Value>,
+}
+
+/// Resposta guardada de uma requisição anterior com a mesma chave
+pub struct Replay {
+    status: StatusCode,
+    body: serde_json::Value,
+}
+
+impl IntoResponse for Replay {
+    fn into_response(self) -> Response {
+        let mut response = (self.status, Json(self.body)).into_response();
+        response
+            .headers_mut()
+            .insert(REPLAYED, HeaderValue::from_static("true"));
+        response
+    }
+}
+
+/// Lê o `Idempotency-Key` da requisição, se houver (400 se vazio ou longo demais)
+pub fn key_from(headers: &HeaderMap) -> Result<Option<String>, AppError> {
+    let Some(value) = headers.get(IDEMPOTENCY_KEY) else {
+        return Ok(None);
+    };
+    match value.to_str().map(str::trim) {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LEN => Ok(Some(key.to_string())),
+        _ => Err(AppError::BadRequest(format!(
+            "Idempotency-Key must be 1 to {} visible ASCII characters",
+            MAX_KEY_LEN
+        ))),
+    }
+}
+
+/// Chave do hash dos corpos (`Config::idempotency_key`)
+pub fn fingerprint_key(secret_key: &str) -> [u8; 32] {
+    blake3::derive_key(FINGERPRINT_CONTEXT, secret_key.as_bytes())
+}
+
+/// Hash do corpo, para detectar a mesma chave reaproveitada com outro conteúdo.
+/// O corpo inclui a senha do link: com a chave derivada da `SECRET_KEY`, quem lê
+/// o Redis não consegue testar senhas contra o hash guardado
+pub fn fingerprint(key: &[u8; 32], request: &serde_json::Value) -> String {
+    blake3::keyed_hash(key, request.to_string().as_bytes()).to_hex().to_string()
+}
+
+/// Chaves são separadas por dono: duas API keys podem usar o mesmo valor
+fn redis_key(owner: Option<&str>, key: &str) -> String {
+    format!("idem:{}:{}", owner.unwrap_or("-"), key)
+}
+
+/// Reserva a chave para esta requisição.
+///
+/// Retorna `Some` com a resposta original se a chave já foi concluída com o
+/// mesmo corpo; 422 se foi usada com outro corpo e 409 se outra requisição com
+/// a mesma chave ainda está em andamento.
+pub async fn begin(
+    redis_conn: &mut RedisConnection,
+    owner: Option<&str>,
+    key: &str,
+    fingerprint: &str,
+) -> Result<Option<Replay>, AppError> {
+    let pending = Entry {
+        fingerprint: fingerprint.to_string(),
+        status: None,
+        body: None,
+    };
+    let pending = serde_json::to_string(&pending)
+        .map_err(|e| AppError::Internal(format!("idempotency entry: {}", e)))?;
+    let reserved: Option<String> = redis::cmd("SET")
+        .arg(redis_key(owner, key))
+        .arg(pending)
+        .arg("NX")
+        .arg("EX")
+        .arg(IN_FLIGHT_TTL_SECS)
+        .query_async(redis_conn)
+        .await?;
+    if reserved.is_some() {
+        return Ok(None);
+    }
+
+    let stored: Option<String> = redis::cmd("GET")
+        .arg(redis_key(owner, key))
+        .query_async(redis_conn)
+        .await?;
+    let entry = stored.and_then(|json| serde_json::from_str::<Entry>(&json).ok());
+    let Some(entry) = entry else {
+        // Expirou entre o SET e o GET: raro o bastante para pedir um novo retry
+        return Err(AppError::IdempotencyInProgress);
+    };
+
+    if entry.fingerprint != fingerprint {
+        return Err(AppError::IdempotencyMismatch);
+    }
+    match (entry.status.and_then(|s| StatusCode::from_u16(s).ok()), entry.body) {
+        (Some(status), Some(body)) => Ok(Some(Replay { status, body })),
+        _ => Err(AppError::IdempotencyInProgress),
+    }
+}
+
+/// Guarda a resposta de uma requisição bem-sucedida por `ttl_secs`
+pub async fn complete(
+    redis_conn: &mut RedisConnection,
+    owner: Option<&str>,
+    key: &str,
+    fingerprint: &str,
+    status: StatusCode,
+    body: &impl Serialize,
+    ttl_secs: u64,
+) {
+    let entry = serde_json::to_value(body).map(|body| Entry {
+        fingerprint: fingerprint.to_string(),
+        status: Some(status.as_u16()),
+        body: Some(body),
+    });
+    let Ok(json) = entry.and_then(|entry| serde_json::to_string(&entry)) else {
+        return;
+    };
+    let result: redis::RedisResult<()> = redis::AsyncCommands::set_ex(
+        redis_conn,
+        redis_key(owner, key),
+        json,
+        ttl_secs,
+    )
+    .await;
+    if let Err(e) = result {
+        warn!("Redis idempotency error: {}", e);
+    }
+}
+
+/// Libera a chave de uma requisição que falhou, para que o retry possa tentar de novo
+pub async fn abandon(redis_conn: &mut RedisConnection, owner: Option<&str>, key: &str) {
+    let result: redis::RedisResult<()> =
+        redis::AsyncCommands::del(redis_conn, redis_key(owner, key)).await;
+    if let Err(e) = result {
+        warn!("Redis idempotency error: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_idempotency_key() {
+        let mut headers = HeaderMap::new();
+        assert!(key_from(&headers).unwrap().is_none());
+
+        headers.insert(IDEMPOTENCY_KEY, HeaderValue::from_static(" order-42 "));
+        assert_eq!(key_from(&headers).unwrap().as_deref(), Some("order-42"));
+
+        headers.insert(IDEMPOTENCY_KEY, HeaderValue::from_static(""));
+        assert!(key_from(&headers).is_err());
+    }
+
+    #[test]
+    fn fingerprint_depends_on_body() {
+        let key = fingerprint_key("s3cr3t");
+        let a = fingerprint(&key, &serde_json::json!({ "long_url": "https://a.example/" }));
+        let b = fingerprint(&key, &serde_json::json!({ "long_url": "https://b.example/" }));
+        assert_ne!(a, b);
+        assert_eq!(a, fingerprint(&key, &serde_json::json!({ "long_url": "https://a.example/" })));
+    }
+
+    #[test]
+    fn fingerprint_is_keyed_by_secret() {
+        let body = serde_json::json!({ "long_url": "https://a.example/", "password": "hunter2" });
+        let keyed = fingerprint(&fingerprint_key("s3cr3t"), &body);
+        assert_ne!(keyed, blake3::hash(body.to_string().as_bytes()).to_hex().to_string());
+        assert_ne!(keyed, fingerprint(&fingerprint_key("other"), &body));
+    }
+}
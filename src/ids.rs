@@ -0,0 +1,173 @@
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use redis::AsyncCommands;
+use tracing::error;
+
+use crate::redis_pool::RedisConnection;
+
+/// Chave do contador global no Redis
+const COUNTER_KEY: &str = "url_id";
+
+/// Sem o `INCR` nenhum link é criado: um timeout aqui merece um log próprio
+fn log_timeout(e: &redis::RedisError) {
+    if e.is_timeout() {
+        error!("Redis timed out on INCR {}: link creation is blocked until it recovers", COUNTER_KEY);
+    }
+}
+
+/// Época dos IDs Snowflake (2024-01-01T00:00:00Z), em milissegundos
+const SNOWFLAKE_EPOCH_MS: u64 = 1_704_067_200_000;
+const NODE_BITS: u32 = 10;
+const SEQUENCE_BITS: u32 = 12;
+pub const MAX_NODE_ID: u16 = (1 << NODE_BITS) - 1;
+const MAX_SEQUENCE: u64 = (1 << SEQUENCE_BITS) - 1;
+
+/// Estratégia configurada em `ID_STRATEGY`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IdStrategy {
+    Redis,
+    Snowflake { node_id: u16 },
+}
+
+/// Origem dos IDs numéricos que viram short codes
+pub enum IdGenerator {
+    /// `INCR` no contador global do Redis: IDs sequenciais e códigos curtos
+    Redis,
+    /// Gerado localmente, sem depender do Redis para criar links
+    Snowflake(Snowflake),
+}
+
+impl IdGenerator {
+    pub fn new(strategy: IdStrategy) -> Self {
+        match strategy {
+            IdStrategy::Redis => IdGenerator::Redis,
+            IdStrategy::Snowflake { node_id } => IdGenerator::Snowflake(Snowflake::new(node_id)),
+        }
+    }
+
+    pub async fn next(&self, redis: &mut RedisConnection) -> Result<u64, redis::RedisError> {
+        match self {
+            IdGenerator::Redis => redis.incr(COUNTER_KEY, 1).await.inspect_err(log_timeout),
+            IdGenerator::Snowflake(snowflake) => Ok(snowflake.next_id().await),
+        }
+    }
+
+    /// Próximo ID sem consumi-lo (`POST /shorten?dry_run=true`): o contador atual + 1.
+    /// Outra criação pode usá-lo antes; no Snowflake é só um ID local a mais
+    pub async fn peek(&self, redis: &mut RedisConnection) -> Result<u64, redis::RedisError> {
+        match self {
+            IdGenerator::Redis => {
+                let current: Option<u64> = redis.get(COUNTER_KEY).await?;
+                Ok(current.unwrap_or(0) + 1)
+            }
+            IdGenerator::Snowflake(snowflake) => Ok(snowflake.next_id().await),
+        }
+    }
+
+    /// Reserva `count` IDs de uma vez (um único `INCRBY` no Redis)
+    pub async fn reserve(
+        &self,
+        redis: &mut RedisConnection,
+        count: u64,
+    ) -> Result<Vec<u64>, redis::RedisError> {
+        match self {
+            IdGenerator::Redis => {
+                let last: u64 = redis.incr(COUNTER_KEY, count).await.inspect_err(log_timeout)?;
+                Ok((last + 1 - count..=last).collect())
+            }
+            IdGenerator::Snowflake(snowflake) => {
+                let mut ids = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    ids.push(snowflake.next_id().await);
+                }
+                Ok(ids)
+            }
+        }
+    }
+}
+
+/// IDs no formato Snowflake: 41 bits de timestamp (ms desde `SNOWFLAKE_EPOCH_MS`),
+/// 10 bits de nó e 12 bits de sequência por milissegundo.
+///
+/// Cada instância precisa de um `NODE_ID` distinto para que os IDs não colidam.
+pub struct Snowflake {
+    node_id: u64,
+    /// Último milissegundo usado e a sequência dentro dele
+    state: Mutex<(u64, u64)>,
+}
+
+impl Snowflake {
+    pub fn new(node_id: u16) -> Self {
+        assert!(node_id <= MAX_NODE_ID, "node id out of range");
+        Snowflake {
+            node_id: node_id as u64,
+            state: Mutex::new((0, 0)),
+        }
+    }
+
+    /// Próximo ID. Com a sequência do milissegundo esgotada, espera o próximo fora
+    /// do lock, sem bloquear a thread do tokio nem as outras criações
+    pub async fn next_id(&self) -> u64 {
+        loop {
+            match self.try_next_id() {
+                Ok(id) => return id,
+                Err(wait) => tokio::time::sleep(wait).await,
+            }
+        }
+    }
+
+    /// Próximo ID, ou quanto esperar até o próximo milissegundo se a sequência do
+    /// atual se esgotou
+    fn try_next_id(&self) -> Result<u64, Duration> {
+        let mut state = self.state.lock().unwrap();
+        let (last_ms, sequence) = *state;
+
+        // Se o relógio voltar, continua no último milissegundo usado para não repetir IDs
+        let clock = now_ms();
+        let now = clock.max(last_ms);
+        let sequence = if now == last_ms {
+            if sequence == MAX_SEQUENCE {
+                return Err(Duration::from_millis(last_ms + 1 - clock));
+            }
+            sequence + 1
+        } else {
+            0
+        };
+        *state = (now, sequence);
+
+        let timestamp = (now - SNOWFLAKE_EPOCH_MS) << (NODE_BITS + SEQUENCE_BITS);
+        Ok(timestamp | (self.node_id << SEQUENCE_BITS) | sequence)
+    }
+}
+
+fn now_ms() -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64);
+    now.max(SNOWFLAKE_EPOCH_MS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn snowflake_ids_are_unique_and_increasing() {
+        // Mais de uma sequência inteira: passa pela espera do milissegundo seguinte
+        let snowflake = Snowflake::new(7);
+        let mut ids = Vec::new();
+        for _ in 0..10_000 {
+            ids.push(snowflake.next_id().await);
+        }
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(ids.iter().all(|id| (id >> SEQUENCE_BITS) & MAX_NODE_ID as u64 == 7));
+    }
+
+    #[tokio::test]
+    async fn different_nodes_never_collide() {
+        let a = Snowflake::new(1).next_id().await;
+        let b = Snowflake::new(2).next_id().await;
+        assert_ne!(a, b);
+    }
+}
@@ -0,0 +1,243 @@
+use axum::{
+    extract::{rejection::QueryRejection, Extension, Query},
+    Json,
+};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use scylla::frame::value::Timestamp;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::tags;
+use crate::{from_cql_timestamp, AppError, AppState};
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+
+#[derive(Deserialize)]
+pub struct ListParams {
+    limit: Option<usize>,
+    cursor: Option<String>,
+    /// Só os links criados por esta API key
+    owner: Option<String>,
+    /// Só os links com esta tag (sem diferenciar maiúsculas)
+    tag: Option<String>,
+}
+
+/// Colunas de `list_urls`, `list_urls_by_owner` e `select_listed_url`
+type ListedRow = (String, String, Option<Timestamp>, Option<String>, Option<Vec<String>>);
+
+#[derive(Serialize)]
+pub struct ListedUrl {
+    short_url: String,
+    long_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    owner: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+}
+
+impl ListedUrl {
+    fn from_row((short_url, long_url, created_at, owner, tags): ListedRow) -> Self {
+        ListedUrl {
+            short_url,
+            long_url,
+            created_at: created_at.map(from_cql_timestamp),
+            owner,
+            tags,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct UrlPage {
+    urls: Vec<ListedUrl>,
+    /// Cursor da próxima página; ausente na última
+    next_cursor: Option<String>,
+}
+
+// GET /api/urls?limit=&cursor=&owner=&tag=
+pub async fn list_urls(
+    params: Result<Query<ListParams>, QueryRejection>,
+    Extension(state): Extension<Arc<AppState>>,
+) -> Result<Json<UrlPage>, AppError> {
+    let Query(params) = params.map_err(|e| AppError::BadRequest(e.body_text()))?;
+
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        return Err(AppError::BadRequest(format!(
+            "limit must be between 1 and {}",
+            MAX_PAGE_SIZE
+        )));
+    }
+    let paging_state = params
+        .cursor
+        .map(|cursor| decode_cursor(&cursor).ok_or_else(|| AppError::BadRequest("invalid cursor".to_string())))
+        .transpose()?;
+
+    // Paging nativo do Cassandra: o paging state marca onde a varredura parou,
+    // sem o custo de pular linhas que um OFFSET teria
+    // Com `owner`, uma página pode vir com menos linhas que `limit` (até vazia) e
+    // ainda assim ter `next_cursor`: o filtro é aplicado sobre cada página varrida
+    let owner = params.owner.filter(|owner| !owner.is_empty());
+    if let Some(tag) = params.tag.filter(|tag| !tag.trim().is_empty()) {
+        return list_by_tag(&state, &tag, owner, limit, paging_state).await;
+    }
+    let statement = match owner {
+        Some(_) => &state.statements.list_urls_by_owner,
+        None => &state.statements.list_urls,
+    };
+    let mut statement = statement.clone();
+    statement.set_page_size(limit as i32);
+    let result = match owner {
+        Some(owner) => {
+            state
+                .cassandra_breaker
+                .run(state.cassandra.execute_paged(&statement, (owner,), paging_state))
+                .await?
+        }
+        None => {
+            state
+                .cassandra_breaker
+                .run(state.cassandra.execute_paged(&statement, &[], paging_state))
+                .await?
+        }
+    };
+
+    let next_cursor = result.paging_state.as_ref().map(|state| encode_cursor(state));
+    let urls = result
+        .rows_typed::<ListedRow>()
+        .map_err(|e| AppError::Internal(format!("urls rows: {}", e)))?
+        .map(|row| {
+            row.map(ListedUrl::from_row)
+                .map_err(|e| AppError::Internal(format!("urls row: {}", e)))
+        })
+        .collect::<Result<Vec<_>, AppError>>()?;
+
+    Ok(Json(UrlPage { urls, next_cursor }))
+}
+
+/// Página de `urls_by_tag`, completada com as linhas de `urls`. Entradas cujo link
+/// já não existe ou perdeu a tag (remoção que falhou no meio) são ignoradas
+async fn list_by_tag(
+    state: &AppState,
+    tag: &str,
+    owner: Option<String>,
+    limit: usize,
+    paging_state: Option<Bytes>,
+) -> Result<Json<UrlPage>, AppError> {
+    let tag = tag.trim().to_lowercase();
+    if !tags::is_valid_tag(&tag) {
+        return Err(AppError::BadRequest(format!("invalid tag '{}'", tag)));
+    }
+
+    let mut statement = state.statements.list_urls_by_tag.clone();
+    statement.set_page_size(limit as i32);
+    let result = state
+        .cassandra_breaker
+        .run(state.cassandra.execute_paged(&statement, (tag.clone(),), paging_state))
+        .await?;
+    let next_cursor = result.paging_state.as_ref().map(|state| encode_cursor(state));
+    let codes = result
+        .rows_typed::<(String,)>()
+        .map_err(|e| AppError::Internal(format!("urls_by_tag rows: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::Internal(format!("urls_by_tag row: {}", e)))?;
+
+    // As linhas de `urls` são lidas em paralelo, uma partição por código
+    let lookups = codes.into_iter().map(|(short_url,)| {
+        state
+            .cassandra_breaker
+            .run(state.cassandra.execute(&state.statements.select_listed_url, (short_url,)))
+    });
+    let mut urls = Vec::new();
+    for result in futures::future::try_join_all(lookups).await? {
+        let row = result
+            .maybe_first_row_typed::<ListedRow>()
+            .map_err(|e| AppError::Internal(format!("urls row: {}", e)))?;
+        let Some(url) = row.map(ListedUrl::from_row) else {
+            continue;
+        };
+        let tagged = url.tags.as_ref().is_some_and(|tags| tags.contains(&tag));
+        if tagged && owner.as_ref().is_none_or(|owner| url.owner.as_ref() == Some(owner)) {
+            urls.push(url);
+        }
+    }
+
+    Ok(Json(UrlPage { urls, next_cursor }))
+}
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Paging state como base64 URL-safe sem padding, opaco para o cliente
+fn encode_cursor(paging_state: &[u8]) -> String {
+    let mut out = String::with_capacity(paging_state.len().div_ceil(3) * 4);
+    for chunk in paging_state.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &byte)| n | (byte as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(BASE64URL[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+        }
+    }
+    out
+}
+
+fn decode_cursor(cursor: &str) -> Option<Bytes> {
+    if cursor.is_empty() || cursor.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(cursor.len() * 3 / 4);
+    for chunk in cursor.as_bytes().chunks(4) {
+        let mut n = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let value = BASE64URL.iter().position(|&b| b == c)? as u32;
+            n |= value << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            out.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(Bytes::from(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_round_trip() {
+        for len in 0..40u8 {
+            let state: Vec<u8> = (0..len).map(|i| i.wrapping_mul(37) ^ 0xA5).collect();
+            let cursor = encode_cursor(&state);
+            assert!(cursor.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_'));
+            if len > 0 {
+                assert_eq!(decode_cursor(&cursor).unwrap(), state);
+            }
+        }
+        assert_eq!(encode_cursor(b"hello"), "aGVsbG8");
+    }
+
+    #[test]
+    fn rejects_invalid_cursors() {
+        assert!(decode_cursor("").is_none());
+        assert!(decode_cursor("a").is_none());
+        assert!(decode_cursor("ab+c").is_none());
+    }
+
+    #[test]
+    fn response_schemas_match_structs() {
+        use crate::openapi::schema_fields::assert_response;
+        let listed = ListedUrl::from_row((String::new(), String::new(), None, None, None));
+        assert_response("UrlPage/properties/urls/items", &listed);
+        let page = UrlPage {
+            urls: Vec::new(),
+            next_cursor: None,
+        };
+        assert_response("UrlPage", &page);
+    }
+}
@@ -0,0 +1,342 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use axum::http::{header, HeaderMap, Request, Response};
+use chrono::{SecondsFormat, Utc};
+use tower_http::trace::{MakeSpan, OnResponse};
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::{info, info_span, Event, Metadata, Span, Subscriber};
+
+/// Tamanho máximo do `Referer` registrado nos redirecionamentos
+const MAX_REFERER_LEN: usize = 256;
+
+/// Formato de saída dos logs (`LOG_FORMAT=json` para o ELK, texto por padrão)
+#[derive(Clone, Copy, PartialEq)]
+enum LogFormat {
+    Text,
+    Json,
+}
+
+/// Instala o subscriber global de `tracing`.
+///
+/// O nível vem de `RUST_LOG` (ex: `info` ou `info,scylla=warn`) e o formato de `LOG_FORMAT`.
+pub fn init() {
+    let format = match std::env::var("LOG_FORMAT").as_deref() {
+        Ok("json") => LogFormat::Json,
+        _ => LogFormat::Text,
+    };
+    let filter = Filter::parse(&std::env::var("RUST_LOG").unwrap_or_default());
+
+    let logger = Logger {
+        format,
+        filter,
+        next_span_id: AtomicU64::new(1),
+        spans: Mutex::new(HashMap::new()),
+    };
+    if tracing::subscriber::set_global_default(logger).is_err() {
+        eprintln!("A global tracing subscriber is already installed");
+    }
+}
+
+/// Span de cada requisição no `TraceLayer`, com o método e o caminho
+#[derive(Clone, Copy)]
+pub struct RequestSpan;
+
+impl<B> MakeSpan<B> for RequestSpan {
+    fn make_span(&mut self, request: &Request<B>) -> Span {
+        info_span!("request", method = %request.method(), path = %request.uri().path())
+    }
+}
+
+/// Registra status e latência de cada requisição; o método e o caminho vêm do span
+#[derive(Clone, Copy)]
+pub struct LogResponse;
+
+impl<B> OnResponse<B> for LogResponse {
+    fn on_response(self, response: &Response<B>, latency: Duration, _span: &Span) {
+        info!(
+            status = response.status().as_u16(),
+            latency_ms = latency.as_secs_f64() * 1000.0,
+            "request"
+        );
+    }
+}
+
+/// `Referer` de uma requisição para o log (`LOG_REFERER`), sem query string nem
+/// fragmento, que costumam carregar tokens e dados pessoais
+pub fn referer(headers: &HeaderMap) -> Option<String> {
+    let referer = headers.get(header::REFERER)?.to_str().ok()?;
+    let end = referer.find(['?', '#']).unwrap_or(referer.len());
+    Some(referer[..end].chars().take(MAX_REFERER_LEN).collect())
+}
+
+/// Filtro de nível no estilo `RUST_LOG`: um nível padrão e diretivas `target=nivel`
+struct Filter {
+    default: LevelFilter,
+    directives: Vec<(String, LevelFilter)>,
+}
+
+impl Filter {
+    fn parse(spec: &str) -> Self {
+        let mut filter = Filter {
+            default: LevelFilter::INFO,
+            directives: Vec::new(),
+        };
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((target, level)) => {
+                    if let Ok(level) = level.parse() {
+                        filter.directives.push((target.to_string(), level));
+                    }
+                }
+                None => {
+                    if let Ok(level) = directive.parse() {
+                        filter.default = level;
+                    }
+                }
+            }
+        }
+        // O prefixo mais específico (mais longo) tem prioridade
+        filter
+            .directives
+            .sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+        filter
+    }
+
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.directives
+            .iter()
+            .find(|(prefix, _)| target.starts_with(prefix.as_str()))
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.directives
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, LevelFilter::max)
+    }
+}
+
+thread_local! {
+    /// Spans em que a thread está no momento, do mais externo ao mais interno
+    static CURRENT_SPANS: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Campos de um span aberto e quantas referências ainda existem para ele
+struct SpanData {
+    refs: usize,
+    fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Subscriber mínimo: escreve cada evento como uma linha em stdout, com os campos
+/// dos spans em que ele ocorreu (ex: método e caminho da requisição)
+struct Logger {
+    format: LogFormat,
+    filter: Filter,
+    next_span_id: AtomicU64,
+    spans: Mutex<HashMap<u64, SpanData>>,
+}
+
+impl Logger {
+    fn spans(&self) -> std::sync::MutexGuard<'_, HashMap<u64, SpanData>> {
+        self.spans.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Subscriber for Logger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= &self.filter.level_for(metadata.target())
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(self.filter.max_level())
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let id = self.next_span_id.fetch_add(1, Ordering::Relaxed);
+        let mut fields = Fields::default();
+        span.record(&mut fields);
+        self.spans().insert(
+            id,
+            SpanData {
+                refs: 1,
+                fields: fields.values,
+            },
+        );
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut fields = Fields::default();
+        values.record(&mut fields);
+        if let Some(data) = self.spans().get_mut(&span.into_u64()) {
+            data.fields.extend(fields.values);
+        }
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let metadata = event.metadata();
+        let mut fields = Fields::default();
+        CURRENT_SPANS.with(|current| {
+            let spans = self.spans();
+            for id in current.borrow().iter() {
+                if let Some(data) = spans.get(id) {
+                    fields.values.extend(data.fields.clone());
+                }
+            }
+        });
+        event.record(&mut fields);
+
+        let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        let line = match self.format {
+            LogFormat::Json => {
+                let mut object = serde_json::json!({
+                    "timestamp": timestamp,
+                    "level": metadata.level().as_str(),
+                    "target": metadata.target(),
+                    "message": fields.message,
+                });
+                if !fields.values.is_empty() {
+                    object["fields"] = serde_json::Value::Object(fields.values);
+                }
+                object.to_string()
+            }
+            LogFormat::Text => {
+                let mut line = format!(
+                    "{} {:>5} {}: {}",
+                    timestamp,
+                    metadata.level().as_str(),
+                    metadata.target(),
+                    fields.message
+                );
+                for (key, value) in &fields.values {
+                    match value {
+                        serde_json::Value::String(s) => line.push_str(&format!(" {}={}", key, s)),
+                        other => line.push_str(&format!(" {}={}", key, other)),
+                    }
+                }
+                line
+            }
+        };
+
+        let _ = writeln!(std::io::stdout().lock(), "{}", line);
+    }
+
+    fn enter(&self, span: &Id) {
+        CURRENT_SPANS.with(|current| current.borrow_mut().push(span.into_u64()));
+    }
+
+    fn exit(&self, span: &Id) {
+        CURRENT_SPANS.with(|current| {
+            let mut current = current.borrow_mut();
+            if let Some(position) = current.iter().rposition(|id| *id == span.into_u64()) {
+                current.remove(position);
+            }
+        });
+    }
+
+    fn clone_span(&self, span: &Id) -> Id {
+        if let Some(data) = self.spans().get_mut(&span.into_u64()) {
+            data.refs += 1;
+        }
+        span.clone()
+    }
+
+    fn try_close(&self, span: Id) -> bool {
+        let mut spans = self.spans();
+        let Some(data) = spans.get_mut(&span.into_u64()) else {
+            return false;
+        };
+        data.refs -= 1;
+        if data.refs > 0 {
+            return false;
+        }
+        spans.remove(&span.into_u64());
+        true
+    }
+}
+
+/// Coleta a mensagem e os campos estruturados de um evento
+#[derive(Default)]
+struct Fields {
+    message: String,
+    values: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Fields {
+    fn insert(&mut self, field: &Field, value: serde_json::Value) {
+        if field.name() == "message" {
+            self.message = match value {
+                serde_json::Value::String(s) => s,
+                other => other.to_string(),
+            };
+        } else {
+            self.values.insert(field.name().to_string(), value);
+        }
+    }
+}
+
+impl Visit for Fields {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, format!("{:?}", value).into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing::Level;
+
+    #[test]
+    fn filter_uses_most_specific_directive() {
+        let filter = Filter::parse("warn,scylla=error,api_rust=debug");
+        assert_eq!(filter.level_for("api_rust::batch"), LevelFilter::DEBUG);
+        assert_eq!(filter.level_for("scylla::transport"), LevelFilter::ERROR);
+        assert_eq!(filter.level_for("redis"), LevelFilter::WARN);
+        assert!(Level::INFO <= filter.max_level());
+    }
+
+    #[test]
+    fn referer_drops_query_and_fragment() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(referer(&headers), None);
+        headers.insert(header::REFERER, "https://news.example.com/post?token=abc#top".parse().unwrap());
+        assert_eq!(referer(&headers).unwrap(), "https://news.example.com/post");
+    }
+
+    #[test]
+    fn filter_defaults_to_info() {
+        let filter = Filter::parse("");
+        assert_eq!(filter.level_for("api_rust"), LevelFilter::INFO);
+    }
+}
@@ -1,192 +1,2358 @@
+// `openapi::document` monta o documento com `json!`, que passa do limite padrão de 128
+#![recursion_limit = "256"]
+
 use axum::{
-    extract::{Path, Extension},
-    http::StatusCode,
-    response::{IntoResponse, Redirect},
-    routing::{get, post},
+    extract::{
+        rejection::{JsonRejection, QueryRejection},
+        ConnectInfo, DefaultBodyLimit, Form, Path, Extension, Query, RawQuery,
+    },
+    http::{header, HeaderMap, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
-use std::{net::SocketAddr, sync::Arc};
-use scylla::{Session, SessionBuilder, FromRow};
-use redis::aio::MultiplexedConnection; 
+use std::net::SocketAddr;
+use std::sync::{atomic::Ordering, Arc};
+use std::time::Duration;
+use tokio_util::task::TaskTracker;
+use tower_http::compression::CompressionLayer;
+use tower_http::trace::TraceLayer;
+use tracing::{error, info, warn};
+use scylla::{Session, SessionBuilder, FromRow, QueryResult};
+use scylla::statement::Consistency;
+use scylla::transport::load_balancing::{DcAwareRoundRobinPolicy, TokenAwarePolicy};
+use scylla::frame::value::{Counter, Timestamp};
+use scylla::transport::errors::{DbError, NewSessionError};
+use chrono::{DateTime, SubsecRound, Utc};
 use redis::AsyncCommands;
-use rand::{SeedableRng, seq::SliceRandom};
-use rand_chacha::ChaCha8Rng;
-use blake3;
+
+mod audit;
+mod auth;
+mod batch;
+mod body;
+mod bots;
+mod cache_warm;
+mod chain;
+mod circuit;
+mod cleanup;
+mod click_events;
+mod click_limit;
+mod client_ip;
+mod config;
+mod cors;
+mod dedup;
+mod error;
+mod export;
+mod geo;
+mod idempotency;
+mod ids;
+mod listing;
+mod logging;
+mod maintenance;
+mod metrics;
+mod openapi;
+mod password;
+mod qr;
+mod quota;
+mod rate_limit;
+mod redirect;
+mod redis_pool;
+mod retry;
+mod schema;
+mod shortcode;
+mod stampede;
+mod statements;
+mod summary;
+mod tags;
+mod timing;
+mod tombstone;
+mod validation;
+mod variants;
+
+use auth::ApiKeyId;
+use body::JsonOrForm;
+use circuit::CircuitBreaker;
+use config::Config;
+use error::AppError;
+use geo::GeoDestinations;
+use ids::IdGenerator;
+use maintenance::ReadOnly;
+use metrics::Metrics;
+use qr::QrCode;
+use redirect::{
+    append_default_params, append_query, landing_page, limited_link_page, normalize_code, password_prompt,
+    preview_page, redirect_response, redirect_with_status, take_password, take_preview_flag, RedirectCache,
+};
+use redis_pool::{RedisConnection, RedisPool};
+use retry::retry_with_backoff;
+use shortcode::{decode_code, generate_code, is_reserved, is_valid_alias, CodeMode};
+use statements::Statements;
+use validation::{is_self_link, normalize_long_url, AllowedSchemes, FieldError};
+use variants::Variant;
 
 pub struct AppState {
-    pub redis: MultiplexedConnection,
+    pub redis: RedisPool,
     pub cassandra: Session,
+    pub statements: Statements,
+    pub config: Config,
+    pub metrics: Metrics,
+    pub ids: IdGenerator,
+    /// Tarefas em segundo plano (ex: contador de cliques), aguardadas no shutdown
+    pub background: TaskTracker,
+    /// Todas as queries do Cassandra nos handlers passam por aqui
+    pub cassandra_breaker: CircuitBreaker,
+    pub read_only: ReadOnly,
 }
 
 #[derive(FromRow, Debug)]
 struct UrlRow {
     long_url: String,
+    /// TTL restante da linha (segundos), `None` se o link não expira
+    ttl: Option<i32>,
+    permanent: Option<bool>,
+    created_at: Option<Timestamp>,
+    max_clicks: Option<i64>,
+    password_hash: Option<String>,
+    owner: Option<String>,
+    tags: Option<Vec<String>>,
 }
 
+/// Corpo de `POST /shorten`, em JSON ou formulário HTML.
+///
+/// `Serialize` só existe para a impressão digital do `Idempotency-Key`, que cobre
+/// todos os campos (inclusive os novos) sem precisar listá-los; a senha entra no
+/// hash (com chave, ver `idempotency::fingerprint`), então este tipo nunca é
+/// devolvido nas respostas
 #[derive(Serialize, Deserialize)]
+struct ShortenRequest {
+    /// Alias personalizado; sem valor, o código é gerado
+    short_url: Option<String>,
+    long_url: String,
+    /// Tempo de vida do link em segundos; sem valor, o link não expira
+    ttl_secs: Option<u64>,
+    /// Sobrescreve `REDIRECT_PERMANENT` para este link (301 em vez de 307)
+    permanent: Option<bool>,
+    /// Número de redirecionamentos permitidos; depois disso o link responde 410 e é removido
+    max_clicks: Option<u64>,
+    /// Senha exigida antes do redirecionamento; só o hash é guardado
+    password: Option<String>,
+    /// Gera um código novo mesmo que a URL já tenha sido encurtada
+    force_new: Option<bool>,
+    /// Destinos por país (ISO 3166-1 alpha-2 → URL); sem correspondência vale `long_url`
+    geo: Option<GeoDestinations>,
+    /// Destinos de um teste A/B, sorteados por peso e fixados por cookie
+    variants: Option<Vec<Variant>>,
+    /// Tags para organizar os links (`GET /api/urls?tag=`), gravadas em minúsculas
+    tags: Option<Vec<String>>,
+}
+
+/// Link devolvido pela criação e pelo `PUT /api/urls/:short_url`
+#[derive(Serialize)]
 struct Url {
     short_url: Option<String>,
+    /// URL pública completa do short link
+    #[serde(skip_serializing_if = "Option::is_none")]
+    short_link: Option<String>,
     long_url: String,
+    /// Definido pelo servidor na criação (RFC3339)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ttl_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    permanent: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_clicks: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    geo: Option<GeoDestinations>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    variants: Option<Vec<Variant>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+    /// Em `POST /shorten`: `true` se um link novo foi gravado
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created: Option<bool>,
+    /// Em `POST /shorten`: `true` se o código devolvido já existia para a mesma URL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deduplicated: Option<bool>,
+    /// Em `POST /shorten?dry_run=true`: nada foi gravado e `short_url` é só a previsão
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dry_run: Option<bool>,
+}
+
+/// Dados necessários para redirecionar, guardados como JSON no cache do Redis
+#[derive(Serialize, Deserialize)]
+struct CachedLink {
+    long_url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    permanent: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_clicks: Option<u64>,
+    /// Hash da senha do link (ver `password::hash_password`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    password_hash: Option<String>,
+    /// Quando o link expira (segundos desde a epoch), `None` se não expira
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<i64>,
+    /// Destinos por país (tabela `url_geo`), `None` se o link não tiver nenhum
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    geo: Option<GeoDestinations>,
+    /// Variantes A/B (tabela `url_variants`), `None` se o link não tiver nenhuma
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    variants: Option<Vec<Variant>>,
+}
+
+impl CachedLink {
+    /// Expiração restante do link em segundos
+    fn remaining_ttl(&self) -> Option<u64> {
+        self.expires_at
+            .map(|at| (at - Utc::now().timestamp()).max(1) as u64)
+    }
+}
+
+/// Instante de expiração de um link com o TTL informado
+fn expires_at(ttl_secs: Option<u64>) -> Option<i64> {
+    ttl_secs.map(|ttl| Utc::now().timestamp() + ttl as i64)
+}
+
+/// Resposta para destinos no próprio domínio dos short links
+const SELF_LINK_ERROR: &str = "long_url cannot point to this URL shortener";
+
+/// Maior TTL aceito pelo Cassandra (20 anos)
+const MAX_TTL_SECS: u64 = 630_720_000;
+
+/// TTL do cache limitado ao TTL do link, para que o cache nunca sobreviva à linha no Cassandra
+fn effective_cache_ttl(cache_ttl_secs: u64, link_ttl_secs: Option<u64>) -> u64 {
+    link_ttl_secs
+        .map_or(cache_ttl_secs, |ttl| ttl.min(cache_ttl_secs))
+        .max(1)
+}
+
+/// Converte um `DateTime` para o tipo `timestamp` do CQL (milissegundos desde a epoch)
+fn to_cql_timestamp(datetime: DateTime<Utc>) -> Timestamp {
+    Timestamp(chrono::Duration::milliseconds(datetime.timestamp_millis()))
+}
+
+/// Converte um `timestamp` do CQL de volta para `DateTime`
+fn from_cql_timestamp(timestamp: Timestamp) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(timestamp.0.num_milliseconds()).unwrap_or_default()
+}
+
+/// Resultado de um `INSERT ... IF NOT EXISTS`
+enum InsertOutcome {
+    Applied,
+    AlreadyExists { long_url: Option<String>, owner: Option<String> },
+}
+
+/// Lê a coluna `[applied]` do resultado de uma LWT (`IF EXISTS` / `IF NOT EXISTS`)
+fn lwt_applied(result: &QueryResult) -> Option<bool> {
+    let applied_idx = result.get_column_spec("[applied]")?.0;
+    let row = result.rows.as_ref()?.first()?;
+    row.columns.get(applied_idx)?.as_ref()?.as_boolean()
+}
+
+/// Interpreta o resultado de um `INSERT ... IF NOT EXISTS`, incluindo a linha existente
+fn parse_insert_outcome(result: QueryResult) -> Option<InsertOutcome> {
+    if lwt_applied(&result)? {
+        return Some(InsertOutcome::Applied);
+    }
+
+    let long_url_idx = result.get_column_spec("long_url").map(|(idx, _)| idx);
+    let owner_idx = result.get_column_spec("owner").map(|(idx, _)| idx);
+    let row = result.rows?.into_iter().next()?;
+    let text_column = |idx: Option<usize>| {
+        idx.and_then(|idx| row.columns.get(idx).cloned().flatten())
+            .and_then(|value| value.into_string())
+    };
+    let long_url = text_column(long_url_idx);
+    let owner = text_column(owner_idx);
+    Some(InsertOutcome::AlreadyExists { long_url, owner })
+}
+
+/// Forma canônica de um código recebido: em minúsculas com `CASE_INSENSITIVE`
+fn canonical_code(config: &Config, code: &str) -> String {
+    if config.case_insensitive {
+        code.to_ascii_lowercase()
+    } else {
+        code.to_string()
+    }
+}
+
+/// Código gravado para um código recebido nas rotas da API: com `CASE_INSENSITIVE`,
+/// a forma canônica, ou a grafia exata de links criados antes do modo (como o
+/// redirecionamento faz em `find_short_link`)
+async fn stored_code(state: &AppState, short: String) -> Result<String, AppError> {
+    let canonical = canonical_code(&state.config, &short);
+    if canonical == short {
+        return Ok(short);
+    }
+    let exists = state
+        .cassandra_breaker
+        .run(state.cassandra.execute(&state.statements.exists_url, (canonical.clone(),)))
+        .await?
+        .rows
+        .is_some_and(|rows| !rows.is_empty());
+    Ok(if exists { canonical } else { short })
+}
+
+/// Gera o short URL para um ID do contador do Redis. Com `CODE_WIDTH`, `limit` é
+/// o primeiro ID deslocado que já não cabe na largura fixa
+fn short_url_for_id(
+    id: u64,
+    offset: u64,
+    limit: Option<u64>,
+    mode: &CodeMode,
+    seed: &[u8; 32],
+    alphabet: &str,
+    min_len: usize,
+) -> Result<String, AppError> {
+    // Desloca o ID por `ID_OFFSET`; só estoura com um contador corrompido
+    let id_adjusted = id
+        .checked_add(offset)
+        .ok_or_else(|| AppError::Internal(format!("id {} overflows the code space", id)))?;
+    if let Some(limit) = limit.filter(|limit| id_adjusted >= *limit) {
+        return Err(AppError::Internal(format!(
+            "id {} does not fit in CODE_WIDTH: all {} codes of this width were issued",
+            id,
+            limit - offset
+        )));
+    }
+    Ok(generate_code(mode, seed, alphabet, id_adjusted, min_len))
+}
+
+/// Chave do cache de redirecionamento no Redis
+fn cache_key(short_url: &str) -> String {
+    format!("url:{}", short_url)
 }
 
-/// Gera o short URL com base62 e ofuscação via secret_key
-fn generate_short_url(secret_key: &str, mut id: u64) -> String {
-    // Base62 padrão
-    let mut alphabet: Vec<char> = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789"
-        .chars()
-        .collect();
+/// Valor gravado no lugar do link quando o código não existe (cache negativo)
+const NOT_FOUND_SENTINEL: &str = "!404";
+/// Como `NOT_FOUND_SENTINEL`, para códigos removidos de propósito (410)
+const DELETED_SENTINEL: &str = "!410";
+
+/// O que o cache sabe sobre um código
+enum CacheEntry {
+    Link(CachedLink),
+    /// O código foi consultado recentemente e não existia
+    NotFound,
+    /// Como `NotFound`, mas o código foi removido (`url_tombstones`)
+    Deleted,
+}
+
+impl CacheEntry {
+    /// Interpreta o valor de `url:{short_url}`; entradas em formato antigo (ou
+    /// corrompidas) contam como miss
+    fn parse(value: Option<String>) -> Option<Self> {
+        match value {
+            Some(value) if value == NOT_FOUND_SENTINEL => Some(CacheEntry::NotFound),
+            Some(value) if value == DELETED_SENTINEL => Some(CacheEntry::Deleted),
+            value => value
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .map(CacheEntry::Link),
+        }
+    }
 
-    // Usa o hash da secret_key como semente do RNG
-    let mut rng = ChaCha8Rng::from_seed(blake3::hash(secret_key.as_bytes()).into());
+    /// Resultado de `resolve_link` para um link vindo do cache
+    fn into_resolved(self) -> Result<Option<(CachedLink, bool)>, AppError> {
+        match self {
+            CacheEntry::Link(link) => Ok(Some((link, true))),
+            CacheEntry::NotFound => Ok(None),
+            CacheEntry::Deleted => Err(AppError::Deleted),
+        }
+    }
+}
 
-    // Embaralha o alfabeto sempre da mesma forma
-    alphabet.shuffle(&mut rng);
+/// Busca um link no cache; erros do Redis são tratados como cache miss
+async fn cache_get(redis_conn: &mut RedisConnection, short_url: &str) -> Option<CacheEntry> {
+    match redis_conn.get::<_, Option<String>>(cache_key(short_url)).await {
+        Ok(value) => CacheEntry::parse(value),
+        Err(e) => {
+            warn!("Redis cache error: {}", e);
+            None
+        }
+    }
+}
 
-    // Converte o ID para base62 (usando o alfabeto embaralhado)
-    let mut encoded = Vec::new();
-    while id > 0 {
-        let remainder = (id % 62) as usize;
-        encoded.push(alphabet[remainder]);
-        id /= 62;
+/// Marca um código inexistente no cache (`NOT_FOUND_SENTINEL` ou
+/// `DELETED_SENTINEL`). Criar o código depois sobrescreve a marca, já que a
+/// criação grava o link na mesma chave
+async fn cache_set_not_found(redis_conn: &mut RedisConnection, short_url: &str, sentinel: &str, ttl_secs: u64) {
+    if ttl_secs == 0 {
+        return;
+    }
+    let cache_result: redis::RedisResult<()> = redis_conn
+        .set_ex(cache_key(short_url), sentinel, ttl_secs)
+        .await;
+    if let Err(e) = cache_result {
+        warn!("Redis cache error: {}", e);
     }
+}
 
-    if encoded.is_empty() {
-        encoded.push(alphabet[0]);
+/// Grava um link no cache; falhas são apenas registradas
+async fn cache_set(
+    redis_conn: &mut RedisConnection,
+    short_url: &str,
+    link: &CachedLink,
+    ttl_secs: u64,
+) {
+    let json = match serde_json::to_string(link) {
+        Ok(json) => json,
+        Err(e) => {
+            error!("Cache serialization error: {}", e);
+            return;
+        }
+    };
+    let cache_result: redis::RedisResult<()> =
+        redis_conn.set_ex(cache_key(short_url), json, ttl_secs).await;
+    if let Err(e) = cache_result {
+        warn!("Redis cache error: {}", e);
     }
+}
 
-    encoded.iter().rev().collect::<String>()
+#[derive(Deserialize)]
+struct ShortenParams {
+    /// Valida e prevê o código sem gravar nada
+    dry_run: Option<bool>,
 }
 
-// POST /shorten
+// POST /shorten?dry_run=
 async fn create_shorten_url(
     Extension(state): Extension<Arc<AppState>>,
-    Json(payload): Json<Url>,
-) -> impl IntoResponse {
-    let long_url = payload.long_url;
-
-    // O clone é necessário para que `redis_conn` possa ser mutável para a chamada `incr`.
-    let mut redis_conn = state.redis.clone();
-    
-    // 1. Incrementa o contador global no Redis
-    let id: u64 = match redis_conn.incr("url_id", 1).await {
-        Ok(val) => val,
+    api_key: Option<Extension<ApiKeyId>>,
+    params: Result<Query<ShortenParams>, QueryRejection>,
+    headers: HeaderMap,
+    payload: Result<JsonOrForm<ShortenRequest>, AppError>,
+) -> Result<Response, AppError> {
+    let _timer = state.metrics.shorten_latency.start_timer();
+    state.read_only.ensure_writable()?;
+    let Query(params) = params.map_err(|e| AppError::BadRequest(e.body_text()))?;
+    // JSON ou formulário HTML, no mesmo `ShortenRequest`
+    let JsonOrForm(payload) = payload?;
+    // Identificador da API key que está criando o link (`None` sem autenticação)
+    let owner = api_key.map(|Extension(ApiKeyId(id))| id);
+
+    // Dry run não grava nada, então não há resposta a guardar por `Idempotency-Key`
+    let dry_run = params.dry_run.unwrap_or(false);
+    if dry_run {
+        return Ok(shorten(&state, owner, payload, true).await?.into_response());
+    }
+
+    let Some(key) = idempotency::key_from(&headers)? else {
+        return Ok(shorten(&state, owner, payload, false).await?.into_response());
+    };
+
+    // Retry com o mesmo `Idempotency-Key`: devolve a resposta original em vez de criar outro link
+    let request = serde_json::to_value(&payload)
+        .map_err(|e| AppError::Internal(format!("idempotency fingerprint: {}", e)))?;
+    let fingerprint = idempotency::fingerprint(&state.config.idempotency_key, &request);
+    let mut redis_conn = state.redis.get();
+    if let Some(replay) = idempotency::begin(&mut redis_conn, owner.as_deref(), &key, &fingerprint).await? {
+        return Ok(replay.into_response());
+    }
+
+    match shorten(&state, owner.clone(), payload, false).await {
+        Ok((status, Json(response))) => {
+            idempotency::complete(
+                &mut redis_conn,
+                owner.as_deref(),
+                &key,
+                &fingerprint,
+                status,
+                &response,
+                state.config.idempotency_ttl_secs,
+            )
+            .await;
+            Ok((status, Json(response)).into_response())
+        }
         Err(e) => {
-            eprintln!("Redis error: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, "Redis Error").into_response();
+            idempotency::abandon(&mut redis_conn, owner.as_deref(), &key).await;
+            Err(e)
+        }
+    }
+}
+
+/// Valida o corpo de `POST /shorten`, reunindo todos os campos inválidos.
+///
+/// Retorna os destinos normalizados. Regras que não dependem só do corpo
+/// (domínio bloqueado, alias reservado ou em uso) são verificadas depois.
+fn validate_shorten(
+    payload: &ShortenRequest,
+    max_url_len: usize,
+    schemes: &AllowedSchemes,
+    public_base_url: &str,
+) -> Result<ValidShorten, Vec<FieldError>> {
+    let mut errors = Vec::new();
+
+    let long_url = match normalize_long_url(&payload.long_url, max_url_len, schemes) {
+        Ok(long_url) if is_self_link(&long_url, public_base_url) => {
+            errors.push(FieldError::new("long_url", SELF_LINK_ERROR));
+            None
+        }
+        Ok(long_url) => Some(long_url),
+        Err(message) => {
+            errors.push(FieldError::new("long_url", message));
+            None
         }
     };
 
-    // 2. Ajusta o ID (começa com 14 milhões)
-    let id_adjusted = id + 14_000_000;
+    if payload.short_url.as_deref().is_some_and(|alias| !is_valid_alias(alias)) {
+        errors.push(FieldError::new("short_url", "invalid alias: use 3 to 32 letters, digits, '-' or '_'"));
+    }
 
-    // 3. Gera o short URL
-    let secret_key = std::env::var("SECRET_KEY").unwrap_or_else(|_| "default_secret".to_string());
-    let short_url = generate_short_url(&secret_key, id_adjusted);
+    if payload.ttl_secs.is_some_and(|ttl| ttl == 0 || ttl > MAX_TTL_SECS) {
+        errors.push(FieldError::new(
+            "ttl_secs",
+            format!("ttl_secs must be between 1 and {}", MAX_TTL_SECS),
+        ));
+    }
 
-    // 4. Salva no Cassandra
-    let query = "INSERT INTO urls (short_url, long_url) VALUES (?, ?)";
-    if let Err(e) = state
-        .cassandra
-        .query(query, (short_url.clone(), long_url.clone()))
-        .await
+    if payload.max_clicks == Some(0) || payload.max_clicks > Some(i64::MAX as u64) {
+        errors.push(FieldError::new("max_clicks", "max_clicks must be at least 1"));
+    }
+
+    if payload
+        .password
+        .as_ref()
+        .is_some_and(|password| password.is_empty() || password.len() > password::MAX_PASSWORD_LEN)
     {
-        eprintln!("Cassandra error: {}", e);
-        return (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response();
+        errors.push(FieldError::new(
+            "password",
+            format!("password must be between 1 and {} bytes", password::MAX_PASSWORD_LEN),
+        ));
+    }
+
+    let geo = match &payload.geo {
+        Some(raw) => match geo::normalize_destinations(raw, max_url_len, schemes, public_base_url) {
+            Ok(destinations) => Some(destinations),
+            Err(geo_errors) => {
+                errors.extend(geo_errors);
+                None
+            }
+        },
+        None => None,
+    };
+
+    let variants = match &payload.variants {
+        Some(raw) => match variants::normalize_variants(raw, max_url_len, schemes, public_base_url) {
+            Ok(variants) => Some(variants),
+            Err(variant_errors) => {
+                errors.extend(variant_errors);
+                None
+            }
+        },
+        None => None,
+    };
+    let tags = match &payload.tags {
+        Some(raw) => match tags::normalize_tags(raw) {
+            Ok(tags) => Some(tags).filter(|tags| !tags.is_empty()),
+            Err(tag_errors) => {
+                errors.extend(tag_errors);
+                None
+            }
+        },
+        None => None,
+    };
+    // Um destino por país e um sorteio ao mesmo tempo não teriam precedência clara
+    if payload.geo.is_some() && payload.variants.is_some() {
+        errors.push(FieldError::new("variants", "variants cannot be combined with geo"));
+    }
+
+    match long_url {
+        Some(long_url) if errors.is_empty() => Ok(ValidShorten {
+            long_url,
+            geo,
+            variants,
+            tags,
+        }),
+        _ => Err(errors),
+    }
+}
+
+/// Corpo de `POST /shorten` já validado e normalizado
+struct ValidShorten {
+    long_url: String,
+    geo: Option<GeoDestinations>,
+    variants: Option<Vec<Variant>>,
+    tags: Option<Vec<String>>,
+}
+
+/// Cria o link de `POST /shorten`: 201 para um link novo, 200 quando a URL já
+/// tinha sido encurtada pelo mesmo dono. Com `dry_run`, faz as mesmas
+/// verificações e responde 200 com o código previsto, sem gravar nada
+async fn shorten(
+    state: &Arc<AppState>,
+    owner: Option<String>,
+    payload: ShortenRequest,
+    dry_run: bool,
+) -> Result<(StatusCode, Json<Url>), AppError> {
+    // Todos os campos são validados de uma vez (422 com a lista de problemas)
+    let ValidShorten {
+        long_url,
+        geo,
+        variants,
+        tags,
+    } = validate_shorten(
+        &payload,
+        state.config.max_url_len,
+        &state.config.allowed_schemes,
+        &state.config.public_base_url,
+    )
+    .map_err(AppError::Validation)?;
+    let mut destinations = std::iter::once(&long_url)
+        .chain(geo.iter().flat_map(|geo| geo.values()))
+        .chain(variants.iter().flatten().map(|variant| &variant.url));
+    if let Some(blocked) = destinations.find(|url| state.config.blocked_domains.is_blocked(url)) {
+        warn!("Rejected blocked destination {}", blocked);
+        return Err(AppError::BlockedDomain);
     }
 
-    // 5. Retorna resposta
+    // Só deduplica links "simples": alias, expiração, limite de cliques, senha,
+    // destinos por país, variantes, tags ou tipo de redirect próprios pedem um código exclusivo
+    let dedup = payload.short_url.is_none()
+        && geo.is_none()
+        && variants.is_none()
+        && tags.is_none()
+        && payload.ttl_secs.is_none()
+        && payload.permanent.is_none()
+        && payload.max_clicks.is_none()
+        && payload.password.is_none()
+        && !payload.force_new.unwrap_or(false);
+
+    // 0. URL já encurtada pelo mesmo dono: devolve o código existente em vez de consumir um novo ID
+    if dedup {
+        let existing = dedup::find_existing(state, &long_url)
+            .await?
+            .filter(|existing| existing.owner == owner);
+        if let Some(existing) = existing {
+            let short_url = existing.short_url;
+            let created_at = existing.created_at;
+            let response = Url {
+                short_link: Some(state.config.short_link(&short_url)),
+                short_url: Some(short_url),
+                long_url,
+                created_at,
+                ttl_secs: None,
+                permanent: None,
+                max_clicks: None,
+                geo: None,
+                variants: None,
+                tags: None,
+                created: Some(false),
+                deduplicated: Some(true),
+                dry_run: dry_run.then_some(true),
+            };
+            return Ok((StatusCode::OK, Json(response)));
+        }
+    }
+
+    // Conexão do pool; precisa ser mutável para a chamada `incr`.
+    let mut redis_conn = state.redis.get();
+
+    let custom_alias = payload.short_url.is_some();
+    let short_url = match payload.short_url {
+        // Alias personalizado: usa o código informado, sem consumir o contador
+        Some(alias) => {
+            // Aliases não têm assinatura: no modo assinado seriam recusados no redirecionamento
+            if state.config.code_mode.is_signed() {
+                return Err(AppError::Validation(vec![FieldError::new(
+                    "short_url",
+                    "custom aliases are not accepted with CODE_MODE=signed",
+                )]));
+            }
+            if is_reserved(&alias) {
+                return Err(AppError::ReservedAlias);
+            }
+            canonical_code(&state.config, &alias)
+        }
+        // Dry run: prevê o próximo ID sem consumi-lo (pulando códigos reservados)
+        None if dry_run => {
+            let mut id = state.ids.peek(&mut redis_conn).await?;
+            loop {
+                let code = short_url_for_id(
+                    id,
+                    state.config.id_offset,
+                    state.config.id_limit,
+                    &state.config.code_mode,
+                    &state.config.code_seed,
+                    &state.config.code_alphabet,
+                    state.config.min_code_len,
+                )?;
+                if !is_reserved(&code) {
+                    break code;
+                }
+                id += 1;
+            }
+        }
+        None => loop {
+            // 1. Novo ID (contador global no Redis ou Snowflake local)
+            let id = state.ids.next(&mut redis_conn).await?;
+
+            // 2-3. Ajusta o ID e gera o short URL (pulando códigos que colidem com rotas)
+            let code = short_url_for_id(
+                id,
+                state.config.id_offset,
+                state.config.id_limit,
+                &state.config.code_mode,
+                &state.config.code_seed,
+                &state.config.code_alphabet,
+                state.config.min_code_len,
+            )?;
+            if !is_reserved(&code) {
+                break code;
+            }
+        },
+    };
+
+    // Dry run: as mesmas verificações da criação, sem gravar nada. Um alias já em
+    // uso responde 409; a cota não é reservada nem conferida
+    if dry_run {
+        if custom_alias {
+            let taken = state
+                .cassandra_breaker
+                .run(state.cassandra.execute(&state.statements.exists_url, (short_url.clone(),)))
+                .await?
+                .rows
+                .is_some_and(|rows| !rows.is_empty());
+            if taken {
+                return Err(AppError::Conflict);
+            }
+        }
+
+        info!("Dry run: would create '{}' -> {}", short_url, long_url);
+        let response = Url {
+            short_link: Some(state.config.short_link(&short_url)),
+            short_url: Some(short_url),
+            long_url,
+            created_at: None,
+            ttl_secs: payload.ttl_secs,
+            permanent: payload.permanent,
+            max_clicks: payload.max_clicks,
+            geo,
+            variants,
+            tags: tags.clone(),
+            created: Some(false),
+            deduplicated: Some(false),
+            dry_run: Some(true),
+        };
+        return Ok((StatusCode::OK, Json(response)));
+    }
+
+    // 4. Salva no Cassandra sem sobrescrever um short_url já existente
+    // (precisão de milissegundos, a mesma do tipo `timestamp` do CQL)
+    let created_at = Utc::now().trunc_subsecs(3);
+    // TTL 0 no Cassandra significa "sem expiração"
+    let ttl = payload.ttl_secs.unwrap_or(0) as i32;
+    let password_hash = payload
+        .password
+        .as_deref()
+        .map(|password| password::hash_password(&short_url, password));
+    // A cota é reservada antes do insert e devolvida se nenhum link novo for gravado
+    quota::reserve(state, &mut redis_conn, owner.as_deref(), 1).await?;
+    // Falhas passageiras são repetidas para não desperdiçar o ID: se uma tentativa
+    // com timeout chegou a gravar, a seguinte vê o mesmo mapeamento e segue como sucesso
+    let result = state
+        .cassandra_breaker
+        .run_retrying(|| {
+            state.cassandra.execute(
+                &state.statements.insert_url,
+                (
+                    short_url.clone(),
+                    long_url.clone(),
+                    to_cql_timestamp(created_at),
+                    payload.permanent,
+                    payload.max_clicks.map(|n| n as i64),
+                    password_hash.clone(),
+                    owner.clone(),
+                    tags.clone(),
+                    ttl,
+                ),
+            )
+        })
+        .await;
+    let result = match result {
+        Ok(result) => result,
+        Err(e) => {
+            quota::release(&mut redis_conn, owner.as_deref(), 1).await;
+            return Err(e);
+        }
+    };
+
+    match parse_insert_outcome(result) {
+        Some(InsertOutcome::Applied) => {}
+        // Código gerado já gravado com o mesmo destino e dono: uma tentativa anterior
+        // (com timeout) desta requisição gravou o link. Segue como sucesso e mantém a
+        // cota reservada, que é a desse link. Um alias existente é sempre 409, mesmo
+        // com o mesmo destino: ele pode pertencer a outro cliente
+        Some(InsertOutcome::AlreadyExists {
+            long_url: Some(existing_url),
+            owner: existing_owner,
+        }) if !custom_alias && existing_url == long_url && existing_owner == owner => {}
+        Some(InsertOutcome::AlreadyExists { .. }) => {
+            quota::release(&mut redis_conn, owner.as_deref(), 1).await;
+            warn!("Short URL collision: '{}' already exists", short_url);
+            return Err(AppError::Conflict);
+        }
+        None => {
+            quota::release(&mut redis_conn, owner.as_deref(), 1).await;
+            return Err(AppError::Internal(format!(
+                "unexpected LWT result for '{}'",
+                short_url
+            )));
+        }
+    }
+
+    // Destinos por país e variantes só depois do insert: gravá-los antes
+    // sobrescreveria os de um alias que já pertence a outro link. Sem eles o
+    // link não é criado: a linha acima é desta requisição (inclusive a gravada por
+    // uma tentativa anterior), então é removida e a cota devolvida
+    let mut extras = match (&geo, &variants) {
+        (Some(geo), _) => geo::store(state, &short_url, geo, ttl).await,
+        (_, Some(variants)) => variants::store(state, &short_url, variants, ttl).await,
+        (None, None) => Ok(()),
+    };
+    if let (Ok(()), Some(tags)) = (&extras, &tags) {
+        extras = tags::store(state, &short_url, tags, ttl).await;
+    }
+    if let Err(e) = extras {
+        if let Err(e) = state
+            .cassandra_breaker
+            .run(state.cassandra.execute(&state.statements.delete_url, (short_url.clone(),)))
+            .await
+        {
+            error!("Cassandra delete error for incomplete '{}': {}", short_url, e);
+        }
+        quota::release(&mut redis_conn, owner.as_deref(), 1).await;
+        return Err(e);
+    }
+
+    if dedup {
+        dedup::remember(state, &long_url, &short_url).await;
+    }
+
+    // 5. Popula o cache para que o link recém-criado já esteja quente
+    let link = CachedLink {
+        long_url: long_url.clone(),
+        permanent: payload.permanent,
+        max_clicks: payload.max_clicks,
+        password_hash,
+        expires_at: expires_at(payload.ttl_secs),
+        geo: geo.clone(),
+        variants: variants.clone(),
+    };
+    let cache_ttl = effective_cache_ttl(state.config.cache_ttl_secs, payload.ttl_secs);
+    cache_set(&mut redis_conn, &short_url, &link, cache_ttl).await;
+
+    info!(api_key = owner.as_deref(), "Created '{}' -> {}", short_url, long_url);
+    audit::record(state, audit::Action::Create, &short_url, owner.as_deref(), long_url.clone());
+
+    // 6. Retorna resposta
     let response = Url {
+        short_link: Some(state.config.short_link(&short_url)),
         short_url: Some(short_url),
         long_url,
+        created_at: Some(created_at),
+        ttl_secs: payload.ttl_secs,
+        permanent: payload.permanent,
+        max_clicks: payload.max_clicks,
+        geo,
+        variants,
+        tags,
+        created: Some(true),
+        deduplicated: Some(false),
+        dry_run: None,
+    };
+
+    state.metrics.shortens.fetch_add(1, Ordering::Relaxed);
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// Incrementa o contador de cliques em segundo plano, sem atrasar o redirecionamento
+fn record_click(state: &Arc<AppState>, short_url: &str) {
+    let state = Arc::clone(state);
+    let short_url = short_url.to_string();
+    let background = state.background.clone();
+    background.spawn(async move {
+        if let Err(e) = state
+            .cassandra_breaker
+            .run(state.cassandra.execute(&state.statements.increment_clicks, (short_url,)))
+            .await
+        {
+            error!("Cassandra click counter error: {}", e);
+        }
+    });
+}
+
+/// Remove um link que esgotou `max_clicks` (no último uso permitido ou em um acesso
+/// além do limite): daqui em diante ele responde 404
+async fn expire_exhausted_link(
+    state: &AppState,
+    redis_conn: &mut RedisConnection,
+    short_url: &str,
+) {
+    // Dono e tags, para devolver a vaga na cota e limpar `urls_by_tag` como em
+    // `delete_short_url`
+    let row = state
+        .cassandra_breaker
+        .run(state.cassandra.execute(&state.statements.select_url, (short_url.to_string(),)))
+        .await
+        .and_then(|result| {
+            result
+                .maybe_first_row_typed::<UrlRow>()
+                .map_err(|e| AppError::Internal(format!("urls row: {}", e)))
+        });
+    let (owner, tags) = match row {
+        Ok(row) => row.map_or((None, None), |row| (row.owner, row.tags)),
+        Err(e) => {
+            error!("Cassandra select error for exhausted '{}': {}", short_url, e);
+            (None, None)
+        }
+    };
+
+    // O IF EXISTS garante que só uma das requisições que esgotaram o link devolva a cota
+    let deleted = match state
+        .cassandra_breaker
+        .run(state.cassandra.execute(&state.statements.delete_url, (short_url.to_string(),)))
+        .await
+    {
+        Ok(result) => lwt_applied(&result) == Some(true),
+        Err(e) => {
+            error!("Cassandra delete error for exhausted '{}': {}", short_url, e);
+            false
+        }
     };
+    geo::delete(state, short_url).await;
+    variants::delete(state, short_url).await;
+    tags::delete(state, short_url, tags.as_deref().unwrap_or_default()).await;
+    if deleted {
+        quota::release(redis_conn, owner.as_deref(), 1).await;
+    }
+
+    // O contador não é apagado na hora: uma requisição que leu o cache pouco
+    // antes ainda o encontra esgotado, em vez de recomeçar do zero
+    let cache_result: redis::RedisResult<()> = redis::pipe()
+        .del(cache_key(short_url))
+        .ignore()
+        .expire(click_limit::counter_key(short_url), click_limit::EXHAUSTED_COUNTER_TTL_SECS)
+        .ignore()
+        .query_async(redis_conn)
+        .await;
+    if let Err(e) = cache_result {
+        warn!("Redis cache error: {}", e);
+    }
+    info!("Link '{}' reached its click limit", short_url);
+}
+
+/// Busca um link pelo código: cache primeiro e, se faltar (ou o Redis falhar), o
+/// Cassandra, repopulando o cache. `None` se o código não existir e
+/// `AppError::Deleted` se ele foi removido; o `bool` indica se o link veio do cache
+async fn resolve_link(
+    state: &AppState,
+    redis_conn: &mut RedisConnection,
+    short: &str,
+) -> Result<Option<(CachedLink, bool)>, AppError> {
+    // 1. Tenta o cache primeiro
+    if let Some(entry) = cache_get(redis_conn, short).await {
+        state.metrics.cache_hits.fetch_add(1, Ordering::Relaxed);
+        if !matches!(entry, CacheEntry::Link(_)) {
+            state.metrics.negative_cache_hits.fetch_add(1, Ordering::Relaxed);
+        }
+        return entry.into_resolved();
+    }
+    state.metrics.cache_misses.fetch_add(1, Ordering::Relaxed);
 
-    (StatusCode::CREATED, Json(response)).into_response()
+    // 2. Cache miss: só uma requisição por código consulta o Cassandra; as demais
+    // esperam ela repopular o cache (links virais que acabaram de expirar)
+    let lock = stampede::acquire(redis_conn, short).await;
+    if lock.is_none() {
+        state.metrics.stampede_waits.fetch_add(1, Ordering::Relaxed);
+        if let Some(entry) = stampede::wait_for_fill(redis_conn, short).await {
+            return entry.into_resolved();
+        }
+    }
+
+    // 3. Cassandra e repopulação do cache. Só um cache miss depende do Cassandra:
+    // com o cluster fora do ar, links em cache continuam redirecionando no passo 1
+    let loaded = load_link(state, redis_conn, short).await;
+    if let Some(lock) = lock {
+        lock.release(redis_conn).await;
+    }
+    let loaded = loaded.map_err(|e| unavailable_on_outage(e, state.config.circuit_cooldown_secs))?;
+    Ok(loaded.map(|link| (link, false)))
+}
+
+/// Cassandra inacessível ou sobrecarregado em um cache miss: 503 com `Retry-After`,
+/// como com o circuit breaker aberto, em vez de 500/504
+fn unavailable_on_outage(e: AppError, retry_after: u64) -> AppError {
+    match e {
+        AppError::Cassandra(query_error) if circuit::is_overload(&query_error) => {
+            warn!("Cassandra unavailable on a cache miss: {}", query_error);
+            AppError::Unavailable { retry_after: retry_after.max(1) }
+        }
+        e => e,
+    }
+}
+
+/// Lê um link do Cassandra (com destinos por país e variantes, em paralelo) e o
+/// grava no cache. `None` se o código não existir e `AppError::Deleted` se ele foi
+/// removido; nos dois casos a resposta fica no cache negativo
+async fn load_link(
+    state: &AppState,
+    redis_conn: &mut RedisConnection,
+    short: &str,
+) -> Result<Option<CachedLink>, AppError> {
+    let (result, geo, variants) = futures::try_join!(
+        state
+            .cassandra_breaker
+            .run(state.cassandra.execute(&state.statements.select_url, (short.to_string(),))),
+        geo::load(state, short),
+        variants::load(state, short),
+    )?;
+    let row = match result.single_row_typed::<UrlRow>() {
+        Ok(row) => row,
+        Err(_) => {
+            // Códigos removidos de propósito respondem 410; os demais, 404. A
+            // resposta fica no cache para que acessos repetidos não voltem ao Cassandra
+            let deleted = tombstone::exists(state, short).await?;
+            let sentinel = if deleted { DELETED_SENTINEL } else { NOT_FOUND_SENTINEL };
+            cache_set_not_found(redis_conn, short, sentinel, state.config.negative_cache_ttl_secs).await;
+            return if deleted { Err(AppError::Deleted) } else { Ok(None) };
+        }
+    };
+
+    // Repopula o cache com a URL resolvida
+    let link = CachedLink {
+        long_url: row.long_url,
+        permanent: row.permanent,
+        max_clicks: row.max_clicks.map(|n| n.max(0) as u64),
+        password_hash: row.password_hash,
+        expires_at: expires_at(row.ttl.map(|ttl| ttl.max(0) as u64)),
+        geo,
+        variants,
+    };
+    let cache_ttl = effective_cache_ttl(
+        state.config.cache_ttl_secs,
+        row.ttl.map(|ttl| ttl.max(0) as u64),
+    );
+    cache_set(redis_conn, short, &link, cache_ttl).await;
+    Ok(Some(link))
+}
+
+/// Intervalo mínimo entre duas gravações de `last_accessed` do mesmo link (segundos)
+const ACCESS_WRITE_INTERVAL_SECS: u64 = 60;
+
+/// Atualiza `last_accessed` em segundo plano, no máximo uma vez por minuto por código:
+/// a chave `accessed:{short_url}` no Redis funciona como trava (`SET NX EX`)
+fn record_access(state: &Arc<AppState>, short_url: &str, link_ttl: Option<u64>, cache_hit: bool) {
+    let state = Arc::clone(state);
+    let short_url = short_url.to_string();
+    let background = state.background.clone();
+    background.spawn(async move {
+        let mut redis_conn = state.redis.get();
+        let gate: redis::RedisResult<Option<String>> = redis::cmd("SET")
+            .arg(format!("accessed:{}", short_url))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(ACCESS_WRITE_INTERVAL_SECS)
+            .query_async(&mut redis_conn)
+            .await;
+        match gate {
+            Ok(Some(_)) => {}
+            // Já gravado há menos de um minuto
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Redis access gate error: {}", e);
+                return;
+            }
+        }
+
+        // TTL 0 no Cassandra significa "sem expiração"
+        let ttl = link_ttl.unwrap_or(0) as i32;
+        if let Err(e) = state
+            .cassandra_breaker
+            .run(state.cassandra.execute(
+                &state.statements.touch_url,
+                (ttl, to_cql_timestamp(Utc::now()), cache_hit, short_url),
+            ))
+            .await
+        {
+            error!("Cassandra last_accessed error: {}", e);
+        }
+    });
 }
 
 // GET /:short_url
 async fn redirect_to_long_url(
     Path(short): Path<String>,
+    RawQuery(query): RawQuery,
+    Extension(state): Extension<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    serve_short_link(&state, short, query, peer, &headers, None).await
+}
+
+#[derive(Deserialize)]
+struct PasswordForm {
+    pw: String,
+}
+
+// POST /:short_url (formulário da página de senha)
+async fn unlock_short_link(
+    Path(short): Path<String>,
+    RawQuery(query): RawQuery,
+    Extension(state): Extension<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Form(form): Form<PasswordForm>,
+) -> Result<Response, AppError> {
+    serve_short_link(&state, short, query, peer, &headers, Some(form.pw)).await
+}
+
+/// Busca o link de um código já normalizado, pelo código canônico. Retorna o
+/// código encontrado, o link e se ele veio do cache
+async fn find_short_link(
+    state: &AppState,
+    redis_conn: &mut RedisConnection,
+    short: &str,
+) -> Result<Option<(String, CachedLink, bool)>, AppError> {
+    // Códigos vazios (ex: `/%20`), rotas reservadas e códigos que não decodificam
+    // nem são aliases válidos não existem: respondem 404 sem consultar o banco.
+    // Com CODE_MODE=signed não há aliases, e assinaturas inválidas param aqui
+    let canonical = canonical_code(&state.config, short);
+    let code_mode = &state.config.code_mode;
+    if short.is_empty()
+        || is_reserved(short)
+        || (decode_code(code_mode, &state.config.code_seed, &state.config.code_alphabet, &canonical).is_none()
+            && (code_mode.is_signed() || !is_valid_alias(short)))
+    {
+        return Ok(None);
+    }
+
+    let resolved = resolve_link(state, redis_conn, &canonical).await?;
+    if let Some((link, cache_hit)) = resolved {
+        return Ok(Some((canonical, link, cache_hit)));
+    }
+    // Com CASE_INSENSITIVE, links criados antes do modo podem ter maiúsculas no código
+    if canonical != short {
+        if let Some((link, cache_hit)) = resolve_link(state, redis_conn, short).await? {
+            return Ok(Some((short.to_string(), link, cache_hit)));
+        }
+    }
+    Ok(None)
+}
+
+/// Resolve um short link e responde com o redirecionamento, a pré-visualização
+/// ou a página de senha. `form_password` vem do formulário (POST)
+async fn serve_short_link(
+    state: &Arc<AppState>,
+    short: String,
+    query: Option<String>,
+    peer: SocketAddr,
+    headers: &HeaderMap,
+    form_password: Option<String>,
+) -> Result<Response, AppError> {
+    let _timer = state.metrics.redirect_latency.start_timer();
+    let short = normalize_code(&short).to_string();
+
+    // Pré-visualização: sufixo `+` no código (estilo bit.ly) ou `?preview` na query
+    let (short, preview_suffix) = match short.strip_suffix('+') {
+        Some(code) => (code.to_string(), true),
+        None => (short, false),
+    };
+    let (preview_query, query) = take_preview_flag(query.as_deref());
+    let preview = preview_suffix || preview_query;
+    // A senha nunca é repassada ao destino
+    let (query_password, query) = take_password(query.as_deref());
+    let from_form = form_password.is_some();
+    let submitted_password = form_password.or(query_password);
+
+    // 1-3. Cache, Cassandra e repopulação do cache
+    let mut redis_conn = state.redis.get();
+    let Some((short, link, cache_hit)) = find_short_link(state, &mut redis_conn, &short).await? else {
+        state.metrics.redirect_not_found.fetch_add(1, Ordering::Relaxed);
+        return Err(AppError::NotFound);
+    };
+    // IP (anonimizado conforme LOG_CLIENT_IP) e Referer só entram no log se configurados
+    let client_ip = state
+        .config
+        .log_client_ip
+        .render(client_ip::client_ip(headers, peer, state.config.trust_forwarded_for));
+    let referer = state.config.log_referer.then(|| logging::referer(headers)).flatten();
+    info!(
+        client_ip = client_ip.as_deref(),
+        referer = referer.as_deref(),
+        "Redirecting '{}' -> {}",
+        short,
+        link.long_url
+    );
+
+    // 4. Links protegidos: sem a senha certa, nem o destino nem a pré-visualização são revelados
+    if let Some(password_hash) = &link.password_hash {
+        match &submitted_password {
+            Some(password) if password::verify_password(&short, password, password_hash) => {}
+            Some(_) => {
+                warn!("Wrong password for '{}'", short);
+                return Ok(password_prompt(&short, true));
+            }
+            None => return Ok(password_prompt(&short, false)),
+        }
+    }
+
+    // Destino do país do cliente ou variante A/B (a do cookie ou uma sorteada),
+    // se o link tiver; senão a `long_url`
+    let country = geo::client_country(headers, &state.config.geo_country_header);
+    let variant = link
+        .variants
+        .as_deref()
+        .map(|variants| variants::choose(headers, &short, variants));
+    let base_url = match variant {
+        Some((variant, _)) => &variant.url,
+        None => geo::pick(link.geo.as_ref(), country.as_deref()).unwrap_or(&link.long_url),
+    };
+    // Destino que é outro short link do serviço: com MAX_CHAIN_HOPS, vai direto ao final
+    let base_url = chain::follow(state, &mut redis_conn, &short, base_url).await?;
+
+    // Parâmetros padrão primeiro (só os que faltam), depois a query recebida
+    let long_url = append_default_params(
+        &base_url,
+        &state.config.default_query_params,
+        query.as_deref(),
+    );
+    let destination = append_query(&long_url, query.as_deref());
+
+    // Bots de pré-visualização (Slack, Twitter, ...) não gastam os usos de um link
+    // com limite de cliques: recebem uma página sem o destino, em vez do redirecionamento
+    let user_agent = headers.get(header::USER_AGENT).and_then(|ua| ua.to_str().ok());
+    let is_bot = state.config.bots.is_bot(user_agent);
+    if is_bot && link.max_clicks.is_some() {
+        return Ok(limited_link_page(&short));
+    }
+
+    // 5. Pré-visualização não redireciona nem conta como clique
+    if preview {
+        return Ok(preview_page(&short, &destination));
+    }
+
+    // 6. Links com limite de cliques: o contador no Redis decide quem ainda passa.
+    // Um erro do Redis falha a requisição em vez de liberar cliques além do limite
+    let link_ttl = link.remaining_ttl();
+    if let Some(max_clicks) = link.max_clicks {
+        let uses = click_limit::consume(&mut redis_conn, &short, link_ttl).await?;
+        if uses > max_clicks {
+            expire_exhausted_link(state, &mut redis_conn, &short).await;
+            return Err(AppError::Gone);
+        }
+        // Último uso permitido: o link sai do Cassandra em segundo plano, sem esperar
+        // o próximo acesso, para que um flush ou failover do Redis (que perde o
+        // contador) não reabra um link de uso único já usado
+        if uses == max_clicks {
+            let state = Arc::clone(state);
+            let short = short.clone();
+            let background = state.background.clone();
+            background.spawn(async move {
+                let mut redis_conn = state.redis.get();
+                expire_exhausted_link(&state, &mut redis_conn, &short).await;
+            });
+        }
+    }
+
+    // 7. Os demais bots são redirecionados, mas não contam como clique
+    state.metrics.redirects.fetch_add(1, Ordering::Relaxed);
+    if is_bot {
+        state.metrics.bot_redirects.fetch_add(1, Ordering::Relaxed);
+    } else {
+        record_click(state, &short);
+        if let Some((variant, _)) = variant {
+            variants::record_click(state, &short, &variant.name);
+        }
+        let variant_name = variant.map(|(variant, _)| variant.name.as_str());
+        click_events::record(state, &short, variant_name, country.as_deref());
+    }
+    record_access(state, &short, link_ttl, cache_hit);
+
+    // Links com senha, limite de cliques, destinos por país ou variantes nunca ficam
+    // no cache do navegador ou da CDN (que serviria o mesmo destino a todos)
+    let permanent = link.permanent.unwrap_or(state.config.redirect_permanent);
+    let cache = RedirectCache::for_link(
+        permanent,
+        link.password_hash.is_some()
+            || link.max_clicks.is_some()
+            || link.geo.is_some()
+            || link.variants.is_some(),
+        state.config.redirect_cache_max_age,
+        link_ttl,
+    );
+
+    // Depois do POST do formulário, 303 faz o navegador seguir com um GET
+    let mut response = if from_form {
+        redirect_with_status(StatusCode::SEE_OTHER, &destination, cache)
+    } else {
+        redirect_response(&destination, permanent, cache)
+    };
+    // Variante recém-sorteada: o cookie mantém o mesmo destino nas próximas visitas
+    if let Some((variant, true)) = variant {
+        if let Some(cookie) = variants::sticky_cookie(&short, variant) {
+            response.headers_mut().insert(header::SET_COOKIE, cookie);
+        }
+    }
+    Ok(response)
+}
+
+#[derive(Serialize)]
+struct Availability {
+    available: bool,
+    /// Motivo da indisponibilidade: `invalid`, `reserved`, `taken` ou
+    /// `aliases_disabled` (`CODE_MODE=signed`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<&'static str>,
+}
+
+impl Availability {
+    fn unavailable(reason: &'static str) -> Self {
+        Availability {
+            available: false,
+            reason: Some(reason),
+        }
+    }
+}
+
+// GET /api/urls/:short_url/available
+async fn check_alias_available(
+    Path(alias): Path<String>,
     Extension(state): Extension<Arc<AppState>>,
-) -> impl IntoResponse {
-    let query = "SELECT long_url FROM urls WHERE short_url = ?";
-
-    match state.cassandra.query(query, (short.clone(),)).await {
-        Ok(result) => {
-            if let Ok(row) = result.single_row_typed::<UrlRow>() {
-                println!("Redirecting '{}' -> {}", short, row.long_url);
-                return Redirect::to(&row.long_url).into_response();
+) -> Result<Json<Availability>, AppError> {
+    // 1. Mesmas regras da criação, sem tocar no banco
+    if state.config.code_mode.is_signed() {
+        return Ok(Json(Availability::unavailable("aliases_disabled")));
+    }
+    if !is_valid_alias(&alias) {
+        return Ok(Json(Availability::unavailable("invalid")));
+    }
+    if is_reserved(&alias) {
+        return Ok(Json(Availability::unavailable("reserved")));
+    }
+    let alias = canonical_code(&state.config, &alias);
+
+    // 2. O cache (inclusive o negativo) responde sem consultar o Cassandra
+    let mut redis_conn = state.redis.get();
+    let taken = match cache_get(&mut redis_conn, &alias).await {
+        Some(CacheEntry::Link(_)) => true,
+        Some(CacheEntry::NotFound) | Some(CacheEntry::Deleted) => false,
+        None => {
+            // 3. Consulta só a chave, com LIMIT 1
+            let exists = state
+                .cassandra_breaker
+                .run(state.cassandra.execute(&state.statements.exists_url, (alias.clone(),)))
+                .await?
+                .rows
+                .is_some_and(|rows| !rows.is_empty());
+            if !exists {
+                cache_set_not_found(
+                    &mut redis_conn,
+                    &alias,
+                    NOT_FOUND_SENTINEL,
+                    state.config.negative_cache_ttl_secs,
+                )
+                .await;
             }
-            (StatusCode::NOT_FOUND, "URL not found").into_response()
+            exists
+        }
+    };
+
+    Ok(Json(if taken {
+        Availability::unavailable("taken")
+    } else {
+        Availability {
+            available: true,
+            reason: None,
+        }
+    }))
+}
+
+#[derive(FromRow)]
+struct UrlDetailsRow {
+    long_url: String,
+    ttl: Option<i32>,
+    permanent: Option<bool>,
+    created_at: Option<Timestamp>,
+    max_clicks: Option<i64>,
+    password_hash: Option<String>,
+    last_accessed: Option<Timestamp>,
+    owner: Option<String>,
+    tags: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+struct UrlDetails {
+    short_url: String,
+    short_link: String,
+    long_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_accessed: Option<DateTime<Utc>>,
+    clicks: i64,
+    /// Só indica se há senha; o hash nunca sai do banco
+    password_protected: bool,
+    /// API key que criou o link; ausente em criações sem autenticação
+    #[serde(skip_serializing_if = "Option::is_none")]
+    owner: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    permanent: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_clicks: Option<u64>,
+    /// TTL restante (segundos) e instante de expiração; ausentes se o link não expira
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ttl_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_at: Option<DateTime<Utc>>,
+}
+
+// GET /api/urls/:short_url
+async fn get_url_details(
+    Path(short): Path<String>,
+    Extension(state): Extension<Arc<AppState>>,
+) -> Result<Json<UrlDetails>, AppError> {
+    let short = stored_code(&state, short).await?;
+    // 1. Linha do link e contador de cliques, em paralelo
+    let (row, clicks) = futures::try_join!(
+        state
+            .cassandra_breaker
+            .run(state.cassandra.execute(&state.statements.select_url_details, (short.clone(),))),
+        state
+            .cassandra_breaker
+            .run(state.cassandra.execute(&state.statements.select_clicks, (short.clone(),))),
+    )?;
+
+    // 2. Sem a linha principal o link não existe (ou já expirou)
+    let row = row
+        .maybe_first_row_typed::<UrlDetailsRow>()
+        .map_err(|e| AppError::Internal(format!("url row: {}", e)))?
+        .ok_or(AppError::NotFound)?;
+    let clicks = clicks
+        .maybe_first_row_typed::<(Counter,)>()
+        .map_err(|e| AppError::Internal(format!("stats row: {}", e)))?
+        .map_or(0, |(Counter(hits),)| hits);
+
+    let ttl_secs = row.ttl.filter(|ttl| *ttl > 0).map(|ttl| ttl as u64);
+    Ok(Json(UrlDetails {
+        short_link: state.config.short_link(&short),
+        short_url: short,
+        long_url: row.long_url,
+        created_at: row.created_at.map(from_cql_timestamp),
+        last_accessed: row.last_accessed.map(from_cql_timestamp),
+        clicks,
+        password_protected: row.password_hash.is_some(),
+        owner: row.owner,
+        tags: row.tags,
+        permanent: row.permanent,
+        max_clicks: row.max_clicks.map(|n| n.max(0) as u64),
+        ttl_secs,
+        expires_at: expires_at(ttl_secs).and_then(|at| DateTime::from_timestamp(at, 0)),
+    }))
+}
+
+#[derive(Deserialize)]
+struct UpdateUrl {
+    long_url: String,
+}
+
+// PUT /api/urls/:short_url
+async fn update_short_url(
+    Path(short): Path<String>,
+    Extension(state): Extension<Arc<AppState>>,
+    api_key: Option<Extension<ApiKeyId>>,
+    payload: Result<Json<UpdateUrl>, JsonRejection>,
+) -> Result<Json<Url>, AppError> {
+    state.read_only.ensure_writable()?;
+    let Json(payload) = payload?;
+    let short = stored_code(&state, short).await?;
+
+    // 1. Valida o novo destino com as mesmas regras da criação
+    let long_url = normalize_long_url(
+        &payload.long_url,
+        state.config.max_url_len,
+        &state.config.allowed_schemes,
+    )
+    .map_err(AppError::BadRequest)?;
+    if state.config.blocked_domains.is_blocked(&long_url) {
+        warn!("Rejected blocked destination {}", long_url);
+        return Err(AppError::BlockedDomain);
+    }
+    // Com MAX_CHAIN_HOPS > 1 o redirecionamento resolve cadeias (e recusa loops com 508)
+    if state.config.max_chain_hops <= 1 && is_self_link(&long_url, &state.config.public_base_url) {
+        return Err(AppError::BadRequest(SELF_LINK_ERROR.to_string()));
+    }
+
+    // 2. Lê o link atual para preservar o TTL restante e devolver os demais campos
+    let row = state
+        .cassandra_breaker
+        .run(state.cassandra.execute(&state.statements.select_url, (short.clone(),)))
+        .await?
+        .single_row_typed::<UrlRow>()
+        .map_err(|_| AppError::NotFound)?;
+    let ttl = row.ttl.unwrap_or(0).max(0);
+
+    // 3. Atualiza só se o código ainda existir (pode ter expirado ou sido removido)
+    let result = state
+        .cassandra_breaker
+        .run(state.cassandra.execute(&state.statements.update_url, (ttl, long_url.clone(), short.clone())))
+        .await?;
+
+    match lwt_applied(&result) {
+        Some(true) => {}
+        Some(false) => return Err(AppError::NotFound),
+        None => {
+            return Err(AppError::Internal(format!(
+                "unexpected LWT result for '{}'",
+                short
+            )));
+        }
+    }
+
+    // 4. Invalida o cache para que o próximo acesso já use o novo destino
+    let mut redis_conn = state.redis.get();
+    let cache_result: redis::RedisResult<()> = redis_conn.del(cache_key(&short)).await;
+    if let Err(e) = cache_result {
+        warn!("Redis cache error: {}", e);
+    }
+    if row.long_url != long_url {
+        dedup::reindex(&state, &row.long_url, &long_url, &short).await;
+    }
+
+    info!("Updated '{}' -> {}", short, long_url);
+    audit::record(
+        &state,
+        audit::Action::Update,
+        &short,
+        api_key.as_ref().map(|Extension(ApiKeyId(id))| id.as_str()),
+        format!("{} -> {}", row.long_url, long_url),
+    );
+    Ok(Json(Url {
+        short_link: Some(state.config.short_link(&short)),
+        short_url: Some(short),
+        long_url,
+        created_at: row.created_at.map(from_cql_timestamp),
+        ttl_secs: None,
+        permanent: row.permanent,
+        max_clicks: row.max_clicks.map(|n| n.max(0) as u64),
+        geo: None,
+        variants: None,
+        tags: None,
+        created: None,
+        deduplicated: None,
+        dry_run: None,
+    }))
+}
+
+// DELETE /:short_url
+async fn delete_short_url(
+    Path(short): Path<String>,
+    Extension(state): Extension<Arc<AppState>>,
+    api_key: Option<Extension<ApiKeyId>>,
+) -> Result<impl IntoResponse, AppError> {
+    state.read_only.ensure_writable()?;
+    let short = stored_code(&state, short).await?;
+
+    // 1. Dono do link, para devolver a vaga na cota depois da remoção, e o
+    // destino, para a trilha de auditoria
+    let row = state
+        .cassandra_breaker
+        .run(state.cassandra.execute(&state.statements.select_url, (short.clone(),)))
+        .await?
+        .maybe_first_row_typed::<UrlRow>()
+        .map_err(|e| AppError::Internal(format!("urls row: {}", e)))?;
+    let (long_url, owner, tags) =
+        row.map_or((None, None, None), |row| (Some(row.long_url), row.owner, row.tags));
+
+    // 2. Remove do Cassandra; o IF EXISTS informa se o código existia
+    let result = state
+        .cassandra_breaker
+        .run(state.cassandra.execute(&state.statements.delete_url, (short.clone(),)))
+        .await?;
+
+    match lwt_applied(&result) {
+        Some(true) => {}
+        Some(false) => return Err(AppError::NotFound),
+        None => {
+            return Err(AppError::Internal(format!(
+                "unexpected LWT result for '{}'",
+                short
+            )));
+        }
+    }
+
+    geo::delete(&state, &short).await;
+    variants::delete(&state, &short).await;
+    tags::delete(&state, &short, tags.as_deref().unwrap_or_default()).await;
+    // O redirecionamento passa a responder 410 em vez de 404
+    tombstone::record(&state, &short).await;
+
+    // 3. Remove do cache (e o contador de cliques, se houver) para que o link
+    // pare de redirecionar imediatamente
+    let mut redis_conn = state.redis.get();
+    let cache_result: redis::RedisResult<()> = redis_conn
+        .del(&[cache_key(&short), click_limit::counter_key(&short)])
+        .await;
+    if let Err(e) = cache_result {
+        warn!("Redis cache error: {}", e);
+    }
+
+    quota::release(&mut redis_conn, owner.as_deref(), 1).await;
+
+    info!("Deleted '{}'", short);
+    audit::record(
+        &state,
+        audit::Action::Delete,
+        &short,
+        api_key.as_ref().map(|Extension(ApiKeyId(id))| id.as_str()),
+        long_url.unwrap_or_default(),
+    );
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct ResolveParams {
+    pw: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ResolvedLink {
+    short_url: String,
+    long_url: String,
+}
+
+/// ETag da resolução: muda quando o destino muda (ex: `PUT /api/urls/:short_url`)
+fn resolve_etag(short_url: &str, long_url: &str) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(short_url.as_bytes()).update(b"\n").update(long_url.as_bytes());
+    format!("\"{}\"", &hasher.finalize().to_hex()[..32])
+}
+
+/// Indica se o `If-None-Match` do cliente cobre o ETag atual (comparação fraca, `*` casa com tudo)
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
+// GET /api/resolve/:short_url
+async fn resolve_short_url(
+    Path(short): Path<String>,
+    params: Result<Query<ResolveParams>, QueryRejection>,
+    headers: HeaderMap,
+    Extension(state): Extension<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    let Query(params) = params.map_err(|e| AppError::BadRequest(e.body_text()))?;
+    state.metrics.resolves.fetch_add(1, Ordering::Relaxed);
+
+    // 1. Mesma busca do redirecionamento (cache, Cassandra, cache negativo)
+    let mut redis_conn = state.redis.get();
+    let (short_url, link, _) = find_short_link(&state, &mut redis_conn, normalize_code(&short))
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    // 2. Links com senha só revelam o destino com a senha (`?pw=`)
+    if let Some(password_hash) = &link.password_hash {
+        match &params.pw {
+            Some(password) if password::verify_password(&short_url, password, password_hash) => {}
+            _ => return Err(AppError::PasswordRequired),
+        }
+    }
+
+    // 3. Cliente com a resolução atual no cache: 304 sem corpo
+    let etag = resolve_etag(&short_url, &link.long_url);
+    let if_none_match = headers.get(header::IF_NONE_MATCH).and_then(|value| value.to_str().ok());
+    if if_none_match.is_some_and(|value| etag_matches(value, &etag)) {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+    }
+
+    // 4. Sem clique, limite de cliques ou destino por país/variante: só a `long_url`
+    let resolved = ResolvedLink {
+        short_url,
+        long_url: link.long_url,
+    };
+    Ok(([(header::ETAG, etag)], Json(resolved)).into_response())
+}
+
+#[derive(Deserialize)]
+struct LookupParams {
+    url: String,
+}
+
+// GET /api/lookup?url=
+async fn lookup_short_url(
+    params: Result<Query<LookupParams>, QueryRejection>,
+    Extension(state): Extension<Arc<AppState>>,
+) -> Result<Json<Url>, AppError> {
+    let Query(params) = params.map_err(|e| AppError::BadRequest(e.body_text()))?;
+
+    // Normaliza como na criação, para chegar ao mesmo hash do índice reverso
+    let long_url = normalize_long_url(&params.url, state.config.max_url_len, &state.config.allowed_schemes)
+        .map_err(AppError::BadRequest)?;
+
+    let dedup::ExistingLink {
+        short_url, created_at, ..
+    } = dedup::find_existing(&state, &long_url)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    Ok(Json(Url {
+        short_link: Some(state.config.short_link(&short_url)),
+        short_url: Some(short_url),
+        long_url,
+        created_at,
+        ttl_secs: None,
+        permanent: None,
+        max_clicks: None,
+        geo: None,
+        variants: None,
+        tags: None,
+        created: None,
+        deduplicated: None,
+        dry_run: None,
+    }))
+}
+
+/// Tamanho máximo de cada módulo do QR Code, em pixels
+const MAX_QR_MODULE_PX: u32 = 20;
+
+#[derive(Deserialize)]
+struct QrParams {
+    format: Option<String>,
+    size: Option<u32>,
+}
+
+// GET /api/urls/:short_url/qr
+async fn get_url_qr(
+    Path(short): Path<String>,
+    params: Result<Query<QrParams>, QueryRejection>,
+    Extension(state): Extension<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    let short = stored_code(&state, short).await?;
+    let Query(params) = params.map_err(|e| AppError::BadRequest(e.body_text()))?;
+
+    // 1. Valida os parâmetros
+    let module_px = params.size.unwrap_or(8);
+    if module_px == 0 || module_px > MAX_QR_MODULE_PX {
+        return Err(AppError::BadRequest(format!(
+            "size must be between 1 and {}",
+            MAX_QR_MODULE_PX
+        )));
+    }
+    let svg = match params.format.as_deref() {
+        None | Some("png") => false,
+        Some("svg") => true,
+        Some(other) => {
+            return Err(AppError::BadRequest(format!("unsupported format '{}'", other)));
         }
+    };
+
+    // 2. Só gera QR Codes para links que existem
+    state
+        .cassandra_breaker
+        .run(state.cassandra.execute(&state.statements.select_url, (short.clone(),)))
+        .await?
+        .single_row_typed::<UrlRow>()
+        .map_err(|_| AppError::NotFound)?;
+
+    // 3. Codifica a URL pública completa do short link
+    let link = state.config.short_link(&short);
+    let code = QrCode::encode(link.as_bytes())
+        .ok_or_else(|| AppError::Internal(format!("short link too long for a QR code: {}", link)))?;
+
+    Ok(if svg {
+        ([(header::CONTENT_TYPE, "image/svg+xml")], code.to_svg(module_px)).into_response()
+    } else {
+        ([(header::CONTENT_TYPE, "image/png")], code.to_png(module_px)).into_response()
+    })
+}
+
+#[derive(Serialize)]
+struct UrlStats {
+    short_url: String,
+    hits: i64,
+    /// Último redirecionamento (atualizado no máximo uma vez por minuto)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_accessed: Option<DateTime<Utc>>,
+    /// Se esse redirecionamento foi servido pelo cache do Redis
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_cache_hit: Option<bool>,
+    /// Cliques por variante, nos links com teste A/B
+    #[serde(skip_serializing_if = "Option::is_none")]
+    variants: Option<Vec<variants::VariantStats>>,
+}
+
+// GET /api/urls/:short_url/stats
+async fn get_url_stats(
+    Path(short): Path<String>,
+    Extension(state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, AppError> {
+    let short = stored_code(&state, short).await?;
+    // Sem linha no contador = nenhum clique ainda
+    let hits = state
+        .cassandra_breaker
+        .run(state.cassandra.execute(&state.statements.select_clicks, (short.clone(),)))
+        .await?
+        .maybe_first_row_typed::<(Counter,)>()
+        .map_err(|e| AppError::Internal(format!("stats row: {}", e)))?
+        .map_or(0, |(Counter(hits),)| hits);
+
+    let (last_accessed, last_cache_hit) = state
+        .cassandra_breaker
+        .run(state.cassandra.execute(&state.statements.select_last_accessed, (short.clone(),)))
+        .await?
+        .maybe_first_row_typed::<(Option<Timestamp>, Option<bool>)>()
+        .map_err(|e| AppError::Internal(format!("stats row: {}", e)))?
+        .unwrap_or_default();
+
+    let variants = variants::stats(&state, &short).await?;
+
+    Ok(Json(UrlStats {
+        short_url: short,
+        hits,
+        last_accessed: last_accessed.map(from_cql_timestamp),
+        last_cache_hit,
+        variants,
+    }))
+}
+
+// GET /metrics
+async fn metrics_handler(Extension(state): Extension<Arc<AppState>>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(),
+    )
+}
+
+// GET /
+async fn root(Extension(state): Extension<Arc<AppState>>) -> Response {
+    // A raiz tem rota própria: nunca vira a busca de um código vazio
+    match &state.config.root_redirect {
+        Some(location) => redirect_response(location, false, RedirectCache::Revalidate),
+        None => landing_page(),
+    }
+}
+
+// GET /favicon.ico
+async fn favicon() -> Response {
+    // Sem ícone embutido: 204 encerra a busca do navegador, e o cache evita que ela
+    // se repita a cada página
+    (StatusCode::NO_CONTENT, [(header::CACHE_CONTROL, "public, max-age=86400")]).into_response()
+}
+
+// GET /robots.txt
+async fn robots_txt(Extension(state): Extension<Arc<AppState>>) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, "text/plain; charset=utf-8"),
+            (header::CACHE_CONTROL, "public, max-age=3600"),
+        ],
+        state.config.robots_txt.clone(),
+    )
+        .into_response()
+}
+
+// GET /health
+async fn health() -> impl IntoResponse {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+// GET /ready
+async fn ready(Extension(state): Extension<Arc<AppState>>) -> impl IntoResponse {
+    let mut redis_conn = state.redis.get();
+    let redis_ok = match redis::cmd("PING").query_async::<_, String>(&mut redis_conn).await {
+        Ok(_) => true,
         Err(e) => {
-            eprintln!("Database query error: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+            warn!("Readiness: Redis error: {}", e);
+            false
+        }
+    };
+
+    let cassandra_ok = match state.cassandra.query("SELECT now() FROM system.local", &[]).await {
+        Ok(_) => true,
+        Err(e) => {
+            warn!("Readiness: Cassandra error: {}", e);
+            false
+        }
+    };
+
+    let status = if redis_ok && cassandra_ok {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let body = serde_json::json!({
+        "status": if status == StatusCode::OK { "ready" } else { "unavailable" },
+        "redis": redis_ok,
+        "cassandra": cassandra_ok,
+    });
+    (status, Json(body))
+}
+
+/// Tempo máximo para concluir as tarefas em segundo plano no shutdown
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+/// Resolve ao receber SIGTERM (ex: rolling deploy) ou ctrl-c
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for ctrl-c: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
         }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    info!("Shutdown signal received, draining in-flight requests...");
+}
+
+/// Níveis que contam réplicas de todos os DCs: com LOCAL_DC, prefira os `LOCAL_*`
+fn waits_for_remote_dcs(consistency: Consistency) -> bool {
+    matches!(consistency, Consistency::Quorum | Consistency::All | Consistency::EachQuorum)
+}
+
+/// Explica falhas de conexão ao Cassandra que nenhum retry resolve
+fn cassandra_connect_hint(error: &NewSessionError) -> Option<&'static str> {
+    match error {
+        NewSessionError::DbError(DbError::AuthenticationError, _) => Some(
+            "Cassandra rejected the credentials: check CASSANDRA_USER and CASSANDRA_PASSWORD",
+        ),
+        NewSessionError::InvalidMessage(message) if message.contains("Authentication is required") => Some(
+            "Cassandra requires authentication: set CASSANDRA_USER and CASSANDRA_PASSWORD",
+        ),
+        _ => None,
     }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("Starting URL Shortener Service...");
+    logging::init();
+    info!("Starting URL Shortener Service...");
 
-    const REDIS_URL: &str = "redis://redis:6379/";
-    const CASSANDRA_NODE: &str = "cassandra:9042";
+    let config = Config::from_env()?;
 
-    // Redis
-    let redis_client = redis::Client::open(REDIS_URL)?;
-    let redis_conn = redis_client.get_multiplexed_async_connection().await?;
+    // Redis (aguarda o serviço subir em vez de encerrar o processo)
+    let redis_client = redis::Client::open(config.redis_url.as_str())?;
+    let redis_pool = retry_with_backoff("Redis", config.startup_retries, || {
+        RedisPool::connect(&redis_client, config.redis_pool_size, config.redis_timeout)
+    })
+    .await?;
 
     // Cassandra
-    let cassandra = SessionBuilder::new()
-        .known_node(CASSANDRA_NODE)
-        .build()
-        .await?;
+    // O timeout da sessão cobre o que não usa os statements preparados (lotes, readiness)
+    let mut session_builder = SessionBuilder::new()
+        .known_nodes(&config.cassandra_nodes)
+        .pool_size(config.cassandra_pool_size.clone())
+        .request_timeout(Some(config.query_timeout));
+    if let Some((user, password)) = &config.cassandra_credentials {
+        session_builder = session_builder.user(user, password);
+    }
+    // Com LOCAL_DC, as réplicas do DC local vêm primeiro no plano de cada query e
+    // as dos outros DCs só entram se nenhuma local responder. Sem ele, o padrão do
+    // driver (token-aware + round-robin entre todos os nós)
+    if let Some(local_dc) = &config.local_dc {
+        let policy = TokenAwarePolicy::new(Box::new(DcAwareRoundRobinPolicy::new(local_dc.clone())));
+        session_builder = session_builder.load_balancing(Arc::new(policy));
+        if waits_for_remote_dcs(config.consistency) {
+            warn!(
+                "LOCAL_DC is set, but CASSANDRA_CONSISTENCY {:?} still waits for remote replicas",
+                config.consistency
+            );
+        }
+        info!("Cassandra queries prefer datacenter '{}'", local_dc);
+    }
+    let cassandra = retry_with_backoff("Cassandra", config.startup_retries, || {
+        session_builder.build()
+    })
+    .await
+    .inspect_err(|e| {
+        if let Some(hint) = cassandra_connect_hint(e) {
+            error!("{}", hint);
+        }
+    })?;
 
-    //  Cria o keyspace se não existir
-    cassandra
-        .query(
-            "CREATE KEYSPACE IF NOT EXISTS shortener WITH replication = {'class': 'SimpleStrategy', 'replication_factor': 1};",
-            &[],
-        )
-        .await?;
+    // Keyspace e tabelas
+    schema::migrate(&cassandra, &config.cassandra_keyspace, &config.replication).await?;
 
-    // Usa o keyspace
-    cassandra.use_keyspace("shortener", false).await?;
-
-    // Cria a tabela se não existir
-    cassandra
-        .query(
-            "CREATE TABLE IF NOT EXISTS urls (
-                short_url text PRIMARY KEY,
-                long_url text,
-                created_at timestamp
-            );",
-            &[],
-        )
-        .await?;
+    // Statements preparados (após o keyspace estar em uso)
+    let statements = Statements::prepare(
+        &cassandra,
+        config.consistency,
+        config.analytics_consistency,
+        config.query_timeout,
+    )
+    .await?;
+
+    info!("Connected to Redis and Cassandra (keyspace ready)");
 
-    println!("Connected to Redis and Cassandra (keyspace ready)");
+    if !config.api_keys.is_enabled() {
+        warn!("API_KEYS is not set: write endpoints are open to anyone");
+    }
+
+    let read_only = config.read_only;
+    if read_only {
+        warn!("READ_ONLY is set: create, update and delete requests will be rejected");
+    }
+
+    let addr = config.bind_addr;
+    let ids = IdGenerator::new(config.id_strategy);
+    info!("ID strategy: {:?}", config.id_strategy);
+    let cassandra_breaker = CircuitBreaker::new(
+        config.circuit_failure_threshold,
+        Duration::from_secs(config.circuit_window_secs),
+        Duration::from_secs(config.circuit_cooldown_secs),
+    );
 
     // Shared state
     let state = Arc::new(AppState {
-        redis: redis_conn,
+        redis: redis_pool,
         cassandra,
+        statements,
+        config,
+        metrics: Metrics::default(),
+        ids,
+        background: TaskTracker::new(),
+        cassandra_breaker,
+        read_only: ReadOnly::new(read_only),
+    });
+    let shutdown_state = Arc::clone(&state);
+
+    // Limpeza periódica de contadores e índices de links que já expiraram
+    let cleanup_task = (state.config.cleanup_interval_secs > 0).then(|| {
+        cleanup::spawn(
+            Arc::clone(&state),
+            Duration::from_secs(state.config.cleanup_interval_secs),
+        )
     });
 
+    // Aquecimento do cache com os links mais clicados, sem atrasar o início do tráfego
+    let warm_task = state
+        .config
+        .cache_warm
+        .then(|| cache_warm::spawn(Arc::clone(&state), state.config.cache_warm_limit));
+
     // Rotas
+    // Criação de URLs, com rate limit por IP (redirecionamentos não são limitados)
+    // O corpo é limitado antes da desserialização (413 acima do limite)
+    let shorten_routes = Router::new()
+        .route(
+            "/shorten",
+            post(create_shorten_url).layer(DefaultBodyLimit::max(state.config.max_body_bytes)),
+        )
+        .route(
+            "/shorten/batch",
+            post(batch::create_shorten_batch)
+                .layer(DefaultBodyLimit::max(state.config.batch_max_body_bytes)),
+        )
+        .route_layer(middleware::from_fn(rate_limit::limit_shorten))
+        .route_layer(middleware::from_fn(auth::require_api_key));
+
+    // Rotas JSON chamadas pelo front-end, com CORS (o preflight é respondido antes do auth)
+    // Com DISABLE_SHORTEN as rotas de criação nem existem: `/shorten` cai no
+    // catch-all dos short links, que responde 404 para nomes reservados
+    let shorten_routes = if state.config.disable_shorten {
+        info!("DISABLE_SHORTEN is set: POST /shorten and /shorten/batch are not served");
+        Router::new()
+    } else {
+        shorten_routes
+    };
+    let api_routes = Router::new()
+        .merge(shorten_routes)
+        .route("/api/lookup", get(lookup_short_url))
+        .route("/api/resolve/:short_url", get(resolve_short_url))
+        .route(
+            "/api/urls",
+            get(listing::list_urls).route_layer(middleware::from_fn(auth::require_api_key)),
+        )
+        .route(
+            "/api/export.csv",
+            get(export::export_csv).route_layer(middleware::from_fn(auth::require_api_key)),
+        )
+        .route(
+            "/api/audit",
+            get(audit::audit_history).route_layer(middleware::from_fn(auth::require_api_key)),
+        )
+        .route(
+            "/api/stats/summary",
+            get(summary::summary_stats).route_layer(middleware::from_fn(auth::require_api_key)),
+        )
+        .route(
+            "/api/urls/batch-delete",
+            post(batch::delete_batch)
+                .layer(DefaultBodyLimit::max(state.config.batch_max_body_bytes))
+                .route_layer(middleware::from_fn(auth::require_api_key)),
+        )
+        .route(
+            "/api/urls/:short_url",
+            get(get_url_details)
+                .merge(put(update_short_url).layer(DefaultBodyLimit::max(state.config.max_body_bytes)))
+                .route_layer(middleware::from_fn(auth::require_api_key)),
+        )
+        .route("/api/urls/:short_url/stats", get(get_url_stats))
+        .route("/api/urls/:short_url/clicks", get(click_events::click_history))
+        .route("/api/urls/:short_url/available", get(check_alias_available))
+        .route("/api/urls/:short_url/qr", get(get_url_qr));
+    let api_routes = match state.config.cors_origins.layer() {
+        Some(cors) => api_routes.layer(cors),
+        None => api_routes,
+    };
+
+    // Com SWAGGER_UI desligado, `/swagger` cai no catch-all (nome reservado, 404);
+    // o documento OpenAPI continua disponível
+    let docs_routes = Router::new().route("/api-docs/openapi.json", get(openapi::openapi_json));
+    let docs_routes = if state.config.swagger_ui {
+        docs_routes.route("/swagger", get(openapi::swagger_ui))
+    } else {
+        docs_routes
+    };
+
+    let compression = state.config.compression;
     let app = Router::new()
-        .route("/shorten", post(create_shorten_url))
-        .route("/:short_url", get(redirect_to_long_url))
-        .layer(Extension(state));
+        .route("/", get(root))
+        .route("/favicon.ico", get(favicon))
+        .route("/robots.txt", get(robots_txt))
+        .route("/health", get(health))
+        .route("/ready", get(ready))
+        .route("/metrics", get(metrics_handler))
+        .route(
+            "/admin/readonly",
+            post(maintenance::set_read_only).route_layer(middleware::from_fn(auth::require_api_key)),
+        )
+        .merge(docs_routes)
+        .merge(api_routes)
+        // Catch-all dos short links: o roteador do axum sempre prefere os segmentos
+        // estáticos acima, e `shortcode::is_reserved` impede aliases com esses nomes
+        .route(
+            "/:short_url",
+            get(redirect_to_long_url)
+                .merge(post(unlock_short_link).layer(DefaultBodyLimit::max(state.config.max_body_bytes)))
+                .merge(
+                    delete(delete_short_url).route_layer(middleware::from_fn(auth::require_api_key)),
+                ),
+        )
+        // `/abc/` redireciona como `/abc` (a barra é removida em `serve_short_link`)
+        .route(
+            "/:short_url/",
+            get(redirect_to_long_url)
+                .merge(post(unlock_short_link).layer(DefaultBodyLimit::max(state.config.max_body_bytes))),
+        )
+        .fallback(error::not_found)
+        .layer(middleware::from_fn(error::html_not_found))
+        .layer(middleware::from_fn(timing::track_slow_requests))
+        .layer(Extension(state))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(logging::RequestSpan)
+                .on_request(())
+                .on_response(logging::LogResponse)
+                .on_eos(())
+                // Erros 5xx já são logados por `AppError`
+                .on_failure(()),
+        );
+    // gzip/brotli conforme o `Accept-Encoding`. Redirecionamentos, respostas
+    // pequenas e imagens (QR Codes em PNG) saem sem compressão
+    let app = if compression {
+        app.layer(CompressionLayer::new())
+    } else {
+        info!("COMPRESSION is disabled: responses are sent uncompressed");
+        app
+    };
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
-    println!("Listening on http://{}", addr);
+    info!("Listening on http://{}", addr);
 
     use tokio::net::TcpListener;
 
     let listener = TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
+
+    // Requisições drenadas: conclui as escritas em segundo plano antes de fechar as conexões
+    shutdown_state.background.close();
+    if tokio::time::timeout(SHUTDOWN_GRACE, shutdown_state.background.wait())
+        .await
+        .is_err()
+    {
+        warn!("Background tasks did not finish within {:?}", SHUTDOWN_GRACE);
+    }
+
+    for task in [cleanup_task, warm_task].into_iter().flatten() {
+        task.abort();
+        let _ = task.await;
+    }
+
+    // Último handle do estado: encerra as conexões com Redis e Cassandra
+    drop(shutdown_state);
+    info!("Connections closed, bye");
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shortcode::{BASE62_ALPHABET, DEFAULT_ID_OFFSET};
+    use std::collections::{BTreeMap, BTreeSet};
+
+    /// Rotas registradas no `Router` de `main`, lidas do próprio código-fonte, com os
+    /// métodos de cada uma e o caminho no formato do OpenAPI (`:short_url` → `{short_url}`)
+    fn registered_routes() -> BTreeMap<String, BTreeSet<&'static str>> {
+        let source = include_str!("main.rs");
+        let start = source.find("    // Rotas\n").unwrap();
+        let end = start + source[start..].find(".fallback(error::not_found)").unwrap();
+        let mut routes = BTreeMap::new();
+        for route in source[start..end].split(".route(").skip(1) {
+            // Argumentos do `.route(...)`: até o parêntese que o fecha
+            let mut depth = 1;
+            let (len, _) = route
+                .char_indices()
+                .find(|&(_, c)| {
+                    depth += match c {
+                        '(' => 1,
+                        ')' => -1,
+                        _ => 0,
+                    };
+                    depth == 0
+                })
+                .unwrap();
+            let args = &route[..len];
+            let path: Vec<String> = args
+                .split('"')
+                .nth(1)
+                .unwrap()
+                .split('/')
+                .map(|segment| match segment.strip_prefix(':') {
+                    Some(param) => format!("{{{}}}", param),
+                    None => segment.to_string(),
+                })
+                .collect();
+            // `get(` isolado, não o fim de outro nome (`forget(`)
+            let bytes = args.as_bytes();
+            let is_ident = |idx: usize| bytes[idx].is_ascii_alphanumeric() || bytes[idx] == b'_';
+            let methods = ["get", "post", "put", "delete"].into_iter().filter(|method| {
+                args.match_indices(&format!("{}(", method)).any(|(idx, _)| idx == 0 || !is_ident(idx - 1))
+            });
+            routes.entry(path.join("/")).or_insert_with(BTreeSet::new).extend(methods);
+        }
+        routes
+    }
+
+    #[test]
+    fn openapi_documents_every_route() {
+        // O próprio documento, a página que o exibe e a variante com barra final de `/{short_url}`
+        let undocumented = ["/api-docs/openapi.json", "/swagger", "/{short_url}/"];
+        let mut routes = registered_routes();
+        assert!(routes.contains_key("/shorten/batch") && routes.contains_key("/{short_url}"));
+        routes.retain(|path, _| !undocumented.contains(&path.as_str()));
+
+        let doc = openapi::document();
+        let documented: BTreeMap<String, BTreeSet<&'static str>> = doc["paths"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .map(|(path, operations)| {
+                let methods = ["get", "post", "put", "delete"]
+                    .into_iter()
+                    .filter(|method| operations.get(method).is_some())
+                    .collect();
+                (path.clone(), methods)
+            })
+            .collect();
+        assert_eq!(routes, documented);
+    }
+
+    #[test]
+    fn resolve_etag_follows_destination() {
+        let etag = resolve_etag("2tx", "https://rust-lang.org/");
+        assert_eq!(etag, resolve_etag("2tx", "https://rust-lang.org/"));
+        assert_ne!(etag, resolve_etag("2tx", "https://www.rust-lang.org/"));
+        assert!(etag_matches(&etag, &etag));
+        assert!(etag_matches(&format!("\"other\", W/{}", etag), &etag));
+        assert!(etag_matches("*", &etag));
+        assert!(!etag_matches("\"other\"", &etag));
+    }
+
+    #[test]
+    fn cassandra_outage_becomes_unavailable() {
+        use scylla::transport::errors::{DbError, QueryError};
+        assert!(matches!(
+            unavailable_on_outage(AppError::Cassandra(QueryError::TimeoutError), 30),
+            AppError::Unavailable { retry_after: 30 }
+        ));
+        let syntax = QueryError::DbError(DbError::SyntaxError, String::new());
+        assert!(matches!(unavailable_on_outage(AppError::Cassandra(syntax), 30), AppError::Cassandra(_)));
+        assert!(matches!(unavailable_on_outage(AppError::NotFound, 30), AppError::NotFound));
+    }
+
+    #[test]
+    fn short_url_for_id_rejects_overflow() {
+        let plain = &CodeMode::Plain;
+        let last = u64::MAX - DEFAULT_ID_OFFSET;
+        assert!(short_url_for_id(last, DEFAULT_ID_OFFSET, None, plain, &[0; 32], BASE62_ALPHABET, 6).is_ok());
+        assert!(matches!(
+            short_url_for_id(u64::MAX, DEFAULT_ID_OFFSET, None, plain, &[0; 32], BASE62_ALPHABET, 6),
+            Err(AppError::Internal(_))
+        ));
+    }
+
+    #[test]
+    fn short_url_for_id_respects_code_width() {
+        let plain = &CodeMode::Plain;
+        let (offset, limit) = shortcode::fixed_width_range(62, 3).unwrap();
+        let last = limit - offset - 1;
+        let code = short_url_for_id(last, offset, Some(limit), plain, &[0; 32], BASE62_ALPHABET, 3).unwrap();
+        assert_eq!(code.len(), 3);
+        assert!(matches!(
+            short_url_for_id(last + 1, offset, Some(limit), plain, &[0; 32], BASE62_ALPHABET, 3),
+            Err(AppError::Internal(_))
+        ));
+    }
+
+    #[test]
+    fn validate_shorten_reports_every_field() {
+        let base = "https://sho.rt";
+        let schemes = AllowedSchemes::default();
+        let payload: ShortenRequest = serde_json::from_value(serde_json::json!({
+            "long_url": "javascript:alert(1)",
+            "short_url": "x",
+            "ttl_secs": 0,
+            "max_clicks": 0,
+        }))
+        .unwrap();
+        let Err(errors) = validate_shorten(&payload, 2048, &schemes, base) else {
+            panic!("invalid payload accepted");
+        };
+        let fields: Vec<&str> = errors.iter().map(|e| e.field).collect();
+        assert_eq!(fields, ["long_url", "short_url", "ttl_secs", "max_clicks"]);
+
+        let payload: ShortenRequest =
+            serde_json::from_value(serde_json::json!({ "long_url": "https://Rust-lang.org" })).unwrap();
+        let valid = validate_shorten(&payload, 2048, &schemes, base).ok().unwrap();
+        assert_eq!(valid.long_url, "https://rust-lang.org/");
+    }
+}
@@ -0,0 +1,79 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use axum::extract::{rejection::JsonRejection, Extension, Json};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::auth::ApiKeyId;
+use crate::{AppError, AppState};
+
+/// Modo somente leitura (`READ_ONLY`): bloqueia criações, edições e remoções
+/// enquanto os redirecionamentos continuam sendo servidos.
+///
+/// O estado é da instância: alterado pela rota de admin, vale só para a
+/// instância que recebeu a requisição e volta a `READ_ONLY` ao reiniciar.
+pub struct ReadOnly(AtomicBool);
+
+impl ReadOnly {
+    pub fn new(enabled: bool) -> Self {
+        ReadOnly(AtomicBool::new(enabled))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Falha com 503 `read_only` se o modo estiver ativo; chamado pelos handlers de escrita
+    pub fn ensure_writable(&self) -> Result<(), AppError> {
+        if self.is_enabled() {
+            Err(AppError::ReadOnly)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ReadOnlyToggle {
+    enabled: bool,
+}
+
+#[derive(Serialize)]
+pub struct ReadOnlyStatus {
+    read_only: bool,
+}
+
+// POST /admin/readonly
+pub async fn set_read_only(
+    Extension(state): Extension<Arc<AppState>>,
+    api_key: Option<Extension<ApiKeyId>>,
+    payload: Result<Json<ReadOnlyToggle>, JsonRejection>,
+) -> Result<Json<ReadOnlyStatus>, AppError> {
+    let Json(payload) = payload?;
+
+    let previous = state.read_only.0.swap(payload.enabled, Ordering::Relaxed);
+    if previous != payload.enabled {
+        let api_key = api_key.map(|Extension(ApiKeyId(id))| id);
+        warn!(
+            api_key = api_key.as_deref(),
+            "Read-only mode {}",
+            if payload.enabled { "enabled" } else { "disabled" }
+        );
+    }
+
+    Ok(Json(ReadOnlyStatus {
+        read_only: payload.enabled,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn response_schemas_match_structs() {
+        use crate::openapi::schema_fields::assert_response;
+        assert_response("ReadOnlyStatus", &ReadOnlyStatus { read_only: false });
+    }
+}
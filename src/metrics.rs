@@ -0,0 +1,193 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+/// Limites (em segundos) dos buckets do histograma de latência
+const LATENCY_BUCKETS: [f64; 11] = [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+/// Métricas da aplicação, expostas em `GET /metrics` no formato texto do Prometheus
+#[derive(Default)]
+pub struct Metrics {
+    pub shortens: AtomicU64,
+    pub redirects: AtomicU64,
+    pub redirect_not_found: AtomicU64,
+    /// Redirecionamentos de bots, que ficam fora do contador de cliques
+    pub bot_redirects: AtomicU64,
+    pub cache_hits: AtomicU64,
+    pub cache_misses: AtomicU64,
+    /// Parte de `cache_hits` que foi de códigos inexistentes (cache negativo)
+    pub negative_cache_hits: AtomicU64,
+    /// Cache misses que esperaram outra requisição repopular o cache (ver `stampede`)
+    pub stampede_waits: AtomicU64,
+    /// Códigos resolvidos em JSON (`GET /api/resolve`), fora do contador de cliques
+    pub resolves: AtomicU64,
+    pub shorten_latency: Histogram,
+    pub redirect_latency: Histogram,
+    /// Tempo de cada requisição gasto no Redis e no Cassandra (ver `timing`)
+    pub redis_latency: Histogram,
+    pub cassandra_latency: Histogram,
+}
+
+impl Metrics {
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        counter(&mut out, "shortener_shortens_total", "Short URLs created", &self.shortens);
+        counter(&mut out, "shortener_redirects_total", "Redirects served", &self.redirects);
+        counter(
+            &mut out,
+            "shortener_redirect_not_found_total",
+            "Redirects for unknown short URLs",
+            &self.redirect_not_found,
+        );
+        counter(
+            &mut out,
+            "shortener_bot_redirects_total",
+            "Redirects served to bots and link-preview crawlers",
+            &self.bot_redirects,
+        );
+        counter(
+            &mut out,
+            "shortener_resolves_total",
+            "Short URLs resolved as JSON without redirecting",
+            &self.resolves,
+        );
+        counter(&mut out, "shortener_cache_hits_total", "Redirect cache hits", &self.cache_hits);
+        counter(&mut out, "shortener_cache_misses_total", "Redirect cache misses", &self.cache_misses);
+        counter(
+            &mut out,
+            "shortener_negative_cache_hits_total",
+            "Redirect cache hits for unknown short URLs",
+            &self.negative_cache_hits,
+        );
+        counter(
+            &mut out,
+            "shortener_cache_stampede_waits_total",
+            "Cache misses that waited for a concurrent request to repopulate the cache",
+            &self.stampede_waits,
+        );
+
+        let name = "shortener_cache_hit_ratio";
+        let _ = writeln!(out, "# HELP {} Redirect cache hits over lookups since startup", name);
+        let _ = writeln!(out, "# TYPE {} gauge", name);
+        let _ = writeln!(
+            out,
+            "{} {}",
+            name,
+            hit_ratio(self.cache_hits.load(Ordering::Relaxed), self.cache_misses.load(Ordering::Relaxed))
+        );
+
+        let name = "shortener_handler_duration_seconds";
+        let _ = writeln!(out, "# HELP {} Handler latency in seconds", name);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        self.shorten_latency.render(&mut out, name, "handler", "shorten");
+        self.redirect_latency.render(&mut out, name, "handler", "redirect");
+
+        let name = "shortener_backend_duration_seconds";
+        let _ = writeln!(out, "# HELP {} Time each request spent waiting on a backend, in seconds", name);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        self.redis_latency.render(&mut out, name, "backend", "redis");
+        self.cassandra_latency.render(&mut out, name, "backend", "cassandra");
+        out
+    }
+}
+
+/// Fração das consultas servidas pelo cache (0 antes da primeira)
+fn hit_ratio(hits: u64, misses: u64) -> f64 {
+    let lookups = hits + misses;
+    if lookups == 0 {
+        0.0
+    } else {
+        hits as f64 / lookups as f64
+    }
+}
+
+fn counter(out: &mut String, name: &str, help: &str, value: &AtomicU64) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    let _ = writeln!(out, "{} {}", name, value.load(Ordering::Relaxed));
+}
+
+/// Histograma de latência com buckets fixos
+#[derive(Default)]
+pub struct Histogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    pub fn observe(&self, seconds: f64) {
+        // Cada observação entra só no primeiro bucket que a comporta; o acumulado é feito no render
+        if let Some(idx) = LATENCY_BUCKETS.iter().position(|&le| seconds <= le) {
+            self.buckets[idx].fetch_add(1, Ordering::Relaxed);
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add((seconds * 1_000_000.0) as u64, Ordering::Relaxed);
+    }
+
+    /// Registra a duração até o fim do escopo (inclusive em retornos antecipados com `?`)
+    pub fn start_timer(&self) -> Timer<'_> {
+        Timer {
+            histogram: self,
+            start: Instant::now(),
+        }
+    }
+
+    /// Escreve as séries do histograma com o rótulo `label="value"` (ex: `handler="shorten"`)
+    fn render(&self, out: &mut String, name: &str, label: &str, value: &str) {
+        let mut cumulative = 0;
+        for (le, bucket) in LATENCY_BUCKETS.iter().zip(&self.buckets) {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let _ = writeln!(out, "{}_bucket{{{}=\"{}\",le=\"{}\"}} {}", name, label, value, le, cumulative);
+        }
+        let count = self.count.load(Ordering::Relaxed);
+        let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(out, "{}_bucket{{{}=\"{}\",le=\"+Inf\"}} {}", name, label, value, count);
+        let _ = writeln!(out, "{}_sum{{{}=\"{}\"}} {}", name, label, value, sum);
+        let _ = writeln!(out, "{}_count{{{}=\"{}\"}} {}", name, label, value, count);
+    }
+}
+
+pub struct Timer<'a> {
+    histogram: &'a Histogram,
+    start: Instant,
+}
+
+impl Drop for Timer<'_> {
+    fn drop(&mut self) {
+        self.histogram.observe(self.start.elapsed().as_secs_f64());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_cumulative_histogram_buckets() {
+        let metrics = Metrics::default();
+        metrics.redirects.fetch_add(3, Ordering::Relaxed);
+        metrics.redirect_latency.observe(0.002);
+        metrics.redirect_latency.observe(0.3);
+        metrics.redirect_latency.observe(10.0);
+
+        let text = metrics.render();
+        assert!(text.contains("shortener_redirects_total 3\n"));
+        assert!(text.contains("shortener_handler_duration_seconds_bucket{handler=\"redirect\",le=\"0.001\"} 0\n"));
+        assert!(text.contains("shortener_handler_duration_seconds_bucket{handler=\"redirect\",le=\"0.005\"} 1\n"));
+        assert!(text.contains("shortener_handler_duration_seconds_bucket{handler=\"redirect\",le=\"5\"} 2\n"));
+        assert!(text.contains("shortener_handler_duration_seconds_bucket{handler=\"redirect\",le=\"+Inf\"} 3\n"));
+        assert!(text.contains("shortener_handler_duration_seconds_count{handler=\"redirect\"} 3\n"));
+    }
+
+    #[test]
+    fn renders_cache_hit_ratio() {
+        let metrics = Metrics::default();
+        assert!(metrics.render().contains("shortener_cache_hit_ratio 0\n"));
+
+        metrics.cache_hits.fetch_add(3, Ordering::Relaxed);
+        metrics.cache_misses.fetch_add(1, Ordering::Relaxed);
+        assert!(metrics.render().contains("shortener_cache_hit_ratio 0.75\n"));
+    }
+}
@@ -0,0 +1,928 @@
+use axum::{
+    http::header,
+    response::{Html, IntoResponse},
+    Json,
+};
+use serde_json::{json, Value};
+
+/// Página do Swagger UI; os assets vêm do CDN para não embutir o bundle no binário.
+/// Como o script roda na origem do serviço, a rota só existe com `SWAGGER_UI=true`
+const SWAGGER_HTML: &str = r##"<!doctype html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>URL Shortener API</title>
+<link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+<div id="swagger-ui"></div>
+<script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+<script>SwaggerUIBundle({ url: "/api-docs/openapi.json", dom_id: "#swagger-ui" });</script>
+</body>
+</html>
+"##;
+
+// GET /api-docs/openapi.json
+pub async fn openapi_json() -> impl IntoResponse {
+    ([(header::CACHE_CONTROL, "public, max-age=300")], Json(document()))
+}
+
+// GET /swagger
+pub async fn swagger_ui() -> impl IntoResponse {
+    Html(SWAGGER_HTML)
+}
+
+/// Documento OpenAPI 3.0 da API.
+///
+/// Cada rota registrada no `Router` em `main` tem uma entrada em `paths`, com
+/// os mesmos métodos; os schemas espelham os structs serializados pelos handlers.
+/// Ao mudar uma rota ou um desses structs, atualize aqui também: os testes comparam
+/// `paths` com as rotas do `Router` e os `properties` com os campos dos structs.
+pub fn document() -> Value {
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "URL Shortener",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": {
+            "/shorten": {
+                "post": {
+                    "summary": "Create a short URL",
+                    "security": api_key(),
+                    "parameters": [
+                        {
+                            "name": "Idempotency-Key",
+                            "in": "header",
+                            "required": false,
+                            "description": "Retries with the same key return the original response",
+                            "schema": { "type": "string", "maxLength": 255 },
+                        },
+                        query_param("dry_run", "true: validate and predict the code without creating the link", false),
+                    ],
+                    "requestBody": {
+                        "required": true,
+                        "description": "JSON, or an HTML form with the flat fields (geo, variants and tags need JSON)",
+                        "content": {
+                            "application/json": { "schema": schema_ref("ShortenRequest") },
+                            "application/x-www-form-urlencoded": { "schema": schema_ref("ShortenRequest") },
+                        },
+                    },
+                    "responses": responses(&[
+                        ("201", "Short URL created", Some("Url")),
+                        ("200", "Existing short URL for the same destination, or the predicted code of a dry run", Some("Url")),
+                        ("400", "Invalid Idempotency-Key", Some("Error")),
+                        ("403", "Blocked destination domain or link quota exceeded", Some("Error")),
+                        ("409", "Alias already taken or reserved, or Idempotency-Key in progress", Some("Error")),
+                        ("422", "Invalid fields (all listed in errors) or Idempotency-Key reused with a different body", Some("Error")),
+                        ("429", "Rate limit exceeded", Some("Error")),
+                        ("503", "Read-only mode or database unavailable", Some("Error")),
+                    ]),
+                },
+            },
+            "/shorten/batch": {
+                "post": {
+                    "summary": "Create short URLs in bulk",
+                    "security": api_key(),
+                    "requestBody": json_body("BatchRequest"),
+                    "responses": responses(&[
+                        ("200", "One result per URL, in the same order", Some("BatchResponse")),
+                        ("400", "Too many URLs", Some("Error")),
+                        ("403", "Link quota exceeded", Some("Error")),
+                        ("503", "Read-only mode or database unavailable", Some("Error")),
+                    ]),
+                },
+            },
+            "/api/lookup": {
+                "get": {
+                    "summary": "Find the short URL of a destination",
+                    "parameters": [query_param("url", "Destination URL", true)],
+                    "responses": responses(&[
+                        ("200", "Existing short URL", Some("Url")),
+                        ("404", "Destination was never shortened", Some("Error")),
+                    ]),
+                },
+            },
+            "/api/resolve/{short_url}": {
+                "get": {
+                    "summary": "Resolve a short URL to its destination without redirecting",
+                    "parameters": [
+                        short_url_param(),
+                        query_param("pw", "Password of a protected link", false),
+                        {
+                            "name": "If-None-Match",
+                            "in": "header",
+                            "required": false,
+                            "description": "ETag of a previous resolution; 304 if the destination did not change",
+                            "schema": { "type": "string" },
+                        },
+                    ],
+                    "responses": responses(&[
+                        ("200", "Destination of the short URL, with an ETag header", Some("ResolvedLink")),
+                        ("304", "Destination unchanged since the If-None-Match ETag", None),
+                        ("401", "Password protected link without the right password", Some("Error")),
+                        ("404", "Unknown short URL", Some("Error")),
+                        ("410", "Short URL deleted", Some("Error")),
+                    ]),
+                },
+            },
+            "/api/urls": {
+                "get": {
+                    "summary": "List short URLs",
+                    "security": api_key(),
+                    "parameters": [
+                        query_param("limit", "Page size", false),
+                        query_param("cursor", "Cursor returned by the previous page", false),
+                        query_param("owner", "Only links created by this API key", false),
+                        query_param("tag", "Only links with this tag", false),
+                    ],
+                    "responses": responses(&[
+                        ("200", "A page of short URLs", Some("UrlPage")),
+                        ("400", "Invalid limit or cursor", Some("Error")),
+                    ]),
+                },
+            },
+            "/api/export.csv": {
+                "get": {
+                    "summary": "Export every short URL as CSV",
+                    "security": api_key(),
+                    "responses": {
+                        "200": {
+                            "description": "short_url,long_url,created_at,owner",
+                            "content": { "text/csv": { "schema": { "type": "string" } } },
+                        },
+                    },
+                },
+            },
+            "/api/audit": {
+                "get": {
+                    "summary": "Administrative history (create, update, delete) of a short URL",
+                    "security": api_key(),
+                    "parameters": [
+                        query_param("short_url", "Short URL code", true),
+                        query_param("limit", "Maximum entries (1-1000, default 100)", false),
+                    ],
+                    "responses": responses(&[
+                        ("200", "Audit entries, newest first", Some("AuditHistory")),
+                        ("400", "Missing short_url or invalid limit", Some("Error")),
+                    ]),
+                },
+            },
+            "/api/stats/summary": {
+                "get": {
+                    "summary": "Totals for a dashboard header (cached for 60 seconds)",
+                    "security": api_key(),
+                    "responses": responses(&[("200", "Link and click totals", Some("Summary"))]),
+                },
+            },
+            "/api/urls/batch-delete": {
+                "post": {
+                    "summary": "Delete short URLs in bulk",
+                    "security": api_key(),
+                    "requestBody": json_body("BatchDeleteRequest"),
+                    "responses": responses(&[
+                        ("200", "One result per code, in the same order", Some("BatchDeleteResponse")),
+                        ("400", "Too many codes", Some("Error")),
+                        ("503", "Read-only mode or database unavailable", Some("Error")),
+                    ]),
+                },
+            },
+            "/api/urls/{short_url}": {
+                "get": {
+                    "summary": "Full metadata of a short URL",
+                    "security": api_key(),
+                    "parameters": [short_url_param()],
+                    "responses": responses(&[
+                        ("200", "Link metadata", Some("UrlDetails")),
+                        ("404", "Unknown or expired short URL", Some("Error")),
+                    ]),
+                },
+                "put": {
+                    "summary": "Change the destination of a short URL",
+                    "security": api_key(),
+                    "parameters": [short_url_param()],
+                    "requestBody": json_body("UpdateUrl"),
+                    "responses": responses(&[
+                        ("200", "Updated short URL", Some("Url")),
+                        ("400", "Invalid URL", Some("Error")),
+                        ("404", "Unknown or expired short URL", Some("Error")),
+                        ("503", "Read-only mode or database unavailable", Some("Error")),
+                    ]),
+                },
+            },
+            "/api/urls/{short_url}/stats": {
+                "get": {
+                    "summary": "Click statistics of a short URL",
+                    "parameters": [short_url_param()],
+                    "responses": responses(&[("200", "Click statistics", Some("UrlStats"))]),
+                },
+            },
+            "/api/urls/{short_url}/clicks": {
+                "get": {
+                    "summary": "Daily clicks of a short URL",
+                    "parameters": [
+                        short_url_param(),
+                        query_param("from", "First day (YYYY-MM-DD), 29 days before to by default", false),
+                        query_param("to", "Last day (YYYY-MM-DD), today (UTC) by default", false),
+                    ],
+                    "responses": responses(&[
+                        ("200", "Clicks per day, including days without clicks", Some("ClicksHistory")),
+                        ("400", "Invalid date, from after to or range over 366 days", Some("Error")),
+                    ]),
+                },
+            },
+            "/api/urls/{short_url}/available": {
+                "get": {
+                    "summary": "Check whether a custom alias is available",
+                    "parameters": [short_url_param()],
+                    "responses": responses(&[("200", "Availability of the alias", Some("Availability"))]),
+                },
+            },
+            "/api/urls/{short_url}/qr": {
+                "get": {
+                    "summary": "QR code of a short URL",
+                    "parameters": [
+                        short_url_param(),
+                        query_param("format", "png (default) or svg", false),
+                        query_param("size", "Pixels per module, 1 to 20", false),
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "QR code image",
+                            "content": {
+                                "image/png": { "schema": { "type": "string", "format": "binary" } },
+                                "image/svg+xml": { "schema": { "type": "string" } },
+                            },
+                        },
+                        "400": error_response("Invalid format or size"),
+                        "404": error_response("Unknown short URL"),
+                    },
+                },
+            },
+            "/": {
+                "get": {
+                    "summary": "Landing page, or a redirect to ROOT_REDIRECT when set",
+                    "responses": {
+                        "200": { "description": "Built-in landing page (HTML)" },
+                        "307": { "description": "Redirect to ROOT_REDIRECT" },
+                    },
+                },
+            },
+            "/favicon.ico": {
+                "get": {
+                    "summary": "Empty favicon, so browsers stop asking",
+                    "responses": { "204": { "description": "No icon (cached for a day)" } },
+                },
+            },
+            "/robots.txt": {
+                "get": {
+                    "summary": "Crawler rules from ROBOTS_TXT (by default, short links are not crawled)",
+                    "responses": { "200": { "description": "robots.txt (text/plain)" } },
+                },
+            },
+            "/health": {
+                "get": {
+                    "summary": "Liveness probe",
+                    "responses": { "200": { "description": "The process is up" } },
+                },
+            },
+            "/ready": {
+                "get": {
+                    "summary": "Readiness probe (Redis and Cassandra)",
+                    "responses": {
+                        "200": { "description": "Ready to serve traffic" },
+                        "503": { "description": "A dependency is unavailable" },
+                    },
+                },
+            },
+            "/metrics": {
+                "get": {
+                    "summary": "Prometheus metrics",
+                    "responses": {
+                        "200": {
+                            "description": "Prometheus text format",
+                            "content": { "text/plain": { "schema": { "type": "string" } } },
+                        },
+                    },
+                },
+            },
+            "/admin/readonly": {
+                "post": {
+                    "summary": "Toggle read-only mode on this instance",
+                    "security": api_key(),
+                    "requestBody": json_body("ReadOnlyToggle"),
+                    "responses": responses(&[("200", "Current mode", Some("ReadOnlyStatus"))]),
+                },
+            },
+            "/{short_url}": {
+                "get": {
+                    "summary": "Redirect to the destination",
+                    "description": "A trailing `+` or `?preview` shows a preview page instead of redirecting.",
+                    "parameters": [short_url_param(), query_param("pw", "Password of a protected link", false)],
+                    "responses": {
+                        "301": { "description": "Permanent redirect" },
+                        "307": { "description": "Temporary redirect" },
+                        "401": { "description": "Password form (HTML)" },
+                        "404": error_response("Unknown or expired short URL"),
+                        "410": error_response("Click limit reached or link deleted"),
+                        "508": error_response("Chain of short links loops or exceeds MAX_CHAIN_HOPS"),
+                    },
+                },
+                "post": {
+                    "summary": "Unlock a password-protected link",
+                    "parameters": [short_url_param()],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/x-www-form-urlencoded": {
+                                "schema": {
+                                    "type": "object",
+                                    "required": ["pw"],
+                                    "properties": { "pw": { "type": "string" } },
+                                },
+                            },
+                        },
+                    },
+                    "responses": {
+                        "303": { "description": "Correct password, redirect to the destination" },
+                        "401": { "description": "Wrong password, form shown again (HTML)" },
+                        "404": error_response("Unknown or expired short URL"),
+                    },
+                },
+                "delete": {
+                    "summary": "Delete a short URL",
+                    "security": api_key(),
+                    "parameters": [short_url_param()],
+                    "responses": {
+                        "204": { "description": "Deleted" },
+                        "404": error_response("Unknown short URL"),
+                        "503": error_response("Read-only mode or database unavailable"),
+                    },
+                },
+            },
+        },
+        "components": {
+            "securitySchemes": {
+                "apiKey": { "type": "http", "scheme": "bearer" },
+            },
+            "schemas": schemas(),
+        },
+    })
+}
+
+fn schemas() -> Value {
+    json!({
+        "ShortenRequest": {
+            "type": "object",
+            "required": ["long_url"],
+            "properties": {
+                "long_url": { "type": "string", "format": "uri", "description": "http/https, or another scheme from ALLOWED_SCHEMES" },
+                "short_url": { "type": "string", "description": "Custom alias, 3 to 32 characters of [a-zA-Z0-9_-]" },
+                "ttl_secs": { "type": "integer", "minimum": 1 },
+                "permanent": { "type": "boolean" },
+                "max_clicks": { "type": "integer", "minimum": 1 },
+                "password": { "type": "string" },
+                "force_new": { "type": "boolean" },
+                "geo": geo_destinations(),
+                "variants": variants(),
+                "tags": tags(),
+            },
+        },
+        "Url": {
+            "type": "object",
+            "required": ["long_url"],
+            "properties": {
+                "short_url": { "type": "string" },
+                "short_link": { "type": "string", "format": "uri" },
+                "long_url": { "type": "string", "format": "uri" },
+                "created_at": date_time(),
+                "ttl_secs": { "type": "integer" },
+                "permanent": { "type": "boolean" },
+                "max_clicks": { "type": "integer" },
+                "geo": geo_destinations(),
+                "variants": variants(),
+                "tags": tags(),
+                "created": { "type": "boolean", "description": "POST /shorten only: a new link was stored" },
+                "deduplicated": { "type": "boolean", "description": "POST /shorten only: an existing link was returned" },
+                "dry_run": { "type": "boolean", "description": "POST /shorten?dry_run=true only: nothing was stored" },
+            },
+        },
+        "BatchRequest": {
+            "type": "object",
+            "required": ["urls"],
+            "properties": {
+                "urls": { "type": "array", "items": { "type": "string", "format": "uri" } },
+            },
+        },
+        "BatchResponse": {
+            "type": "array",
+            "items": {
+                "type": "object",
+                "required": ["long_url"],
+                "properties": {
+                    "long_url": { "type": "string" },
+                    "short_url": { "type": "string" },
+                    "short_link": { "type": "string", "format": "uri" },
+                    "created_at": date_time(),
+                    "error": { "type": "string" },
+                },
+            },
+        },
+        "BatchDeleteRequest": {
+            "type": "object",
+            "required": ["short_urls"],
+            "properties": {
+                "short_urls": { "type": "array", "items": { "type": "string" } },
+            },
+        },
+        "BatchDeleteResponse": {
+            "type": "array",
+            "items": {
+                "type": "object",
+                "required": ["short_url", "status"],
+                "properties": {
+                    "short_url": { "type": "string" },
+                    "status": { "type": "string", "enum": ["deleted", "not_found", "invalid"] },
+                },
+            },
+        },
+        "AuditHistory": {
+            "type": "object",
+            "required": ["short_url", "entries"],
+            "properties": {
+                "short_url": { "type": "string" },
+                "entries": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["action"],
+                        "properties": {
+                            "action": { "type": "string", "enum": ["create", "update", "delete"] },
+                            "owner": { "type": "string", "description": "API key that performed the action" },
+                            "at": date_time(),
+                            "detail": { "type": "string" },
+                        },
+                    },
+                },
+            },
+        },
+        "Summary": {
+            "type": "object",
+            "required": ["total_links", "total_clicks", "links_last_24h", "generated_at"],
+            "properties": {
+                "total_links": { "type": "integer", "description": "Active links (expired and deleted ones excluded)" },
+                "total_clicks": { "type": "integer" },
+                "links_last_24h": { "type": "integer" },
+                "generated_at": date_time(),
+            },
+        },
+        "UrlPage": {
+            "type": "object",
+            "required": ["urls"],
+            "properties": {
+                "urls": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["short_url", "long_url"],
+                        "properties": {
+                            "short_url": { "type": "string" },
+                            "long_url": { "type": "string" },
+                            "created_at": date_time(),
+                            "owner": { "type": "string" },
+                            "tags": tags(),
+                        },
+                    },
+                },
+                "next_cursor": { "type": "string", "nullable": true },
+            },
+        },
+        "UrlDetails": {
+            "type": "object",
+            "required": ["short_url", "short_link", "long_url", "clicks", "password_protected"],
+            "properties": {
+                "short_url": { "type": "string" },
+                "short_link": { "type": "string", "format": "uri" },
+                "long_url": { "type": "string" },
+                "created_at": date_time(),
+                "last_accessed": date_time(),
+                "clicks": { "type": "integer" },
+                "password_protected": { "type": "boolean" },
+                "owner": { "type": "string" },
+                "permanent": { "type": "boolean" },
+                "max_clicks": { "type": "integer" },
+                "ttl_secs": { "type": "integer" },
+                "expires_at": date_time(),
+                "tags": tags(),
+            },
+        },
+        "UpdateUrl": {
+            "type": "object",
+            "required": ["long_url"],
+            "properties": { "long_url": { "type": "string", "format": "uri" } },
+        },
+        "ClicksHistory": {
+            "type": "object",
+            "required": ["short_url", "from", "to", "days"],
+            "properties": {
+                "short_url": { "type": "string" },
+                "from": { "type": "string", "format": "date" },
+                "to": { "type": "string", "format": "date" },
+                "days": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["day", "clicks"],
+                        "properties": {
+                            "day": { "type": "string", "format": "date" },
+                            "clicks": { "type": "integer", "description": "Estimated from the sample when CLICK_EVENTS_SAMPLE < 1" },
+                        },
+                    },
+                },
+            },
+        },
+        "UrlStats": {
+            "type": "object",
+            "required": ["short_url", "hits"],
+            "properties": {
+                "short_url": { "type": "string" },
+                "hits": { "type": "integer" },
+                "last_accessed": date_time(),
+                "last_cache_hit": { "type": "boolean" },
+                "variants": {
+                    "type": "array",
+                    "description": "Clicks per A/B variant",
+                    "items": {
+                        "type": "object",
+                        "required": ["name", "url", "weight", "hits"],
+                        "properties": {
+                            "name": { "type": "string" },
+                            "url": { "type": "string", "format": "uri" },
+                            "weight": { "type": "integer" },
+                            "hits": { "type": "integer" },
+                        },
+                    },
+                },
+            },
+        },
+        "ResolvedLink": {
+            "type": "object",
+            "required": ["short_url", "long_url"],
+            "properties": {
+                "short_url": { "type": "string" },
+                "long_url": { "type": "string", "format": "uri" },
+            },
+        },
+        "Availability": {
+            "type": "object",
+            "required": ["available"],
+            "properties": {
+                "available": { "type": "boolean" },
+                "reason": { "type": "string", "enum": ["invalid", "reserved", "taken", "aliases_disabled"] },
+            },
+        },
+        "ReadOnlyToggle": {
+            "type": "object",
+            "required": ["enabled"],
+            "properties": { "enabled": { "type": "boolean" } },
+        },
+        "ReadOnlyStatus": {
+            "type": "object",
+            "required": ["read_only"],
+            "properties": { "read_only": { "type": "boolean" } },
+        },
+        "Error": {
+            "type": "object",
+            "required": ["error", "code"],
+            "properties": {
+                "error": { "type": "string", "description": "Stable error identifier, e.g. not_found" },
+                "code": { "type": "integer" },
+                "message": { "type": "string" },
+                "errors": {
+                    "type": "array",
+                    "description": "Every invalid field (validation_failed only)",
+                    "items": {
+                        "type": "object",
+                        "required": ["field", "message"],
+                        "properties": {
+                            "field": { "type": "string" },
+                            "message": { "type": "string" },
+                        },
+                    },
+                },
+            },
+        },
+    })
+}
+
+fn geo_destinations() -> Value {
+    json!({
+        "type": "object",
+        "description": "Destination per ISO 3166-1 alpha-2 country code; other countries get long_url",
+        "additionalProperties": { "type": "string", "format": "uri" },
+    })
+}
+
+fn variants() -> Value {
+    json!({
+        "type": "array",
+        "description": "A/B destinations, picked by weight and kept per client with a cookie",
+        "items": {
+            "type": "object",
+            "required": ["name", "url", "weight"],
+            "properties": {
+                "name": { "type": "string" },
+                "url": { "type": "string", "format": "uri" },
+                "weight": { "type": "integer", "minimum": 1, "maximum": 10000 },
+            },
+        },
+    })
+}
+
+fn tags() -> Value {
+    json!({
+        "type": "array",
+        "description": "Lowercase tags, at most 10, each 1 to 32 letters, digits, '-' or '_'",
+        "maxItems": 10,
+        "items": { "type": "string", "maxLength": 32 },
+    })
+}
+
+fn date_time() -> Value {
+    json!({ "type": "string", "format": "date-time" })
+}
+
+fn api_key() -> Value {
+    json!([{ "apiKey": [] }])
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn json_body(schema: &str) -> Value {
+    json!({
+        "required": true,
+        "content": { "application/json": { "schema": schema_ref(schema) } },
+    })
+}
+
+fn error_response(description: &str) -> Value {
+    json!({
+        "description": description,
+        "content": { "application/json": { "schema": schema_ref("Error") } },
+    })
+}
+
+fn responses(entries: &[(&str, &str, Option<&str>)]) -> Value {
+    let mut map = serde_json::Map::new();
+    for (status, description, schema) in entries {
+        let mut response = json!({ "description": description });
+        if let Some(schema) = schema {
+            response["content"] = json!({ "application/json": { "schema": schema_ref(schema) } });
+        }
+        map.insert(status.to_string(), response);
+    }
+    Value::Object(map)
+}
+
+fn short_url_param() -> Value {
+    json!({ "name": "short_url", "in": "path", "required": true, "schema": { "type": "string" } })
+}
+
+fn query_param(name: &str, description: &str, required: bool) -> Value {
+    json!({
+        "name": name,
+        "in": "query",
+        "required": required,
+        "description": description,
+        "schema": { "type": "string" },
+    })
+}
+
+/// Conferência dos schemas com os structs dos handlers, para os testes de cada
+/// módulo: os `properties` de um schema são exatamente os campos do struct
+#[cfg(test)]
+pub mod schema_fields {
+    use serde::de::{self, value::Error, Deserialize, Visitor};
+    use serde::ser::{self, Impossible, Serialize};
+    use std::collections::BTreeSet;
+
+    /// Campos de um struct serializado, inclusive os omitidos por `skip_serializing_if`
+    pub fn assert_response<T: Serialize>(schema: &str, sample: &T) {
+        let mut fields = Vec::new();
+        sample.serialize(FieldNames(&mut fields)).expect("a struct");
+        assert_eq!(properties(schema), fields.into_iter().map(str::to_string).collect(), "schema {}", schema);
+    }
+
+    /// Campos que um struct aceita na desserialização
+    pub fn assert_request<'de, T: Deserialize<'de>>(schema: &str) {
+        let mut fields = None;
+        let _ = T::deserialize(StructFields(&mut fields));
+        let fields = fields.expect("a struct");
+        assert_eq!(properties(schema), fields.iter().map(|f| f.to_string()).collect(), "schema {}", schema);
+    }
+
+    /// `schema` é um caminho a partir de `components/schemas`, ex: `BatchResponse/items`
+    fn properties(schema: &str) -> BTreeSet<String> {
+        let doc = super::document();
+        let properties = doc
+            .pointer(&format!("/components/schemas/{}/properties", schema))
+            .and_then(|properties| properties.as_object())
+            .unwrap_or_else(|| panic!("no properties in schema {}", schema));
+        properties.keys().cloned().collect()
+    }
+
+    struct FieldNames<'a>(&'a mut Vec<&'static str>);
+
+    macro_rules! not_a_struct {
+        ($($method:ident($($arg:ty),*) -> $ok:ty;)*) => {
+            $(fn $method(self, $(_: $arg),*) -> Result<$ok, Error> {
+                Err(ser::Error::custom("not a struct"))
+            })*
+        };
+    }
+
+    impl<'a> ser::Serializer for FieldNames<'a> {
+        type Ok = ();
+        type Error = Error;
+        type SerializeSeq = Impossible<(), Error>;
+        type SerializeTuple = Impossible<(), Error>;
+        type SerializeTupleStruct = Impossible<(), Error>;
+        type SerializeTupleVariant = Impossible<(), Error>;
+        type SerializeMap = Impossible<(), Error>;
+        type SerializeStruct = Self;
+        type SerializeStructVariant = Impossible<(), Error>;
+
+        fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self, Error> {
+            Ok(self)
+        }
+
+        fn serialize_some<T: Serialize + ?Sized>(self, _: &T) -> Result<(), Error> {
+            Err(ser::Error::custom("not a struct"))
+        }
+
+        fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _: &'static str, _: &T) -> Result<(), Error> {
+            Err(ser::Error::custom("not a struct"))
+        }
+
+        fn serialize_newtype_variant<T: Serialize + ?Sized>(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: &T,
+        ) -> Result<(), Error> {
+            Err(ser::Error::custom("not a struct"))
+        }
+
+        not_a_struct! {
+            serialize_bool(bool) -> ();
+            serialize_i8(i8) -> ();
+            serialize_i16(i16) -> ();
+            serialize_i32(i32) -> ();
+            serialize_i64(i64) -> ();
+            serialize_u8(u8) -> ();
+            serialize_u16(u16) -> ();
+            serialize_u32(u32) -> ();
+            serialize_u64(u64) -> ();
+            serialize_f32(f32) -> ();
+            serialize_f64(f64) -> ();
+            serialize_char(char) -> ();
+            serialize_str(&str) -> ();
+            serialize_bytes(&[u8]) -> ();
+            serialize_none() -> ();
+            serialize_unit() -> ();
+            serialize_unit_struct(&'static str) -> ();
+            serialize_unit_variant(&'static str, u32, &'static str) -> ();
+            serialize_seq(Option<usize>) -> Self::SerializeSeq;
+            serialize_tuple(usize) -> Self::SerializeTuple;
+            serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+            serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+            serialize_map(Option<usize>) -> Self::SerializeMap;
+            serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+        }
+    }
+
+    impl<'a> ser::SerializeStruct for FieldNames<'a> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, _: &T) -> Result<(), Error> {
+            self.0.push(key);
+            Ok(())
+        }
+
+        fn skip_field(&mut self, key: &'static str) -> Result<(), Error> {
+            self.0.push(key);
+            Ok(())
+        }
+
+        fn end(self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    struct StructFields<'a>(&'a mut Option<&'static [&'static str]>);
+
+    impl<'de, 'a> de::Deserializer<'de> for StructFields<'a> {
+        type Error = Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Error> {
+            Err(de::Error::custom("not a struct"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _: &'static str,
+            fields: &'static [&'static str],
+            _: V,
+        ) -> Result<V::Value, Error> {
+            *self.0 = Some(fields);
+            Err(de::Error::custom("fields recorded"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+            unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_schema_reference_resolves() {
+        let doc = document();
+        let text = doc.to_string();
+        for reference in text.split("\"#/components/schemas/").skip(1) {
+            let name = &reference[..reference.find('"').unwrap()];
+            assert!(doc["components"]["schemas"].get(name).is_some(), "missing schema {}", name);
+        }
+    }
+
+    #[test]
+    fn request_schemas_match_structs() {
+        use schema_fields::assert_request;
+        assert_request::<crate::ShortenRequest>("ShortenRequest");
+        assert_request::<crate::Variant>("ShortenRequest/properties/variants/items");
+        assert_request::<crate::UpdateUrl>("UpdateUrl");
+        assert_request::<crate::batch::BatchRequest>("BatchRequest");
+        assert_request::<crate::batch::BatchDeleteRequest>("BatchDeleteRequest");
+        assert_request::<crate::maintenance::ReadOnlyToggle>("ReadOnlyToggle");
+        assert_request::<crate::summary::Summary>("Summary");
+    }
+
+    #[test]
+    fn response_schemas_match_structs() {
+        use schema_fields::assert_response;
+        let url = crate::Url {
+            short_url: None,
+            short_link: None,
+            long_url: String::new(),
+            created_at: None,
+            ttl_secs: None,
+            permanent: None,
+            max_clicks: None,
+            geo: None,
+            variants: None,
+            tags: None,
+            created: None,
+            deduplicated: None,
+            dry_run: None,
+        };
+        assert_response("Url", &url);
+        let details = crate::UrlDetails {
+            short_url: String::new(),
+            short_link: String::new(),
+            long_url: String::new(),
+            created_at: None,
+            last_accessed: None,
+            clicks: 0,
+            password_protected: false,
+            owner: None,
+            tags: None,
+            permanent: None,
+            max_clicks: None,
+            ttl_secs: None,
+            expires_at: None,
+        };
+        assert_response("UrlDetails", &details);
+        let stats = crate::UrlStats {
+            short_url: String::new(),
+            hits: 0,
+            last_accessed: None,
+            last_cache_hit: None,
+            variants: None,
+        };
+        assert_response("UrlStats", &stats);
+        let resolved = crate::ResolvedLink {
+            short_url: String::new(),
+            long_url: String::new(),
+        };
+        assert_response("ResolvedLink", &resolved);
+        assert_response("Availability", &crate::Availability::unavailable("taken"));
+    }
+}
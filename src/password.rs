@@ -0,0 +1,44 @@
+/// Contexto do `derive_key` do blake3 para senhas de links
+const HASH_CONTEXT: &str = "url-shortener 2024-01-01 link password";
+
+/// Tamanho máximo aceito para a senha de um link (bytes)
+pub const MAX_PASSWORD_LEN: usize = 256;
+
+/// Hash (hex) da senha de um link.
+///
+/// O código entra no hash para que a mesma senha gere valores diferentes em
+/// links diferentes.
+pub fn hash_password(short_url: &str, password: &str) -> String {
+    let mut hasher = blake3::Hasher::new_derive_key(HASH_CONTEXT);
+    hasher.update(short_url.as_bytes());
+    hasher.update(&[0]);
+    hasher.update(password.as_bytes());
+    hasher.finalize().to_hex().to_string()
+}
+
+/// Confere a senha informada contra o hash guardado, em tempo constante
+pub fn verify_password(short_url: &str, password: &str, stored_hash: &str) -> bool {
+    constant_time_eq(
+        hash_password(short_url, password).as_bytes(),
+        stored_hash.as_bytes(),
+    )
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifies_only_the_right_password() {
+        let hash = hash_password("launch", "hunter2");
+        assert!(verify_password("launch", "hunter2", &hash));
+        assert!(!verify_password("launch", "hunter3", &hash));
+        // Mesma senha em outro link não reaproveita o hash
+        assert!(!verify_password("other", "hunter2", &hash));
+        assert!(!verify_password("launch", "hunter2", ""));
+    }
+}
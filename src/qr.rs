@@ -0,0 +1,702 @@
+// Adaptado do QR Code generator library de Project Nayuki (versão em Rust):
+// a codificação em modo byte, a correção Reed-Solomon (`reed_solomon_*`,
+// `add_ecc_and_interleave`), o desenho da matriz e a penalidade das máscaras
+// (`finder_*`) seguem a estrutura do original, reduzida ao nível M e às versões 1 a 10.
+//
+// Copyright (c) Project Nayuki. (MIT License)
+// https://www.nayuki.io/page/qr-code-generator-library
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+// - The above copyright notice and this permission notice shall be included in
+//   all copies or substantial portions of the Software.
+// - The Software is provided "as is", without warranty of any kind, express or
+//   implied, including but not limited to the warranties of merchantability,
+//   fitness for a particular purpose and noninfringement. In no event shall the
+//   authors or copyright holders be liable for any claim, damages or other
+//   liability, whether in an action of contract, tort or otherwise, arising from,
+//   out of or in connection with the Software or the use or other dealings in the
+//   Software.
+
+//! Gerador de QR Code em modo byte, com correção de erro nível M (~15%).
+//!
+//! Implementa o necessário para codificar um short link: versões 1 a 10
+//! (até 213 bytes), escolha de máscara pela penalidade da especificação e
+//! saída em SVG ou PNG (1 bit por pixel, sem compressão).
+
+/// Borda clara exigida pela especificação, em módulos
+const QUIET_ZONE: usize = 4;
+
+const MAX_VERSION: usize = 10;
+
+/// Codewords de correção por bloco e número de blocos, nível M, indexados pela versão
+const ECC_CODEWORDS_PER_BLOCK: [usize; MAX_VERSION + 1] = [0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26];
+const NUM_ECC_BLOCKS: [usize; MAX_VERSION + 1] = [0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5];
+
+/// Bits de formato do nível M
+const ECC_LEVEL_M_BITS: u32 = 0b00;
+
+/// Matriz de um QR Code pronto (`true` = módulo escuro)
+pub struct QrCode {
+    size: usize,
+    modules: Vec<bool>,
+}
+
+impl QrCode {
+    /// Codifica `data` na menor versão que comportar; `None` se não couber na versão 10
+    pub fn encode(data: &[u8]) -> Option<Self> {
+        let version = (1..=MAX_VERSION).find(|&v| data_bits(v, data.len()) <= num_data_codewords(v) * 8)?;
+        Some(Self::encode_version(version, data, None).0)
+    }
+
+    /// Codifica `data` na versão dada, com a máscara `mask` ou, sem ela, a de menor
+    /// penalidade. Retorna também a máscara usada
+    fn encode_version(version: usize, data: &[u8], mask: Option<u8>) -> (Self, u8) {
+        let codewords = add_ecc_and_interleave(version, &encode_data(version, data));
+
+        let mut builder = Builder::new(version);
+        builder.draw_function_patterns();
+        builder.draw_codewords(&codewords);
+
+        // Escolhe a máscara de menor penalidade (aplicar duas vezes desfaz)
+        let mask = mask.unwrap_or_else(|| {
+            let mut best = (0, i32::MAX);
+            for mask in 0..8 {
+                builder.apply_mask(mask);
+                builder.draw_format_bits(mask);
+                let penalty = builder.penalty_score();
+                if penalty < best.1 {
+                    best = (mask, penalty);
+                }
+                builder.apply_mask(mask);
+            }
+            best.0
+        });
+        builder.apply_mask(mask);
+        builder.draw_format_bits(mask);
+
+        let qr = QrCode {
+            size: builder.size,
+            modules: builder.modules,
+        };
+        (qr, mask)
+    }
+
+    /// Cor do módulo; coordenadas fora da matriz (borda) são claras
+    fn is_dark(&self, x: isize, y: isize) -> bool {
+        let size = self.size as isize;
+        (0..size).contains(&x) && (0..size).contains(&y) && self.modules[y as usize * self.size + x as usize]
+    }
+
+    /// Lado da imagem em pixels, incluindo a borda
+    pub fn image_size(&self, module_px: u32) -> u32 {
+        (self.size + 2 * QUIET_ZONE) as u32 * module_px
+    }
+
+    pub fn to_svg(&self, module_px: u32) -> String {
+        let dim = self.size + 2 * QUIET_ZONE;
+        let px = self.image_size(module_px);
+
+        let mut path = String::new();
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if self.modules[y * self.size + x] {
+                    path.push_str(&format!("M{},{}h1v1h-1z", x + QUIET_ZONE, y + QUIET_ZONE));
+                }
+            }
+        }
+
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {dim} {dim}\" width=\"{px}\" height=\"{px}\" shape-rendering=\"crispEdges\">\
+<rect width=\"100%\" height=\"100%\" fill=\"#fff\"/><path d=\"{path}\" fill=\"#000\"/></svg>\n"
+        )
+    }
+
+    pub fn to_png(&self, module_px: u32) -> Vec<u8> {
+        let px = self.image_size(module_px) as usize;
+        let row_bytes = px.div_ceil(8);
+        let quiet = QUIET_ZONE as isize;
+
+        // Tons de cinza com 1 bit: 0 = preto, 1 = branco. Cada linha começa com o filtro 0.
+        let mut raw = Vec::with_capacity((row_bytes + 1) * px);
+        for py in 0..px {
+            raw.push(0);
+            let y = (py / module_px as usize) as isize - quiet;
+            let mut byte = 0u8;
+            for px_x in 0..row_bytes * 8 {
+                let x = (px_x / module_px as usize) as isize - quiet;
+                let white = px_x >= px || !self.is_dark(x, y);
+                byte = (byte << 1) | white as u8;
+                if px_x % 8 == 7 {
+                    raw.push(byte);
+                    byte = 0;
+                }
+            }
+        }
+
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend_from_slice(&(px as u32).to_be_bytes());
+        ihdr.extend_from_slice(&(px as u32).to_be_bytes());
+        ihdr.extend_from_slice(&[1, 0, 0, 0, 0]); // 1 bit, tons de cinza, deflate, filtro 0, sem entrelaçamento
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png_chunk(&mut png, b"IHDR", &ihdr);
+        png_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+        png_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+}
+
+/// Bits ocupados pelos dados em modo byte: modo (4) + contagem + 8 por byte
+fn data_bits(version: usize, len: usize) -> usize {
+    4 + char_count_bits(version) + len * 8
+}
+
+fn char_count_bits(version: usize) -> usize {
+    if version <= 9 {
+        8
+    } else {
+        16
+    }
+}
+
+/// Módulos disponíveis para dados + correção, descontando os padrões fixos
+fn num_raw_data_modules(version: usize) -> usize {
+    let mut result = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let num_align = version / 7 + 2;
+        result -= (25 * num_align - 10) * num_align - 55;
+        if version >= 7 {
+            result -= 36;
+        }
+    }
+    result
+}
+
+fn num_data_codewords(version: usize) -> usize {
+    num_raw_data_modules(version) / 8 - ECC_CODEWORDS_PER_BLOCK[version] * NUM_ECC_BLOCKS[version]
+}
+
+/// Segmento em modo byte, terminador e bytes de preenchimento até a capacidade da versão
+fn encode_data(version: usize, data: &[u8]) -> Vec<u8> {
+    let capacity = num_data_codewords(version) * 8;
+    let mut bits = BitBuffer::default();
+    bits.push(0b0100, 4);
+    bits.push(data.len() as u32, char_count_bits(version));
+    for &byte in data {
+        bits.push(byte as u32, 8);
+    }
+
+    bits.push(0, (capacity - bits.len).min(4));
+    bits.push(0, (8 - bits.len % 8) % 8);
+    for pad in [0xEC, 0x11].into_iter().cycle() {
+        if bits.len >= capacity {
+            break;
+        }
+        bits.push(pad, 8);
+    }
+    bits.bytes
+}
+
+#[derive(Default)]
+struct BitBuffer {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl BitBuffer {
+    fn push(&mut self, value: u32, count: usize) {
+        for i in (0..count).rev() {
+            if self.len.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if (value >> i) & 1 == 1 {
+                *self.bytes.last_mut().unwrap() |= 0x80 >> (self.len % 8);
+            }
+            self.len += 1;
+        }
+    }
+}
+
+/// Divide os dados em blocos, calcula a correção Reed-Solomon de cada um e intercala
+fn add_ecc_and_interleave(version: usize, data: &[u8]) -> Vec<u8> {
+    let num_blocks = NUM_ECC_BLOCKS[version];
+    let ecc_len = ECC_CODEWORDS_PER_BLOCK[version];
+    let raw_codewords = num_raw_data_modules(version) / 8;
+    let num_short_blocks = num_blocks - raw_codewords % num_blocks;
+    let short_block_len = raw_codewords / num_blocks;
+
+    let divisor = reed_solomon_divisor(ecc_len);
+    let mut blocks = Vec::with_capacity(num_blocks);
+    let mut offset = 0;
+    for i in 0..num_blocks {
+        let data_len = short_block_len - ecc_len + usize::from(i >= num_short_blocks);
+        let chunk = &data[offset..offset + data_len];
+        offset += data_len;
+
+        let mut block = chunk.to_vec();
+        // Blocos curtos ganham um byte fictício para alinhar a intercalação
+        if i < num_short_blocks {
+            block.push(0);
+        }
+        block.extend(reed_solomon_remainder(chunk, &divisor));
+        blocks.push(block);
+    }
+
+    let mut result = Vec::with_capacity(raw_codewords);
+    for i in 0..blocks[0].len() {
+        for (j, block) in blocks.iter().enumerate() {
+            if i != short_block_len - ecc_len || j >= num_short_blocks {
+                result.push(block[i]);
+            }
+        }
+    }
+    result
+}
+
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0u8; degree];
+    result[degree - 1] = 1;
+    let mut root = 1u8;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    result
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0u8; divisor.len()];
+    for &byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (x, &y) in result.iter_mut().zip(divisor) {
+            *x ^= gf_multiply(y, factor);
+        }
+    }
+    result
+}
+
+/// Multiplicação em GF(2^8) com o polinômio 0x11D
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u32 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= ((y as u32 >> i) & 1) * x as u32;
+    }
+    z as u8
+}
+
+/// Matriz em construção, marcando quais módulos pertencem a padrões fixos
+struct Builder {
+    version: usize,
+    size: usize,
+    modules: Vec<bool>,
+    function: Vec<bool>,
+}
+
+impl Builder {
+    fn new(version: usize) -> Self {
+        let size = version * 4 + 17;
+        Builder {
+            version,
+            size,
+            modules: vec![false; size * size],
+            function: vec![false; size * size],
+        }
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self) {
+        // Padrões de temporização
+        for i in 0..self.size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+
+        // Padrões de localização (três cantos), com separadores
+        let far = self.size - 4;
+        self.draw_finder(3, 3);
+        self.draw_finder(far, 3);
+        self.draw_finder(3, far);
+
+        // Padrões de alinhamento, exceto onde colidem com os de localização
+        let positions = self.alignment_positions();
+        let n = positions.len();
+        for (i, &x) in positions.iter().enumerate() {
+            for (j, &y) in positions.iter().enumerate() {
+                let overlaps_finder = (i == 0 && (j == 0 || j == n - 1)) || (i == n - 1 && j == 0);
+                if !overlaps_finder {
+                    self.draw_alignment(x, y);
+                }
+            }
+        }
+
+        // Reserva as áreas de formato (preenchidas depois, com a máscara) e de versão
+        self.draw_format_bits(0);
+        self.draw_version();
+    }
+
+    fn draw_finder(&mut self, cx: usize, cy: usize) {
+        for dy in -4isize..=4 {
+            for dx in -4isize..=4 {
+                let x = cx as isize + dx;
+                let y = cy as isize + dy;
+                if (0..self.size as isize).contains(&x) && (0..self.size as isize).contains(&y) {
+                    let dist = dx.abs().max(dy.abs());
+                    self.set_function(x as usize, y as usize, dist != 2 && dist != 4);
+                }
+            }
+        }
+    }
+
+    fn draw_alignment(&mut self, cx: usize, cy: usize) {
+        for dy in -2isize..=2 {
+            for dx in -2isize..=2 {
+                let x = (cx as isize + dx) as usize;
+                let y = (cy as isize + dy) as usize;
+                self.set_function(x, y, dx.abs().max(dy.abs()) != 1);
+            }
+        }
+    }
+
+    fn alignment_positions(&self) -> Vec<usize> {
+        if self.version == 1 {
+            return Vec::new();
+        }
+        let num_align = self.version / 7 + 2;
+        let step = (self.version * 4 + num_align * 2 + 1) / (num_align * 2 - 2) * 2;
+        let mut result = vec![6];
+        let mut pos = self.size - 7;
+        for _ in 0..num_align - 1 {
+            result.insert(1, pos);
+            pos -= step;
+        }
+        result
+    }
+
+    fn draw_format_bits(&mut self, mask: u8) {
+        let bits = format_bits(mask);
+        let bit = |i: usize| (bits >> i) & 1 == 1;
+
+        // Primeira cópia, ao redor do padrão superior esquerdo
+        for i in 0..=5 {
+            self.set_function(8, i, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i));
+        }
+
+        // Segunda cópia, dividida entre os outros dois padrões
+        let size = self.size;
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i));
+        }
+        // Módulo sempre escuro
+        self.set_function(8, size - 8, true);
+    }
+
+    fn draw_version(&mut self) {
+        if self.version < 7 {
+            return;
+        }
+        let bits = version_bits(self.version);
+        for i in 0..18 {
+            let dark = (bits >> i) & 1 == 1;
+            let a = self.size - 11 + i % 3;
+            let b = i / 3;
+            self.set_function(a, b, dark);
+            self.set_function(b, a, dark);
+        }
+    }
+
+    /// Preenche os módulos livres em zigue-zague, de baixo para cima, em colunas duplas
+    fn draw_codewords(&mut self, data: &[u8]) {
+        let mut i = 0;
+        let mut right = self.size - 1;
+        while right >= 1 {
+            // A coluna 6 é da temporização
+            if right == 6 {
+                right = 5;
+            }
+            for vert in 0..self.size {
+                for j in 0..2 {
+                    let x = right - j;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward { self.size - 1 - vert } else { vert };
+                    if !self.function[y * self.size + x] && i < data.len() * 8 {
+                        self.modules[y * self.size + x] = (data[i >> 3] >> (7 - (i & 7))) & 1 == 1;
+                        i += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    fn apply_mask(&mut self, mask: u8) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let index = y * self.size + x;
+                self.modules[index] ^= invert && !self.function[index];
+            }
+        }
+    }
+
+    /// Penalidade da especificação: sequências, blocos 2x2, padrões parecidos com os
+    /// de localização e desequilíbrio entre módulos claros e escuros
+    fn penalty_score(&self) -> i32 {
+        const N1: i32 = 3;
+        const N2: i32 = 3;
+        const N3: i32 = 40;
+        const N4: i32 = 10;
+
+        let size = self.size;
+        let mut result = 0;
+
+        for horizontal in [true, false] {
+            for a in 0..size {
+                let mut run_color = false;
+                let mut run_len = 0;
+                let mut history = [0usize; 7];
+                for b in 0..size {
+                    let color = if horizontal { self.get(b, a) } else { self.get(a, b) };
+                    if color == run_color {
+                        run_len += 1;
+                        if run_len == 5 {
+                            result += N1;
+                        } else if run_len > 5 {
+                            result += 1;
+                        }
+                    } else {
+                        self.finder_history_add(run_len, &mut history);
+                        if !run_color {
+                            result += self.finder_count_patterns(&history) * N3;
+                        }
+                        run_color = color;
+                        run_len = 1;
+                    }
+                }
+                result += self.finder_terminate_and_count(run_color, run_len, &mut history) * N3;
+            }
+        }
+
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let color = self.get(x, y);
+                if color == self.get(x + 1, y) && color == self.get(x, y + 1) && color == self.get(x + 1, y + 1) {
+                    result += N2;
+                }
+            }
+        }
+
+        let dark = self.modules.iter().filter(|&&m| m).count() as i32;
+        let total = (size * size) as i32;
+        let k = ((dark * 20 - total * 10).abs() + total - 1) / total - 1;
+        result += k * N4;
+
+        result
+    }
+
+    fn finder_history_add(&self, mut run_len: usize, history: &mut [usize; 7]) {
+        // A primeira sequência conta com a borda clara
+        if history[0] == 0 {
+            run_len += self.size;
+        }
+        history.copy_within(0..6, 1);
+        history[0] = run_len;
+    }
+
+    fn finder_count_patterns(&self, history: &[usize; 7]) -> i32 {
+        let n = history[1];
+        let core = n > 0 && history[2] == n && history[3] == n * 3 && history[4] == n && history[5] == n;
+        i32::from(core && history[0] >= n * 4 && history[6] >= n) + i32::from(core && history[6] >= n * 4 && history[0] >= n)
+    }
+
+    fn finder_terminate_and_count(&self, run_color: bool, mut run_len: usize, history: &mut [usize; 7]) -> i32 {
+        if run_color {
+            self.finder_history_add(run_len, history);
+            run_len = 0;
+        }
+        run_len += self.size;
+        self.finder_history_add(run_len, history);
+        self.finder_count_patterns(history)
+    }
+}
+
+/// Nível de correção + máscara, protegidos por BCH(15,5) e mascarados com 0x5412
+fn format_bits(mask: u8) -> u32 {
+    let data = (ECC_LEVEL_M_BITS << 3) | mask as u32;
+    let mut rem = data;
+    for _ in 0..10 {
+        rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+    }
+    ((data << 10) | rem) ^ 0x5412
+}
+
+/// Número da versão protegido por BCH(18,6), a partir da versão 7
+fn version_bits(version: usize) -> u32 {
+    let mut rem = version as u32;
+    for _ in 0..12 {
+        rem = (rem << 1) ^ ((rem >> 11) * 0x1F25);
+    }
+    ((version as u32) << 12) | rem
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Fluxo zlib com blocos deflate sem compressão (QR Codes pequenos não compensam um compressor)
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut chunks = data.chunks(u16::MAX as usize).peekable();
+    if chunks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        let len = chunk.len() as u16;
+        out.push(last as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(chunk);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_and_version_bits_match_spec_tables() {
+        assert_eq!(format_bits(0), 0b101010000010010);
+        assert_eq!(format_bits(5), 0b100000011001110);
+        assert_eq!(version_bits(7), 0b000111110010010100);
+    }
+
+    #[test]
+    fn picks_smallest_version() {
+        assert_eq!(QrCode::encode(b"https://sho.rt/2tx").unwrap().size, 25);
+        assert_eq!(QrCode::encode(&[b'a'; 213]).unwrap().size, 57);
+        assert!(QrCode::encode(&[b'a'; 214]).is_none());
+    }
+
+    /// Saída do encoder de referência (crate `qrcodegen` 1.8.0, `QrCodeEcc::Medium`,
+    /// modo byte, sem aumento do nível de correção) para `https://sho.rt/abc` na versão 2
+    const REFERENCE_V2_MASK_3: [&str; 25] = [
+        "#######.#...#...#.#######",
+        "#.....#.###..##...#.....#",
+        "#.###.#...#..####.#.###.#",
+        "#.###.#.###.#..#..#.###.#",
+        "#.###.#..#...####.#.###.#",
+        "#.....#..#.#..#...#.....#",
+        "#######.#.#.#.#.#.#######",
+        "........##.#.##..........",
+        "#.##.###..#####.#.#..#.##",
+        "....##.#.##.#...#..#...#.",
+        "####..##..###.#.####.....",
+        "##..##....#.####...#.##..",
+        "#..#.##..#.......####.###",
+        "..#.....#..#.#.#.####...#",
+        ".#..###..##.#.#.###.#.##.",
+        "#...#.......##.###.##...#",
+        ".....###...#....#########",
+        "........###.##.##...#.#.#",
+        "#######.##.##...#.#.#.###",
+        "#.....#.#.#.#..##...#..##",
+        "#.###.#...####..######...",
+        "#.###.#.##..#####.#.#####",
+        "#.###.#.######...##.#.##.",
+        "#.....#........#.##.#.#..",
+        "#######.##..#.#....######",
+    ];
+
+    fn rows(qr: &QrCode) -> Vec<String> {
+        qr.modules
+            .chunks(qr.size)
+            .map(|row| row.iter().map(|&dark| if dark { '#' } else { '.' }).collect())
+            .collect()
+    }
+
+    #[test]
+    fn matches_reference_encoder() {
+        let data = b"https://sho.rt/abc";
+        let (qr, _) = QrCode::encode_version(2, data, Some(3));
+        assert_eq!(rows(&qr), REFERENCE_V2_MASK_3);
+
+        // A penalidade escolhe a mesma máscara que o encoder de referência (0)
+        let (_, mask) = QrCode::encode_version(2, data, None);
+        assert_eq!(mask, 0);
+        assert_eq!(QrCode::encode(data).unwrap().size, 25);
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+}
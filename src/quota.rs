@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use futures::StreamExt;
+use redis::Script;
+use tracing::{info, warn};
+
+use crate::redis_pool::RedisConnection;
+use crate::{AppError, AppState};
+
+/// Reserva `ARGV[1]` links na cota se couberem no limite `ARGV[2]`.
+///
+/// Retorna o novo total, -1 se o contador ainda não existe (precisa ser semeado
+/// a partir do Cassandra) ou -2 se a cota estourou. Conferir e incrementar no
+/// mesmo script evita que criações simultâneas passem juntas do limite.
+const RESERVE_SCRIPT: &str = r"
+local current = redis.call('GET', KEYS[1])
+if not current then
+  return -1
+end
+if tonumber(current) + tonumber(ARGV[1]) > tonumber(ARGV[2]) then
+  return -2
+end
+return redis.call('INCRBY', KEYS[1], ARGV[1])
+";
+
+/// Devolve `ARGV[1]` links à cota, sem deixar o contador negativo
+const RELEASE_SCRIPT: &str = r"
+local current = redis.call('GET', KEYS[1])
+if not current then
+  return 0
+end
+local released = math.min(tonumber(ARGV[1]), math.max(tonumber(current), 0))
+return redis.call('DECRBY', KEYS[1], released)
+";
+
+/// Por quanto tempo o contador vale antes de ser recontado no Cassandra (segundos).
+///
+/// Links que expiram por TTL não passam pelo `release`; a recontagem diária
+/// corrige essa diferença.
+const COUNTER_TTL_SECS: u64 = 86_400;
+
+/// Limite de links ativos por dono, lido de `LINK_QUOTA` e `LINK_QUOTAS`.
+///
+/// `LINK_QUOTAS` aceita entradas `identificador:limite` separadas por vírgula,
+/// que sobrescrevem o padrão para as API keys citadas. Limite 0 = sem cota.
+#[derive(Default)]
+pub struct LinkQuotas {
+    default_limit: u64,
+    per_owner: HashMap<String, u64>,
+}
+
+impl LinkQuotas {
+    pub fn parse(default_limit: u64, overrides: &str) -> Result<Self, String> {
+        let mut per_owner = HashMap::new();
+        for entry in overrides.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (owner, limit) = entry
+                .split_once(':')
+                .ok_or_else(|| format!("Invalid LINK_QUOTAS entry '{}': expected 'key_id:limit'", entry))?;
+            let limit = limit
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| format!("Invalid LINK_QUOTAS entry '{}': limit must be a number", entry))?;
+            per_owner.insert(owner.trim().to_string(), limit);
+        }
+        Ok(LinkQuotas {
+            default_limit,
+            per_owner,
+        })
+    }
+
+    /// Limite do dono, `None` se ele não tem cota
+    pub fn limit_for(&self, owner: &str) -> Option<u64> {
+        let limit = self.per_owner.get(owner).copied().unwrap_or(self.default_limit);
+        (limit > 0).then_some(limit)
+    }
+}
+
+fn counter_key(owner: &str) -> String {
+    format!("quota:{}", owner)
+}
+
+/// Reserva `count` links na cota do dono, falhando com 403 `quota_exceeded`
+/// se não couberem. Sem dono (criação anônima) ou sem limite, não faz nada.
+///
+/// Se a criação falhar depois da reserva, devolva-a com [`release`].
+pub async fn reserve(
+    state: &AppState,
+    redis_conn: &mut RedisConnection,
+    owner: Option<&str>,
+    count: u64,
+) -> Result<(), AppError> {
+    let Some((owner, limit)) = owner.and_then(|o| Some((o, state.config.link_quotas.limit_for(o)?))) else {
+        return Ok(());
+    };
+
+    // No máximo duas voltas: a segunda já encontra o contador semeado
+    for _ in 0..2 {
+        let reserved: i64 = Script::new(RESERVE_SCRIPT)
+            .key(counter_key(owner))
+            .arg(count)
+            .arg(limit)
+            .invoke_async(redis_conn)
+            .await?;
+        match reserved {
+            -1 => seed(state, redis_conn, owner).await?,
+            -2 => {
+                warn!(owner, limit, "Link quota exceeded");
+                return Err(AppError::QuotaExceeded { limit });
+            }
+            _ => return Ok(()),
+        }
+    }
+    Err(AppError::Internal(format!("quota counter for '{}' could not be seeded", owner)))
+}
+
+/// Devolve `count` links à cota do dono (remoção, ou criação que falhou depois da reserva)
+pub async fn release(redis_conn: &mut RedisConnection, owner: Option<&str>, count: u64) {
+    let Some(owner) = owner else {
+        return;
+    };
+    let released: redis::RedisResult<i64> = Script::new(RELEASE_SCRIPT)
+        .key(counter_key(owner))
+        .arg(count)
+        .invoke_async(redis_conn)
+        .await;
+    if let Err(e) = released {
+        warn!("Redis quota error: {}", e);
+    }
+}
+
+/// Conta no Cassandra os links ativos do dono e grava o contador, se outra
+/// requisição ainda não o tiver feito
+async fn seed(
+    state: &AppState,
+    redis_conn: &mut RedisConnection,
+    owner: &str,
+) -> Result<(), AppError> {
+    let mut rows = state
+        .cassandra_breaker
+        .run(
+            state
+                .cassandra
+                .execute_iter(state.statements.list_urls_by_owner.clone(), (owner.to_string(),)),
+        )
+        .await?;
+    let mut active: u64 = 0;
+    while let Some(row) = rows.next().await {
+        row.map_err(|e| AppError::Internal(format!("urls row: {}", e)))?;
+        active += 1;
+    }
+
+    let _: Option<String> = redis::cmd("SET")
+        .arg(counter_key(owner))
+        .arg(active)
+        .arg("NX")
+        .arg("EX")
+        .arg(COUNTER_TTL_SECS)
+        .query_async(redis_conn)
+        .await?;
+    info!(owner, active, "Seeded link quota counter");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_per_owner_overrides() {
+        let quotas = LinkQuotas::parse(100, "acme:1000, free:0").unwrap();
+        assert_eq!(quotas.limit_for("acme"), Some(1000));
+        assert_eq!(quotas.limit_for("other"), Some(100));
+        assert_eq!(quotas.limit_for("free"), None);
+        assert_eq!(LinkQuotas::parse(0, "").unwrap().limit_for("acme"), None);
+        assert!(LinkQuotas::parse(0, "acme").is_err());
+        assert!(LinkQuotas::parse(0, "acme:lots").is_err());
+    }
+}
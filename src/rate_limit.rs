@@ -0,0 +1,152 @@
+use axum::{
+    extract::{ConnectInfo, Extension, Request},
+    http::{HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::client_ip::client_ip;
+use crate::{AppError, AppState};
+
+/// Tamanho da janela do rate limit, em segundos
+const WINDOW_SECS: u64 = 60;
+
+/// Janela do rate limit em que a requisição atual foi contada, disponível para os
+/// handlers como extensão. Rotas que criam mais de um link cobram o restante com
+/// [`charge`]; os headers da resposta usam o total contado.
+#[derive(Clone)]
+pub struct RateLimitWindow {
+    key: String,
+    ip: IpAddr,
+    limit: u64,
+    now: u64,
+    reset_at: u64,
+    count: Arc<AtomicU64>,
+}
+
+/// Limita as criações de URL por IP usando uma janela por minuto no Redis
+/// (`INCR` + `EXPIRE` em `{prefixo}:{ip}:{minuto}`). Cada requisição conta como
+/// uma criação; `POST /shorten/batch` cobra as demais URLs do lote com [`charge`].
+///
+/// Toda resposta, inclusive o 429, leva os headers `X-RateLimit-*` da janela atual.
+/// Se o Redis falhar, a requisição é liberada (sem os headers): o rate limit não
+/// deve derrubar as criações.
+pub async fn limit_shorten(
+    Extension(state): Extension<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let limit = state.config.shorten_rate_limit;
+    if limit == 0 {
+        return next.run(request).await;
+    }
+
+    let ip = client_ip(request.headers(), peer, state.config.trust_forwarded_for);
+    let now = chrono::Utc::now().timestamp().max(0) as u64;
+    let window = now / WINDOW_SECS;
+    let key = format!("{}:{}:{}", state.config.rate_limit_prefix, ip, window);
+
+    let mut redis_conn = state.redis.get();
+    let result: redis::RedisResult<(u64,)> = redis::pipe()
+        .atomic()
+        .incr(&key, 1)
+        .expire(&key, WINDOW_SECS as i64)
+        .ignore()
+        .query_async(&mut redis_conn)
+        .await;
+
+    let count = match result {
+        Ok((count,)) => count,
+        Err(e) => {
+            warn!("Rate limiter Redis error, allowing request: {}", e);
+            return next.run(request).await;
+        }
+    };
+    let reset_at = (window + 1) * WINDOW_SECS;
+    if count > limit {
+        warn!("Rate limit exceeded for {} ({} requests)", ip, count);
+        let mut response = AppError::TooManyRequests {
+            retry_after: reset_at - now,
+        }
+        .into_response();
+        insert_headers(response.headers_mut(), limit, count, reset_at);
+        return response;
+    }
+
+    let counted = Arc::new(AtomicU64::new(count));
+    request.extensions_mut().insert(RateLimitWindow {
+        key,
+        ip,
+        limit,
+        now,
+        reset_at,
+        count: counted.clone(),
+    });
+    let mut response = next.run(request).await;
+    insert_headers(response.headers_mut(), limit, counted.load(Ordering::Relaxed), reset_at);
+    response
+}
+
+/// Cobra mais `extra` criações na janela da requisição (`INCRBY`), falhando com 429
+/// se passarem do limite. Sem janela (rate limit desativado) ou com o Redis fora
+/// do ar, não faz nada, como o middleware.
+pub async fn charge(state: &AppState, window: Option<&RateLimitWindow>, extra: u64) -> Result<(), AppError> {
+    let Some(window) = window.filter(|_| extra > 0) else {
+        return Ok(());
+    };
+    let mut redis_conn = state.redis.get();
+    let result: redis::RedisResult<u64> = redis::cmd("INCRBY")
+        .arg(&window.key)
+        .arg(extra)
+        .query_async(&mut redis_conn)
+        .await;
+    let count = match result {
+        Ok(count) => count,
+        Err(e) => {
+            warn!("Rate limiter Redis error, allowing request: {}", e);
+            return Ok(());
+        }
+    };
+    window.count.store(count, Ordering::Relaxed);
+    if count > window.limit {
+        warn!("Rate limit exceeded for {} ({} requests)", window.ip, count);
+        return Err(AppError::TooManyRequests {
+            retry_after: window.reset_at - window.now,
+        });
+    }
+    Ok(())
+}
+
+/// `X-RateLimit-Limit`, `X-RateLimit-Remaining` (criações que ainda cabem na
+/// janela) e `X-RateLimit-Reset` (quando a janela recomeça, em segundos Unix)
+fn insert_headers(headers: &mut HeaderMap, limit: u64, count: u64, reset_at: u64) {
+    for (name, value) in [
+        ("x-ratelimit-limit", limit),
+        ("x-ratelimit-remaining", limit.saturating_sub(count)),
+        ("x-ratelimit-reset", reset_at),
+    ] {
+        headers.insert(HeaderName::from_static(name), HeaderValue::from(value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remaining_never_goes_negative() {
+        let mut headers = HeaderMap::new();
+        insert_headers(&mut headers, 60, 61, 1_700_000_040);
+        assert_eq!(headers["x-ratelimit-limit"], "60");
+        assert_eq!(headers["x-ratelimit-remaining"], "0");
+        assert_eq!(headers["x-ratelimit-reset"], "1700000040");
+
+        insert_headers(&mut headers, 60, 1, 1_700_000_040);
+        assert_eq!(headers["x-ratelimit-remaining"], "59");
+    }
+}
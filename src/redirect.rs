@@ -0,0 +1,437 @@
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{Html, IntoResponse, Response},
+};
+use url::{form_urlencoded, Url};
+
+/// Política de `Cache-Control` de um redirecionamento
+#[derive(Debug, PartialEq)]
+pub enum RedirectCache {
+    /// Links que não podem ser reaproveitados: senha, limite de cliques
+    NoStore,
+    /// Redirecionamentos temporários: o navegador volta ao serviço a cada acesso
+    Revalidate,
+    /// Redirecionamentos permanentes, por até N segundos
+    MaxAge(u64),
+}
+
+impl RedirectCache {
+    /// Política para um link: `max_age` vale só para redirecionamentos permanentes
+    /// e é limitado ao TTL restante, para que o cache não sobreviva ao link
+    pub fn for_link(permanent: bool, one_time: bool, max_age: u64, link_ttl: Option<u64>) -> Self {
+        if one_time {
+            RedirectCache::NoStore
+        } else if permanent && max_age > 0 {
+            RedirectCache::MaxAge(link_ttl.map_or(max_age, |ttl| ttl.min(max_age)))
+        } else {
+            RedirectCache::Revalidate
+        }
+    }
+
+    fn header_value(&self) -> HeaderValue {
+        match self {
+            RedirectCache::NoStore => HeaderValue::from_static("no-store"),
+            RedirectCache::Revalidate => HeaderValue::from_static("no-cache"),
+            RedirectCache::MaxAge(secs) => HeaderValue::from_str(&format!("public, max-age={}", secs))
+                .unwrap_or_else(|_| HeaderValue::from_static("no-cache")),
+        }
+    }
+}
+
+/// Responde com 301 (permanente) ou 307 (temporário) para a URL de destino.
+///
+/// Redirecionamentos 301 são guardados agressivamente pelos navegadores, então
+/// acessos repetidos podem nem chegar ao serviço (e não entram na contagem de cliques).
+pub fn redirect_response(location: &str, permanent: bool, cache: RedirectCache) -> Response {
+    let status = if permanent {
+        StatusCode::MOVED_PERMANENTLY
+    } else {
+        StatusCode::TEMPORARY_REDIRECT
+    };
+    redirect_with_status(status, location, cache)
+}
+
+/// Redirecionamento com status arbitrário (ex: 303 depois do formulário de senha)
+pub fn redirect_with_status(status: StatusCode, location: &str, cache: RedirectCache) -> Response {
+    (
+        status,
+        [
+            (header::LOCATION, location_header(location)),
+            (header::CACHE_CONTROL, cache.header_value()),
+        ],
+    )
+        .into_response()
+}
+
+/// Valor do header `Location`, sempre em ASCII válido.
+///
+/// URLs absolutas são reserializadas pelo crate `url` (host em punycode, path e
+/// query com percent-encoding); qualquer outro byte fora do ASCII visível é
+/// codificado como `%XX`, de modo que destinos com Unicode ou caracteres de
+/// controle nunca geram um header inválido
+fn location_header(location: &str) -> HeaderValue {
+    let serialized = Url::parse(location).map_or_else(|_| location.to_string(), String::from);
+    let mut encoded = String::with_capacity(serialized.len());
+    for byte in serialized.bytes() {
+        if byte.is_ascii_graphic() {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    HeaderValue::from_str(&encoded).unwrap_or_else(|_| HeaderValue::from_static("/"))
+}
+
+/// Só destinos `http`/`https` recebem a query repassada e os parâmetros padrão:
+/// em `mailto:` ou `tel:` eles mudariam o endereço em si
+fn is_web_url(url: &Url) -> bool {
+    matches!(url.scheme(), "http" | "https")
+}
+
+/// Anexa a query string recebida no short link à URL de destino.
+///
+/// Parâmetros já presentes no destino são mantidos e os recebidos são
+/// adicionados ao final (`?` ou `&`, conforme o caso), antes de um eventual
+/// fragmento (`#...`) do destino.
+pub fn append_query(long_url: &str, incoming: Option<&str>) -> String {
+    let incoming = match incoming.map(|q| q.trim_start_matches('?')) {
+        Some(q) if !q.is_empty() => q,
+        _ => return long_url.to_string(),
+    };
+
+    match Url::parse(long_url) {
+        Ok(mut url) if is_web_url(&url) => {
+            let query = match url.query() {
+                Some(existing) if !existing.is_empty() => format!("{}&{}", existing, incoming),
+                _ => incoming.to_string(),
+            };
+            url.set_query(Some(&query));
+            url.into()
+        }
+        // URLs são normalizadas na criação; se algo não parsear (ou não for uma URL
+        // web, como `mailto:`), não arrisca alterar o destino
+        _ => long_url.to_string(),
+    }
+}
+
+/// Adiciona ao destino os parâmetros padrão (`DEFAULT_UTM`) que ainda não aparecem
+/// nele nem na query recebida no short link.
+///
+/// Parâmetros definidos pelo destino ou por quem acessou o link têm prioridade;
+/// os padrões só preenchem o que falta. A codificação fica a cargo do `url`.
+pub fn append_default_params(long_url: &str, defaults: &[(String, String)], incoming: Option<&str>) -> String {
+    if defaults.is_empty() {
+        return long_url.to_string();
+    }
+    let Ok(mut url) = Url::parse(long_url) else {
+        return long_url.to_string();
+    };
+    if !is_web_url(&url) {
+        return long_url.to_string();
+    }
+
+    let incoming = incoming.unwrap_or("").trim_start_matches('?');
+    let present: Vec<String> = url
+        .query_pairs()
+        .chain(form_urlencoded::parse(incoming.as_bytes()))
+        .map(|(key, _)| key.into_owned())
+        .collect();
+    let missing: Vec<&(String, String)> = defaults
+        .iter()
+        .filter(|(key, _)| !present.contains(key))
+        .collect();
+    if missing.is_empty() {
+        return long_url.to_string();
+    }
+
+    url.query_pairs_mut()
+        .extend_pairs(missing.iter().map(|(key, value)| (key, value)));
+    url.into()
+}
+
+/// Normaliza o código capturado do path, já decodificado do percent-encoding pelo
+/// extractor `Path` (sequências inválidas em UTF-8 são rejeitadas com 400 antes de
+/// chegar aqui): remove espaços nas pontas e uma única `/` final, comuns em links
+/// colados, para que `/abc`, `/abc/` e `/abc%2F` resolvam o mesmo código
+pub fn normalize_code(raw: &str) -> &str {
+    let code = raw.trim();
+    code.strip_suffix('/').unwrap_or(code)
+}
+
+/// Separa o flag `preview` da query string recebida.
+///
+/// Retorna se o flag estava presente (`?preview`, `?preview=1`, ...) e a query
+/// restante, que continua sendo repassada ao destino.
+pub fn take_preview_flag(query: Option<&str>) -> (bool, Option<String>) {
+    let query = match query {
+        Some(q) if !q.is_empty() => q,
+        _ => return (false, None),
+    };
+
+    let mut preview = false;
+    let rest: Vec<&str> = query
+        .split('&')
+        .filter(|pair| {
+            let is_flag = pair.split('=').next() == Some("preview");
+            preview |= is_flag;
+            !is_flag
+        })
+        .collect();
+
+    let rest = rest.join("&");
+    (preview, (!rest.is_empty()).then_some(rest))
+}
+
+/// Separa a senha (`pw`) da query string recebida, para que ela não seja
+/// repassada ao destino. Retorna a senha já decodificada e a query restante.
+pub fn take_password(query: Option<&str>) -> (Option<String>, Option<String>) {
+    let query = match query {
+        Some(q) if !q.is_empty() => q,
+        _ => return (None, None),
+    };
+
+    let mut password = None;
+    let rest: Vec<&str> = query
+        .split('&')
+        .filter(|pair| {
+            let is_password = pair.split('=').next() == Some("pw");
+            if is_password {
+                password = form_urlencoded::parse(pair.as_bytes())
+                    .next()
+                    .map(|(_, value)| value.into_owned());
+            }
+            !is_password
+        })
+        .collect();
+
+    let rest = rest.join("&");
+    (password, (!rest.is_empty()).then_some(rest))
+}
+
+/// Página de senha de um link protegido, respondida com 401.
+///
+/// O formulário envia `pw` via POST para o próprio endereço, mantendo a query.
+pub fn password_prompt(short_url: &str, wrong_password: bool) -> Response {
+    let short_url = html_escape(short_url);
+    let error = if wrong_password {
+        "<p role=\"alert\">Incorrect password.</p>\n"
+    } else {
+        ""
+    };
+    let page = format!(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"robots\" content=\"noindex\">
+<title>/{short_url} is password protected</title>
+</head>
+<body>
+<h1>This link is password protected</h1>
+{error}<form method=\"post\">
+<input type=\"password\" name=\"pw\" autofocus required>
+<button type=\"submit\">Continue</button>
+</form>
+</body>
+</html>
+"
+    );
+
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::CACHE_CONTROL, "no-store")],
+        Html(page),
+    )
+        .into_response()
+}
+
+/// Página intermediária que mostra o destino do link em vez de redirecionar
+pub fn preview_page(short_url: &str, destination: &str) -> Response {
+    let destination = html_escape(destination);
+    let short_url = html_escape(short_url);
+    let page = format!(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"robots\" content=\"noindex\">
+<title>Preview of /{short_url}</title>
+</head>
+<body>
+<h1>This short link points to:</h1>
+<p><code>{destination}</code></p>
+<p><a href=\"{destination}\" rel=\"noopener noreferrer\">Continue</a></p>
+</body>
+</html>
+"
+    );
+
+    ([(header::CACHE_CONTROL, "no-store")], Html(page)).into_response()
+}
+
+/// Página servida aos bots de pré-visualização em links com `max_clicks`: nem o
+/// destino nem um uso do link são entregues a eles
+pub fn limited_link_page(short_url: &str) -> Response {
+    let short_url = html_escape(short_url);
+    let page = format!(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"robots\" content=\"noindex\">
+<title>/{short_url} has limited uses</title>
+</head>
+<body>
+<h1>This link can only be opened a limited number of times</h1>
+<p>Open it in a browser to continue.</p>
+</body>
+</html>
+"
+    );
+
+    ([(header::CACHE_CONTROL, "no-store")], Html(page)).into_response()
+}
+
+/// Página servida em `GET /` quando `ROOT_REDIRECT` não está definido
+pub fn landing_page() -> Response {
+    let page = "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<title>URL shortener</title>
+</head>
+<body>
+<h1>URL shortener</h1>
+<p>Short links served here redirect to their destination. See the <a href=\"/api-docs/openapi.json\">API description</a> to create one.</p>
+</body>
+</html>
+";
+
+    ([(header::CACHE_CONTROL, "public, max-age=300")], Html(page)).into_response()
+}
+
+/// Escapa os caracteres especiais de HTML, para texto e valores de atributo
+fn html_escape(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    for c in raw.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_location_header() {
+        assert_eq!(location_header("https://example.com/a?b=c"), "https://example.com/a?b=c");
+        assert_eq!(
+            location_header("https://exämple.com/café?q=ü"),
+            "https://xn--exmple-cua.com/caf%C3%A9?q=%C3%BC"
+        );
+        // Sem URL absoluta, só os bytes fora do ASCII visível são codificados
+        assert_eq!(location_header("/a b\u{e9}"), "/a%20b%C3%A9");
+    }
+
+    #[test]
+    fn picks_cache_policy() {
+        assert_eq!(RedirectCache::for_link(true, true, 3600, None), RedirectCache::NoStore);
+        assert_eq!(RedirectCache::for_link(false, false, 3600, None), RedirectCache::Revalidate);
+        assert_eq!(RedirectCache::for_link(true, false, 3600, None), RedirectCache::MaxAge(3600));
+        assert_eq!(RedirectCache::for_link(true, false, 3600, Some(60)), RedirectCache::MaxAge(60));
+        assert_eq!(RedirectCache::for_link(true, false, 0, None), RedirectCache::Revalidate);
+    }
+
+    #[test]
+    fn normalizes_pasted_codes() {
+        assert_eq!(normalize_code("abc"), "abc");
+        assert_eq!(normalize_code("abc/"), "abc");
+        assert_eq!(normalize_code(" abc/ "), "abc");
+        // Só uma barra: `abc//` continua sendo um código inválido
+        assert_eq!(normalize_code("abc//"), "abc/");
+    }
+
+    #[test]
+    fn appends_incoming_query() {
+        assert_eq!(
+            append_query("https://example.com/page", Some("utm_source=twitter")),
+            "https://example.com/page?utm_source=twitter"
+        );
+        assert_eq!(
+            append_query("https://example.com/page?id=1#top", Some("utm_source=twitter")),
+            "https://example.com/page?id=1&utm_source=twitter#top"
+        );
+    }
+
+    #[test]
+    fn keeps_destination_without_incoming_query() {
+        assert_eq!(append_query("https://example.com/?a=1", None), "https://example.com/?a=1");
+        assert_eq!(append_query("https://example.com/?a=1", Some("")), "https://example.com/?a=1");
+    }
+
+    #[test]
+    fn leaves_non_web_destinations_untouched() {
+        assert_eq!(append_query("mailto:team@example.com", Some("utm_source=x")), "mailto:team@example.com");
+        let defaults = vec![("utm_source".to_string(), "shortlink".to_string())];
+        assert_eq!(append_default_params("tel:+5511999999999", &defaults, None), "tel:+5511999999999");
+    }
+
+    #[test]
+    fn appends_missing_default_params() {
+        let defaults = vec![
+            ("utm_source".to_string(), "shortlink".to_string()),
+            ("utm_medium".to_string(), "a b".to_string()),
+        ];
+        assert_eq!(
+            append_default_params("https://example.com/page?utm_source=newsletter#top", &defaults, None),
+            "https://example.com/page?utm_source=newsletter&utm_medium=a+b#top"
+        );
+
+        // Combinado com a query recebida, quem acessou o link prevalece sobre o padrão
+        let incoming = Some("utm_medium=qr");
+        let destination = append_query(
+            &append_default_params("https://example.com/", &defaults, incoming),
+            incoming,
+        );
+        assert_eq!(destination, "https://example.com/?utm_source=shortlink&utm_medium=qr");
+
+        assert_eq!(append_default_params("https://example.com/", &[], None), "https://example.com/");
+    }
+
+    #[test]
+    fn extracts_preview_flag() {
+        assert_eq!(take_preview_flag(Some("preview")), (true, None));
+        assert_eq!(
+            take_preview_flag(Some("a=1&preview=1&b=2")),
+            (true, Some("a=1&b=2".to_string()))
+        );
+        assert_eq!(take_preview_flag(Some("previewer=1")), (false, Some("previewer=1".to_string())));
+        assert_eq!(take_preview_flag(None), (false, None));
+    }
+
+    #[test]
+    fn extracts_password() {
+        assert_eq!(
+            take_password(Some("utm=1&pw=a%20b%26c")),
+            (Some("a b&c".to_string()), Some("utm=1".to_string()))
+        );
+        assert_eq!(take_password(Some("pwd=1")), (None, Some("pwd=1".to_string())));
+        assert_eq!(take_password(None), (None, None));
+    }
+
+    #[test]
+    fn escapes_html() {
+        assert_eq!(
+            html_escape("https://x.com/?a=1&b=\"><script>'"),
+            "https://x.com/?a=1&amp;b=&quot;&gt;&lt;script&gt;&#39;"
+        );
+    }
+}
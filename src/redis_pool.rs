@@ -0,0 +1,82 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use redis::aio::{ConnectionLike, MultiplexedConnection};
+use redis::{Cmd, Pipeline, RedisError, RedisFuture, RedisResult, Value};
+
+use crate::timing;
+
+/// Conjunto fixo de conexões multiplexadas ao Redis, distribuídas em round-robin.
+///
+/// Uma conexão multiplexada já atende muitas requisições em paralelo, mas todos os
+/// comandos passam por um único socket; sob carga alta, algumas conexões a mais
+/// (`REDIS_POOL_SIZE`) evitam que ele vire o gargalo.
+pub struct RedisPool {
+    connections: Vec<RedisConnection>,
+    next: AtomicUsize,
+}
+
+impl RedisPool {
+    /// Abre `size` conexões; `timeout` (`REDIS_TIMEOUT_MS`) limita tanto a
+    /// conexão quanto cada comando enviado depois por elas
+    pub async fn connect(client: &redis::Client, size: usize, timeout: Duration) -> RedisResult<Self> {
+        let mut connections = Vec::with_capacity(size);
+        for _ in 0..size.max(1) {
+            let connection = with_timeout(timeout, client.get_multiplexed_async_connection()).await?;
+            connections.push(RedisConnection { connection, timeout });
+        }
+        Ok(RedisPool {
+            connections,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// Próxima conexão da fila; o clone é barato (compartilha o mesmo socket)
+    pub fn get(&self) -> RedisConnection {
+        let idx = self.next.fetch_add(1, Ordering::Relaxed) % self.connections.len();
+        self.connections[idx].clone()
+    }
+}
+
+/// Conexão multiplexada que soma o tempo de cada comando ao da requisição
+/// atual (ver `timing`), para o log de requisições lentas. Comandos que passam
+/// do timeout falham com um erro de I/O `TimedOut` (`RedisError::is_timeout`)
+#[derive(Clone)]
+pub struct RedisConnection {
+    connection: MultiplexedConnection,
+    timeout: Duration,
+}
+
+/// Falha com `TimedOut` se o Redis não responder dentro de `timeout`
+async fn with_timeout<T>(timeout: Duration, future: impl Future<Output = RedisResult<T>>) -> RedisResult<T> {
+    match tokio::time::timeout(timeout, future).await {
+        Ok(result) => result,
+        Err(_) => Err(RedisError::from(std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            format!("no response from Redis within {} ms", timeout.as_millis()),
+        ))),
+    }
+}
+
+impl ConnectionLike for RedisConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        let timeout = self.timeout;
+        Box::pin(timing::redis(with_timeout(timeout, self.connection.req_packed_command(cmd))))
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        let timeout = self.timeout;
+        let commands = self.connection.req_packed_commands(cmd, offset, count);
+        Box::pin(timing::redis(with_timeout(timeout, commands)))
+    }
+
+    fn get_db(&self) -> i64 {
+        self.connection.get_db()
+    }
+}
@@ -0,0 +1,37 @@
+use std::{fmt::Display, future::Future, time::Duration};
+use tracing::{error, info, warn};
+
+/// Espera inicial entre tentativas; dobra a cada falha
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+/// Espera máxima entre tentativas
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Executa `op` até `max_attempts` vezes com backoff exponencial,
+/// retornando o último erro se todas as tentativas falharem
+pub async fn retry_with_backoff<T, E, F, Fut>(what: &str, max_attempts: u32, mut op: F) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: Display,
+{
+    let max_attempts = max_attempts.max(1);
+    let mut backoff = INITIAL_BACKOFF;
+    let mut attempt = 1;
+
+    loop {
+        info!("Connecting to {} (attempt {}/{})", what, attempt, max_attempts);
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt >= max_attempts => {
+                error!("Giving up on {} after {} attempts: {}", what, attempt, e);
+                return Err(e);
+            }
+            Err(e) => {
+                warn!("{} not available: {} (retrying in {:?})", what, e, backoff);
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+                attempt += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use tracing::info;
+
+/// Cria o keyspace e as tabelas, se não existirem.
+///
+/// `replication` é o mapa de replicação em CQL; só vale na criação do keyspace (um
+/// keyspace existente mantém a configuração atual).
+pub async fn migrate(cassandra: &Session, keyspace: &str, replication: &str) -> Result<(), QueryError> {
+    //  Cria o keyspace se não existir
+    cassandra
+        .query(
+            format!("CREATE KEYSPACE IF NOT EXISTS {} WITH replication = {};", keyspace, replication),
+            &[],
+        )
+        .await?;
+
+    // Usa o keyspace
+    cassandra.use_keyspace(keyspace, false).await?;
+
+    // Cria a tabela se não existir
+    cassandra
+        .query(
+            "CREATE TABLE IF NOT EXISTS urls (
+                short_url text PRIMARY KEY,
+                long_url text,
+                created_at timestamp
+            );",
+            &[],
+        )
+        .await?;
+
+    // Colunas adicionadas depois da criação original da tabela
+    ensure_column(cassandra, keyspace, "urls", "permanent", "boolean").await?;
+    ensure_column(cassandra, keyspace, "urls", "max_clicks", "bigint").await?;
+    ensure_column(cassandra, keyspace, "urls", "password_hash", "text").await?;
+    ensure_column(cassandra, keyspace, "urls", "last_accessed", "timestamp").await?;
+    ensure_column(cassandra, keyspace, "urls", "last_cache_hit", "boolean").await?;
+    ensure_column(cassandra, keyspace, "urls", "owner", "text").await?;
+    ensure_column(cassandra, keyspace, "urls", "tags", "set<text>").await?;
+
+    // Contadores não podem ficar na mesma tabela que colunas comuns
+    cassandra
+        .query(
+            "CREATE TABLE IF NOT EXISTS url_clicks (
+                short_url text PRIMARY KEY,
+                hits counter
+            );",
+            &[],
+        )
+        .await?;
+
+    // Índice reverso para deduplicar URLs: hash blake3 da long_url normalizada -> short_url
+    cassandra
+        .query(
+            "CREATE TABLE IF NOT EXISTS url_by_long (
+                long_hash text PRIMARY KEY,
+                short_url text
+            );",
+            &[],
+        )
+        .await?;
+
+    // Destinos por país dos links com redirecionamento geográfico
+    cassandra
+        .query(
+            "CREATE TABLE IF NOT EXISTS url_geo (
+                short_url text,
+                country text,
+                long_url text,
+                PRIMARY KEY (short_url, country)
+            );",
+            &[],
+        )
+        .await?;
+
+    // Variantes dos testes A/B e seus cliques (contadores ficam em tabela própria)
+    cassandra
+        .query(
+            "CREATE TABLE IF NOT EXISTS url_variants (
+                short_url text,
+                variant text,
+                url text,
+                weight int,
+                PRIMARY KEY (short_url, variant)
+            );",
+            &[],
+        )
+        .await?;
+    cassandra
+        .query(
+            "CREATE TABLE IF NOT EXISTS url_variant_clicks (
+                short_url text,
+                variant text,
+                hits counter,
+                PRIMARY KEY (short_url, variant)
+            );",
+            &[],
+        )
+        .await?;
+
+    // Um evento por clique, particionado por código e dia (`GET /api/urls/:short_url/clicks`)
+    cassandra
+        .query(
+            "CREATE TABLE IF NOT EXISTS click_events (
+                short_url text,
+                day date,
+                id timeuuid,
+                ts timestamp,
+                weight double,
+                variant text,
+                country text,
+                PRIMARY KEY ((short_url, day), id)
+            );",
+            &[],
+        )
+        .await?;
+
+    // Códigos removidos de propósito, para responder 410 em vez de 404
+    cassandra
+        .query(
+            "CREATE TABLE IF NOT EXISTS url_tombstones (
+                short_url text PRIMARY KEY,
+                deleted_at timestamp
+            );",
+            &[],
+        )
+        .await?;
+
+    // Links por tag, para `GET /api/urls?tag=`: filtrar pelo set de `urls` varreria
+    // a tabela inteira. Cada linha expira junto com o link
+    cassandra
+        .query(
+            "CREATE TABLE IF NOT EXISTS urls_by_tag (
+                tag text,
+                short_url text,
+                PRIMARY KEY (tag, short_url)
+            );",
+            &[],
+        )
+        .await?;
+
+    // Trilha de auditoria das ações administrativas, por código (mais recentes primeiro)
+    cassandra
+        .query(
+            "CREATE TABLE IF NOT EXISTS audit_log (
+                short_url text,
+                id timeuuid,
+                action text,
+                owner text,
+                at timestamp,
+                detail text,
+                PRIMARY KEY (short_url, id)
+            ) WITH CLUSTERING ORDER BY (id DESC);",
+            &[],
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Adiciona uma coluna a uma tabela existente, caso ela ainda não exista
+async fn ensure_column(
+    cassandra: &Session,
+    keyspace: &str,
+    table: &str,
+    column: &str,
+    cql_type: &str,
+) -> Result<(), QueryError> {
+    let exists = cassandra
+        .query(
+            "SELECT column_name FROM system_schema.columns WHERE keyspace_name = ? AND table_name = ? AND column_name = ?",
+            (keyspace, table, column),
+        )
+        .await?
+        .rows_num()
+        .is_ok_and(|rows| rows > 0);
+
+    if !exists {
+        info!("Adding column {}.{} ({})", table, column, cql_type);
+        cassandra
+            .query(format!("ALTER TABLE {} ADD {} {}", table, column, cql_type), &[])
+            .await?;
+    }
+
+    Ok(())
+}
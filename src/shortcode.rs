@@ -0,0 +1,410 @@
+use rand::{SeedableRng, seq::SliceRandom};
+use rand_chacha::ChaCha8Rng;
+
+/// Alfabeto Base62 padrão, antes do embaralhamento
+pub const BASE62_ALPHABET: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+/// Deslocamento padrão somado aos IDs antes da codificação (`ID_OFFSET`).
+///
+/// 14 milhões fica entre 62³ e 62⁴, então o primeiro ID já vira um código de 4
+/// caracteres em Base62. O código gravado é a fonte de verdade (nada subtrai o
+/// deslocamento depois), mas mudar o valor altera os códigos dos próximos IDs:
+/// reduzi-lo volta a gerar códigos já emitidos.
+pub const DEFAULT_ID_OFFSET: u64 = 14_000_000;
+
+/// Faixa dos IDs (já deslocados) que viram códigos de exatamente `width` dígitos
+/// em uma base: de `base^(width-1)`, usado como deslocamento (`CODE_WIDTH`), até
+/// `base^width`, exclusivo. Cabem `(base - 1) · base^(width-1)` links nessa faixa.
+/// `None` se `base^width` não couber em um `u64`
+pub fn fixed_width_range(base: u64, width: u32) -> Option<(u64, u64)> {
+    let offset = base.checked_pow(width.checked_sub(1)?)?;
+    Some((offset, base.checked_pow(width)?))
+}
+
+/// Valida um alfabeto de `CODE_ALPHABET`: ao menos 2 caracteres, sem repetições,
+/// e só caracteres seguros em um caminho de URL (`a-z`, `A-Z`, `0-9`, `-`, `_`)
+pub fn validate_alphabet(alphabet: &str) -> Result<(), String> {
+    let chars: Vec<char> = alphabet.chars().collect();
+    if chars.len() < 2 {
+        return Err("alphabet must have at least 2 characters".to_string());
+    }
+    if let Some(c) = chars
+        .iter()
+        .find(|c| !(c.is_ascii_alphanumeric() || **c == '-' || **c == '_'))
+    {
+        return Err(format!("character '{}' is not allowed in the alphabet", c));
+    }
+    for (i, c) in chars.iter().enumerate() {
+        if chars[..i].contains(c) {
+            return Err(format!("character '{}' appears more than once", c));
+        }
+    }
+    Ok(())
+}
+
+/// Alfabeto para `CASE_INSENSITIVE`: letras em minúsculas e sem as repetições
+/// que isso cria, para que nenhum código dependa de maiúsculas
+pub fn case_folded_alphabet(alphabet: &str) -> String {
+    let mut folded = String::with_capacity(alphabet.len());
+    for c in alphabet.chars().map(|c| c.to_ascii_lowercase()) {
+        if !folded.contains(c) {
+            folded.push(c);
+        }
+    }
+    folded
+}
+
+/// Contexto do `blake3::derive_key` da semente do alfabeto (`ALPHABET_VERSION=1`)
+const ALPHABET_CONTEXT: &str = "url-shortener alphabet v1";
+
+/// Como a semente do embaralhamento é derivada da `SECRET_KEY` (`ALPHABET_VERSION`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlphabetVersion {
+    /// `0`: hash direto da chave, como nas primeiras versões
+    Legacy,
+    /// `1`: chave derivada com contexto próprio, separada de outros usos da `SECRET_KEY`
+    V1,
+}
+
+/// Semente do embaralhamento do alfabeto. Trocar a versão muda todo o
+/// alfabeto embaralhado e, com ele, os códigos gerados a partir dos IDs
+pub fn alphabet_seed(secret_key: &str, version: AlphabetVersion) -> [u8; 32] {
+    match version {
+        AlphabetVersion::Legacy => blake3::hash(secret_key.as_bytes()).into(),
+        AlphabetVersion::V1 => blake3::derive_key(ALPHABET_CONTEXT, secret_key.as_bytes()),
+    }
+}
+
+/// Contexto do `blake3::derive_key` da chave das assinaturas (`CODE_MODE=signed`)
+const SIGNATURE_CONTEXT: &str = "url-shortener code signature v1";
+/// Caracteres de assinatura acrescentados a cada código no modo assinado
+pub const SIGNATURE_LEN: usize = 4;
+
+/// Formato dos códigos gerados (`CODE_MODE`)
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CodeMode {
+    /// Só o ID codificado (padrão)
+    Plain,
+    /// ID codificado seguido de `SIGNATURE_LEN` caracteres de um MAC sobre o ID:
+    /// códigos adivinhados ou forjados são recusados sem consultar o banco
+    Signed { key: [u8; 32] },
+}
+
+impl CodeMode {
+    /// Modo assinado, com a chave do MAC derivada da `SECRET_KEY`
+    pub fn signed(secret_key: &str) -> Self {
+        CodeMode::Signed {
+            key: blake3::derive_key(SIGNATURE_CONTEXT, secret_key.as_bytes()),
+        }
+    }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(self, CodeMode::Signed { .. })
+    }
+}
+
+/// Embaralha o alfabeto de forma determinística a partir da semente (`alphabet_seed`)
+fn shuffled_alphabet(seed: &[u8; 32], alphabet: &str) -> Vec<char> {
+    let mut alphabet: Vec<char> = alphabet.chars().collect();
+
+    let mut rng = ChaCha8Rng::from_seed(*seed);
+
+    // Embaralha o alfabeto sempre da mesma forma
+    alphabet.shuffle(&mut rng);
+    alphabet
+}
+
+/// Gera o short URL na base do `alphabet` (62 por padrão), com ofuscação via `alphabet_seed`.
+///
+/// Definida para todo `u64`: o ID 0 vira o primeiro caractere do alfabeto
+/// embaralhado (o dígito zero), como qualquer outro dígito.
+///
+/// Códigos menores que `min_len` são completados à esquerda com o primeiro
+/// caractere do alfabeto embaralhado, que vale zero: `decode_short_url` os lê
+/// normalmente.
+pub fn generate_short_url(seed: &[u8; 32], alphabet: &str, mut id: u64, min_len: usize) -> String {
+    let alphabet = shuffled_alphabet(seed, alphabet);
+    let base = alphabet.len() as u64;
+
+    // Converte o ID para a base do alfabeto (já embaralhado). O laço roda ao
+    // menos uma vez, então o 0 gera o seu dígito em vez de um código vazio.
+    let mut encoded = Vec::new();
+    loop {
+        let remainder = (id % base) as usize;
+        encoded.push(alphabet[remainder]);
+        id /= base;
+        if id == 0 {
+            break;
+        }
+    }
+
+    while encoded.len() < min_len {
+        encoded.push(alphabet[0]);
+    }
+
+    encoded.iter().rev().collect::<String>()
+}
+
+/// Inverso de `generate_short_url`: retorna `None` se o código tiver caracteres
+/// fora do alfabeto ou não couber em um `u64`. `seed` e `alphabet` precisam ser os mesmos da geração
+pub fn decode_short_url(seed: &[u8; 32], alphabet: &str, code: &str) -> Option<u64> {
+    if code.is_empty() {
+        return None;
+    }
+
+    let alphabet = shuffled_alphabet(seed, alphabet);
+    let base = alphabet.len() as u64;
+
+    code.chars().try_fold(0u64, |id, c| {
+        let digit = alphabet.iter().position(|&a| a == c)? as u64;
+        id.checked_mul(base)?.checked_add(digit)
+    })
+}
+
+/// MAC (`blake3` com chave) sobre o ID, escrito em `SIGNATURE_LEN` dígitos do alfabeto
+fn signature(key: &[u8; 32], alphabet: &[char], id: u64) -> String {
+    let mac = blake3::keyed_hash(key, &id.to_le_bytes());
+    let mut value = u64::from_le_bytes(mac.as_bytes()[..8].try_into().expect("blake3 output has 32 bytes"));
+    let base = alphabet.len() as u64;
+    (0..SIGNATURE_LEN)
+        .map(|_| {
+            let digit = alphabet[(value % base) as usize];
+            value /= base;
+            digit
+        })
+        .collect()
+}
+
+/// `generate_short_url` conforme o `CODE_MODE`: no modo assinado, o código
+/// recebe a assinatura do ID no final
+pub fn generate_code(mode: &CodeMode, seed: &[u8; 32], alphabet: &str, id: u64, min_len: usize) -> String {
+    let code = generate_short_url(seed, alphabet, id, min_len);
+    match mode {
+        CodeMode::Plain => code,
+        CodeMode::Signed { key } => code + &signature(key, &shuffled_alphabet(seed, alphabet), id),
+    }
+}
+
+/// Inverso de `generate_code`: no modo assinado, `None` também se a assinatura
+/// não confere com o ID
+pub fn decode_code(mode: &CodeMode, seed: &[u8; 32], alphabet: &str, code: &str) -> Option<u64> {
+    let CodeMode::Signed { key } = mode else {
+        return decode_short_url(seed, alphabet, code);
+    };
+    let split = code.len().checked_sub(SIGNATURE_LEN)?;
+    if !code.is_char_boundary(split) {
+        return None;
+    }
+    let (body, given) = code.split_at(split);
+    let id = decode_short_url(seed, alphabet, body)?;
+    let expected = signature(key, &shuffled_alphabet(seed, alphabet), id);
+    // Comparação sem atalho, para não revelar pelo tempo quantos caracteres conferem
+    let diff = given
+        .bytes()
+        .zip(expected.bytes())
+        .fold(0, |diff, (a, b)| diff | (a ^ b));
+    (given.len() == expected.len() && diff == 0).then_some(id)
+}
+
+/// Caminhos do próprio serviço, que nunca podem ser usados como código
+const RESERVED_CODES: &[&str] = &[
+    "shorten",
+    "health",
+    "ready",
+    "metrics",
+    "api",
+    "admin",
+    "api-docs",
+    "swagger",
+    "favicon.ico",
+    "robots.txt",
+];
+
+/// Verifica se o código colide com uma rota do serviço (sem diferenciar maiúsculas)
+pub fn is_reserved(code: &str) -> bool {
+    RESERVED_CODES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(code))
+}
+
+/// Valida um alias personalizado: `^[a-zA-Z0-9_-]{3,32}$`
+pub fn is_valid_alias(alias: &str) -> bool {
+    (3..=32).contains(&alias.len())
+        && alias
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    const KEY: &[u8; 32] = &[7; 32];
+
+    #[test]
+    fn decode_reverses_encode() {
+        let ids = (0..1_000)
+            .chain(14_000_000..14_001_000)
+            .chain([u32::MAX as u64, u64::MAX - 1, u64::MAX]);
+        for id in ids {
+            let code = generate_short_url(KEY, BASE62_ALPHABET, id, 0);
+            assert_eq!(decode_short_url(KEY, BASE62_ALPHABET, &code), Some(id), "id {} -> {}", id, code);
+        }
+    }
+
+    #[test]
+    fn decode_rejects_invalid_codes() {
+        assert_eq!(decode_short_url(KEY, BASE62_ALPHABET, ""), None);
+        assert_eq!(decode_short_url(KEY, BASE62_ALPHABET, "favicon.ico"), None);
+        assert_eq!(decode_short_url(KEY, BASE62_ALPHABET, "abc-def"), None);
+        // Maior que u64::MAX
+        assert_eq!(decode_short_url(KEY, BASE62_ALPHABET, &"z".repeat(12)), None);
+    }
+
+    #[test]
+    fn pads_to_min_len() {
+        for id in [0, 1, 61, 62, 14_000_000] {
+            let code = generate_short_url(KEY, BASE62_ALPHABET, id, 6);
+            assert_eq!(code.len(), 6, "id {} -> {}", id, code);
+            assert_eq!(decode_short_url(KEY, BASE62_ALPHABET, &code), Some(id));
+        }
+        // Códigos já maiores que o mínimo não são alterados
+        assert_eq!(generate_short_url(KEY, BASE62_ALPHABET, u64::MAX, 6), generate_short_url(KEY, BASE62_ALPHABET, u64::MAX, 0));
+    }
+
+    #[test]
+    fn fixed_width_range_yields_exact_width() {
+        let (offset, limit) = fixed_width_range(62, 7).unwrap();
+        assert_eq!((offset, limit), (62u64.pow(6), 62u64.pow(7)));
+        for id in [offset, offset + 1, limit - 1] {
+            let code = generate_short_url(KEY, BASE62_ALPHABET, id, 0);
+            assert_eq!(code.len(), 7, "id {} -> {}", id, code);
+            assert_eq!(decode_short_url(KEY, BASE62_ALPHABET, &code), Some(id));
+        }
+        assert_eq!(generate_short_url(KEY, BASE62_ALPHABET, limit, 0).len(), 8);
+
+        assert!(fixed_width_range(62, 10).is_some());
+        assert_eq!(fixed_width_range(62, 11), None);
+        assert_eq!(fixed_width_range(62, 0), None);
+    }
+
+    #[test]
+    fn reserved_codes() {
+        assert!(is_reserved("health"));
+        assert!(is_reserved("Metrics"));
+        assert!(is_reserved("robots.txt"));
+        assert!(!is_reserved("healthy"));
+    }
+
+    #[test]
+    fn encoding_is_deterministic() {
+        for id in [0, 1, 14_000_000, u64::MAX] {
+            assert_eq!(generate_short_url(KEY, BASE62_ALPHABET, id, 0), generate_short_url(KEY, BASE62_ALPHABET, id, 0));
+        }
+    }
+
+    #[test]
+    fn secret_key_changes_codes() {
+        let key_a = alphabet_seed("key_a", AlphabetVersion::V1);
+        let key_b = alphabet_seed("key_b", AlphabetVersion::V1);
+        let a = generate_short_url(&key_a, BASE62_ALPHABET, 14_000_000, 0);
+        let b = generate_short_url(&key_b, BASE62_ALPHABET, 14_000_000, 0);
+        assert_ne!(a, b);
+        assert_ne!(shuffled_alphabet(&key_a, BASE62_ALPHABET), shuffled_alphabet(&key_b, BASE62_ALPHABET));
+    }
+
+    #[test]
+    fn signed_codes_round_trip() {
+        let mode = CodeMode::signed("test_secret");
+        for id in [0, 1, 14_000_000, u64::MAX] {
+            let code = generate_code(&mode, KEY, BASE62_ALPHABET, id, 6);
+            assert_eq!(code.len(), generate_short_url(KEY, BASE62_ALPHABET, id, 6).len() + SIGNATURE_LEN);
+            assert_eq!(decode_code(&mode, KEY, BASE62_ALPHABET, &code), Some(id), "id {} -> {}", id, code);
+        }
+        let plain = generate_code(&CodeMode::Plain, KEY, BASE62_ALPHABET, 1, 6);
+        assert_eq!(plain, generate_short_url(KEY, BASE62_ALPHABET, 1, 6));
+    }
+
+    #[test]
+    fn rejects_forged_signatures() {
+        let mode = CodeMode::signed("test_secret");
+        let code = generate_code(&mode, KEY, BASE62_ALPHABET, 14_000_000, 6);
+        // Sem a assinatura, com outra chave ou com um caractere trocado
+        let unsigned = generate_short_url(KEY, BASE62_ALPHABET, 14_000_000, 6);
+        assert_eq!(decode_code(&mode, KEY, BASE62_ALPHABET, &unsigned), None);
+        assert_eq!(decode_code(&CodeMode::signed("other"), KEY, BASE62_ALPHABET, &code), None);
+        let mut forged: Vec<char> = code.chars().collect();
+        let last = forged.len() - 1;
+        forged[last] = if forged[last] == 'a' { 'b' } else { 'a' };
+        assert_eq!(decode_code(&mode, KEY, BASE62_ALPHABET, &forged.iter().collect::<String>()), None);
+        assert_eq!(decode_code(&mode, KEY, BASE62_ALPHABET, "abc"), None);
+    }
+
+    #[test]
+    fn alphabet_version_separates_seeds() {
+        // A versão legada continua sendo o hash direto, para não mudar códigos existentes
+        let legacy = alphabet_seed("key", AlphabetVersion::Legacy);
+        assert_eq!(legacy, *blake3::hash(b"key").as_bytes());
+        assert_ne!(legacy, alphabet_seed("key", AlphabetVersion::V1));
+    }
+
+    #[test]
+    fn zero_is_a_single_character() {
+        // 0 é o primeiro caractere do alfabeto embaralhado (o dígito zero)
+        let code = generate_short_url(KEY, BASE62_ALPHABET, 0, 0);
+        assert_eq!(code.chars().count(), 1);
+        assert_eq!(code.chars().next(), Some(shuffled_alphabet(KEY, BASE62_ALPHABET)[0]));
+    }
+
+    #[test]
+    fn output_uses_only_alphabet_characters() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        for _ in 0..1_000 {
+            let id = rng.gen::<u64>();
+            let code = generate_short_url(KEY, BASE62_ALPHABET, id, 6);
+            assert!(code.chars().all(|c| BASE62_ALPHABET.contains(c)), "{}", code);
+        }
+    }
+
+    #[test]
+    fn random_ids_round_trip() {
+        let mut rng = ChaCha8Rng::seed_from_u64(42);
+        for _ in 0..10_000 {
+            let id = rng.gen::<u64>() >> rng.gen_range(0..64);
+            let min_len = rng.gen_range(0..=11);
+            let code = generate_short_url(KEY, BASE62_ALPHABET, id, min_len);
+            assert_eq!(decode_short_url(KEY, BASE62_ALPHABET, &code), Some(id), "id {} -> {}", id, code);
+        }
+    }
+
+    #[test]
+    fn custom_alphabet_round_trips() {
+        // Sem caracteres confundíveis (0/O, 1/l/I)
+        let alphabet = "23456789abcdefghijkmnpqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ";
+        assert!(validate_alphabet(alphabet).is_ok());
+        for id in [0, 1, 14_000_000, u64::MAX] {
+            let code = generate_short_url(KEY, alphabet, id, 6);
+            assert!(code.chars().all(|c| alphabet.contains(c)), "{}", code);
+            assert_eq!(decode_short_url(KEY, alphabet, &code), Some(id));
+        }
+        assert_eq!(decode_short_url(KEY, alphabet, "0OlI1"), None);
+    }
+
+    #[test]
+    fn folds_alphabet_case() {
+        let folded = case_folded_alphabet(BASE62_ALPHABET);
+        assert_eq!(folded, "abcdefghijklmnopqrstuvwxyz0123456789");
+        assert_eq!(case_folded_alphabet("aAbB12"), "ab12");
+    }
+
+    #[test]
+    fn rejects_invalid_alphabets() {
+        assert!(validate_alphabet(BASE62_ALPHABET).is_ok());
+        assert!(validate_alphabet("01").is_ok());
+        assert!(validate_alphabet("a").is_err());
+        assert!(validate_alphabet("abca").is_err());
+        assert!(validate_alphabet("ab+c").is_err());
+        assert!(validate_alphabet("abç").is_err());
+    }
+}
@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use rand::Rng;
+use redis::Script;
+use tracing::warn;
+
+use crate::cleanup::RELEASE_SCRIPT;
+use crate::redis_pool::RedisConnection;
+use crate::{cache_key, CacheEntry};
+
+/// Validade da trava (ms): se a requisição que repopula o cache cair, as
+/// outras voltam a consultar o Cassandra depois disso
+const LOCK_TTL_MS: u64 = 3000;
+/// Intervalo entre as verificações de quem está esperando
+const WAIT_STEP: Duration = Duration::from_millis(25);
+/// Espera máxima (40 × 25 ms = 1 s) antes de consultar o Cassandra por conta própria
+const MAX_WAIT_STEPS: u32 = 40;
+
+fn lock_key(short_url: &str) -> String {
+    format!("fill:{}", short_url)
+}
+
+/// Trava `fill:{short_url}` de quem repopula o cache de um código
+pub struct FillLock {
+    key: String,
+    /// `None` se o Redis falhou ao criar a trava (nada a liberar)
+    token: Option<String>,
+}
+
+/// Disputa a repopulação do cache de um código (`SET NX PX`).
+///
+/// `Some` para a requisição que deve consultar o Cassandra — inclusive se o
+/// Redis falhar, para não travar o redirecionamento — e `None` se outra já
+/// está consultando
+pub async fn acquire(redis_conn: &mut RedisConnection, short_url: &str) -> Option<FillLock> {
+    let key = lock_key(short_url);
+    let token = format!("{:016x}", rand::thread_rng().gen::<u64>());
+    let acquired: redis::RedisResult<Option<String>> = redis::cmd("SET")
+        .arg(&key)
+        .arg(&token)
+        .arg("NX")
+        .arg("PX")
+        .arg(LOCK_TTL_MS)
+        .query_async(redis_conn)
+        .await;
+    match acquired {
+        Ok(Some(_)) => Some(FillLock { key, token: Some(token) }),
+        Ok(None) => None,
+        Err(e) => {
+            warn!("Redis fill lock error: {}", e);
+            Some(FillLock { key, token: None })
+        }
+    }
+}
+
+impl FillLock {
+    /// Libera a trava, só se ela ainda for desta requisição
+    pub async fn release(self, redis_conn: &mut RedisConnection) {
+        let Some(token) = self.token else {
+            return;
+        };
+        let released: redis::RedisResult<i64> = Script::new(RELEASE_SCRIPT)
+            .key(&self.key)
+            .arg(token)
+            .invoke_async(redis_conn)
+            .await;
+        if let Err(e) = released {
+            warn!("Redis fill lock release error: {}", e);
+        }
+    }
+}
+
+/// Espera a requisição com a trava repopular o cache. `None` se a trava foi
+/// liberada sem entrada no cache (ex: cache negativo desativado), se a espera
+/// passou do limite ou se o Redis falhou: nesses casos a requisição consulta o
+/// Cassandra por conta própria
+pub async fn wait_for_fill(redis_conn: &mut RedisConnection, short_url: &str) -> Option<CacheEntry> {
+    for _ in 0..MAX_WAIT_STEPS {
+        tokio::time::sleep(WAIT_STEP).await;
+        let polled: redis::RedisResult<(Option<String>, bool)> = redis::pipe()
+            .get(cache_key(short_url))
+            .exists(lock_key(short_url))
+            .query_async(redis_conn)
+            .await;
+        match polled {
+            Ok((value, locked)) => {
+                if let Some(entry) = CacheEntry::parse(value) {
+                    return Some(entry);
+                }
+                if !locked {
+                    return None;
+                }
+            }
+            Err(e) => {
+                warn!("Redis cache error: {}", e);
+                return None;
+            }
+        }
+    }
+    None
+}
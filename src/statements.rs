@@ -0,0 +1,298 @@
+use scylla::prepared_statement::PreparedStatement;
+use scylla::statement::Consistency;
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use std::time::Duration;
+
+/// Statements usados pelos handlers, preparados uma única vez na inicialização.
+///
+/// Além de evitar que o Scylla reprocesse o CQL a cada requisição, statements
+/// preparados permitem ao driver calcular o token da partition key e enviar a
+/// consulta direto para uma réplica (token-aware routing), reduzindo a latência.
+///
+/// Os statements de links usam `consistency`; os de contadores de cliques usam
+/// `analytics_consistency`, que pode ser mais baixa sem afetar os redirecionamentos.
+/// Todos falham com `QueryError::RequestTimeout` depois de `timeout`.
+pub struct Statements {
+    pub insert_url: PreparedStatement,
+    pub select_url: PreparedStatement,
+    pub select_url_details: PreparedStatement,
+    pub update_url: PreparedStatement,
+    pub delete_url: PreparedStatement,
+    pub delete_url_unconditional: PreparedStatement,
+    pub increment_clicks: PreparedStatement,
+    pub select_clicks: PreparedStatement,
+    pub select_click_codes: PreparedStatement,
+    pub select_all_clicks: PreparedStatement,
+    pub select_created_at: PreparedStatement,
+    pub delete_clicks: PreparedStatement,
+    pub touch_url: PreparedStatement,
+    pub select_last_accessed: PreparedStatement,
+    pub exists_url: PreparedStatement,
+    pub select_url_by_long: PreparedStatement,
+    pub insert_url_by_long: PreparedStatement,
+    pub select_all_url_by_long: PreparedStatement,
+    pub delete_url_by_long: PreparedStatement,
+    pub list_urls: PreparedStatement,
+    pub list_urls_by_owner: PreparedStatement,
+    pub insert_audit: PreparedStatement,
+    pub select_audit: PreparedStatement,
+    pub insert_url_geo: PreparedStatement,
+    pub select_url_geo: PreparedStatement,
+    pub delete_url_geo: PreparedStatement,
+    pub insert_url_variant: PreparedStatement,
+    pub select_url_variants: PreparedStatement,
+    pub delete_url_variants: PreparedStatement,
+    pub increment_variant_clicks: PreparedStatement,
+    pub select_variant_clicks: PreparedStatement,
+    pub delete_variant_clicks: PreparedStatement,
+    pub insert_click_event: PreparedStatement,
+    pub sum_click_events: PreparedStatement,
+    pub insert_tombstone: PreparedStatement,
+    pub select_tombstone: PreparedStatement,
+    pub insert_url_tag: PreparedStatement,
+    pub delete_url_tag: PreparedStatement,
+    pub list_urls_by_tag: PreparedStatement,
+    pub select_listed_url: PreparedStatement,
+}
+
+impl Statements {
+    pub async fn prepare(
+        cassandra: &Session,
+        consistency: Consistency,
+        analytics_consistency: Consistency,
+        timeout: Duration,
+    ) -> Result<Self, QueryError> {
+        let mut statements = Statements {
+            // TTL 0 no Cassandra significa "sem expiração"
+            insert_url: cassandra
+                .prepare("INSERT INTO urls (short_url, long_url, created_at, permanent, max_clicks, password_hash, owner, tags) VALUES (?, ?, ?, ?, ?, ?, ?, ?) IF NOT EXISTS USING TTL ?")
+                .await?,
+            select_url: cassandra
+                .prepare("SELECT long_url, TTL(long_url), permanent, created_at, max_clicks, password_hash, owner, tags FROM urls WHERE short_url = ?")
+                .await?,
+            // Linha completa, para a consulta de metadados
+            select_url_details: cassandra
+                .prepare("SELECT long_url, TTL(long_url), permanent, created_at, max_clicks, password_hash, last_accessed, owner, tags FROM urls WHERE short_url = ?")
+                .await?,
+            // Reaplica o TTL restante do link: sem ele a nova célula não expiraria
+            update_url: cassandra
+                .prepare("UPDATE urls USING TTL ? SET long_url = ? WHERE short_url = ? IF EXISTS")
+                .await?,
+            delete_url: cassandra
+                .prepare("DELETE FROM urls WHERE short_url = ? IF EXISTS")
+                .await?,
+            // Usado em lotes, onde LWT entre partições diferentes não é permitido
+            delete_url_unconditional: cassandra
+                .prepare("DELETE FROM urls WHERE short_url = ?")
+                .await?,
+            increment_clicks: cassandra
+                .prepare("UPDATE url_clicks SET hits = hits + 1 WHERE short_url = ?")
+                .await?,
+            select_clicks: cassandra
+                .prepare("SELECT hits FROM url_clicks WHERE short_url = ?")
+                .await?,
+            // Varreduras da limpeza periódica (ver `cleanup`)
+            select_click_codes: cassandra
+                .prepare("SELECT short_url FROM url_clicks")
+                .await?,
+            // Varredura do aquecimento do cache (ver `cache_warm`)
+            select_all_clicks: cassandra
+                .prepare("SELECT short_url, hits FROM url_clicks")
+                .await?,
+            // Varredura do resumo em `GET /api/stats/summary` (ver `summary`)
+            select_created_at: cassandra
+                .prepare("SELECT created_at FROM urls")
+                .await?,
+            delete_clicks: cassandra
+                .prepare("DELETE FROM url_clicks WHERE short_url = ?")
+                .await?,
+            // O TTL acompanha o restante do link; IF EXISTS evita recriar um link removido
+            touch_url: cassandra
+                .prepare("UPDATE urls USING TTL ? SET last_accessed = ?, last_cache_hit = ? WHERE short_url = ? IF EXISTS")
+                .await?,
+            select_last_accessed: cassandra
+                .prepare("SELECT last_accessed, last_cache_hit FROM urls WHERE short_url = ?")
+                .await?,
+            exists_url: cassandra
+                .prepare("SELECT short_url FROM urls WHERE short_url = ? LIMIT 1")
+                .await?,
+            select_url_by_long: cassandra
+                .prepare("SELECT short_url FROM url_by_long WHERE long_hash = ?")
+                .await?,
+            insert_url_by_long: cassandra
+                .prepare("INSERT INTO url_by_long (long_hash, short_url) VALUES (?, ?)")
+                .await?,
+            select_all_url_by_long: cassandra
+                .prepare("SELECT long_hash, short_url FROM url_by_long")
+                .await?,
+            delete_url_by_long: cassandra
+                .prepare("DELETE FROM url_by_long WHERE long_hash = ?")
+                .await?,
+            // Varredura paginada da tabela (ordem dos tokens, não da criação)
+            list_urls: cassandra
+                .prepare("SELECT short_url, long_url, created_at, owner, tags FROM urls")
+                .await?,
+            // Mesma varredura, filtrada no coordenador: percorre a tabela inteira,
+            // mas evita um índice secundário só para relatórios administrativos
+            list_urls_by_owner: cassandra
+                .prepare("SELECT short_url, long_url, created_at, owner, tags FROM urls WHERE owner = ? ALLOW FILTERING")
+                .await?,
+            // O id (timeuuid) é gerado pelo coordenador
+            insert_audit: cassandra
+                .prepare("INSERT INTO audit_log (short_url, id, action, owner, at, detail) VALUES (?, now(), ?, ?, ?, ?)")
+                .await?,
+            select_audit: cassandra
+                .prepare("SELECT action, owner, at, detail FROM audit_log WHERE short_url = ? LIMIT ?")
+                .await?,
+            insert_url_geo: cassandra
+                .prepare("INSERT INTO url_geo (short_url, country, long_url) VALUES (?, ?, ?) USING TTL ?")
+                .await?,
+            select_url_geo: cassandra
+                .prepare("SELECT country, long_url FROM url_geo WHERE short_url = ?")
+                .await?,
+            delete_url_geo: cassandra
+                .prepare("DELETE FROM url_geo WHERE short_url = ?")
+                .await?,
+            insert_url_variant: cassandra
+                .prepare("INSERT INTO url_variants (short_url, variant, url, weight) VALUES (?, ?, ?, ?) USING TTL ?")
+                .await?,
+            select_url_variants: cassandra
+                .prepare("SELECT variant, url, weight FROM url_variants WHERE short_url = ?")
+                .await?,
+            delete_url_variants: cassandra
+                .prepare("DELETE FROM url_variants WHERE short_url = ?")
+                .await?,
+            increment_variant_clicks: cassandra
+                .prepare("UPDATE url_variant_clicks SET hits = hits + 1 WHERE short_url = ? AND variant = ?")
+                .await?,
+            select_variant_clicks: cassandra
+                .prepare("SELECT variant, hits FROM url_variant_clicks WHERE short_url = ?")
+                .await?,
+            delete_variant_clicks: cassandra
+                .prepare("DELETE FROM url_variant_clicks WHERE short_url = ?")
+                .await?,
+            insert_click_event: cassandra
+                .prepare("INSERT INTO click_events (short_url, day, id, ts, weight, variant, country) VALUES (?, ?, now(), ?, ?, ?, ?) USING TTL ?")
+                .await?,
+            sum_click_events: cassandra
+                .prepare("SELECT SUM(weight) FROM click_events WHERE short_url = ? AND day = ?")
+                .await?,
+            insert_tombstone: cassandra
+                .prepare("INSERT INTO url_tombstones (short_url, deleted_at) VALUES (?, ?) USING TTL ?")
+                .await?,
+            select_tombstone: cassandra
+                .prepare("SELECT short_url FROM url_tombstones WHERE short_url = ?")
+                .await?,
+            insert_url_tag: cassandra
+                .prepare("INSERT INTO urls_by_tag (tag, short_url) VALUES (?, ?) USING TTL ?")
+                .await?,
+            delete_url_tag: cassandra
+                .prepare("DELETE FROM urls_by_tag WHERE tag = ? AND short_url = ?")
+                .await?,
+            list_urls_by_tag: cassandra
+                .prepare("SELECT short_url FROM urls_by_tag WHERE tag = ?")
+                .await?,
+            // Mesmas colunas de `list_urls`, para listar um código vindo de `urls_by_tag`
+            select_listed_url: cassandra
+                .prepare("SELECT short_url, long_url, created_at, owner, tags FROM urls WHERE short_url = ?")
+                .await?,
+        };
+
+        for statement in [
+            &mut statements.insert_url,
+            &mut statements.select_url,
+            &mut statements.select_url_details,
+            &mut statements.update_url,
+            &mut statements.delete_url,
+            &mut statements.delete_url_unconditional,
+            &mut statements.touch_url,
+            &mut statements.select_last_accessed,
+            &mut statements.exists_url,
+            &mut statements.select_url_by_long,
+            &mut statements.insert_url_by_long,
+            &mut statements.select_all_url_by_long,
+            &mut statements.delete_url_by_long,
+            &mut statements.list_urls,
+            &mut statements.list_urls_by_owner,
+            &mut statements.select_created_at,
+            &mut statements.insert_audit,
+            &mut statements.select_audit,
+            &mut statements.insert_url_geo,
+            &mut statements.select_url_geo,
+            &mut statements.delete_url_geo,
+            &mut statements.insert_url_variant,
+            &mut statements.select_url_variants,
+            &mut statements.delete_url_variants,
+            &mut statements.insert_tombstone,
+            &mut statements.select_tombstone,
+            &mut statements.insert_url_tag,
+            &mut statements.delete_url_tag,
+            &mut statements.list_urls_by_tag,
+            &mut statements.select_listed_url,
+        ] {
+            statement.set_consistency(consistency);
+        }
+        for statement in [
+            &mut statements.increment_clicks,
+            &mut statements.select_clicks,
+            &mut statements.select_click_codes,
+            &mut statements.select_all_clicks,
+            &mut statements.delete_clicks,
+            &mut statements.increment_variant_clicks,
+            &mut statements.select_variant_clicks,
+            &mut statements.delete_variant_clicks,
+            &mut statements.insert_click_event,
+            &mut statements.sum_click_events,
+        ] {
+            statement.set_consistency(analytics_consistency);
+        }
+
+        for statement in [
+            &mut statements.insert_url,
+            &mut statements.select_url,
+            &mut statements.select_url_details,
+            &mut statements.update_url,
+            &mut statements.delete_url,
+            &mut statements.delete_url_unconditional,
+            &mut statements.increment_clicks,
+            &mut statements.select_clicks,
+            &mut statements.select_click_codes,
+            &mut statements.select_all_clicks,
+            &mut statements.delete_clicks,
+            &mut statements.touch_url,
+            &mut statements.select_last_accessed,
+            &mut statements.exists_url,
+            &mut statements.select_url_by_long,
+            &mut statements.insert_url_by_long,
+            &mut statements.select_all_url_by_long,
+            &mut statements.delete_url_by_long,
+            &mut statements.list_urls,
+            &mut statements.list_urls_by_owner,
+            &mut statements.select_created_at,
+            &mut statements.insert_audit,
+            &mut statements.select_audit,
+            &mut statements.insert_url_geo,
+            &mut statements.select_url_geo,
+            &mut statements.delete_url_geo,
+            &mut statements.insert_url_variant,
+            &mut statements.select_url_variants,
+            &mut statements.delete_url_variants,
+            &mut statements.increment_variant_clicks,
+            &mut statements.select_variant_clicks,
+            &mut statements.delete_variant_clicks,
+            &mut statements.insert_click_event,
+            &mut statements.sum_click_events,
+            &mut statements.insert_tombstone,
+            &mut statements.select_tombstone,
+            &mut statements.insert_url_tag,
+            &mut statements.delete_url_tag,
+            &mut statements.list_urls_by_tag,
+            &mut statements.select_listed_url,
+        ] {
+            statement.set_request_timeout(Some(timeout));
+        }
+
+        Ok(statements)
+    }
+}
@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use axum::{extract::Extension, Json};
+use chrono::{DateTime, Duration, Utc};
+use futures::StreamExt;
+use redis::AsyncCommands;
+use scylla::frame::value::{Counter, Timestamp};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{from_cql_timestamp, AppError, AppState};
+
+/// Chave do resumo no Redis, compartilhada entre as instâncias
+const CACHE_KEY: &str = "stats:summary";
+/// Por quanto tempo o resumo é servido do cache (segundos)
+const CACHE_TTL_SECS: u64 = 60;
+
+#[derive(Serialize, Deserialize)]
+pub struct Summary {
+    /// Links ativos (expirados e removidos ficam de fora)
+    total_links: u64,
+    /// Soma dos contadores de cliques (ver `sum_clicks`)
+    total_clicks: i64,
+    /// Links ativos criados nas últimas 24 horas
+    links_last_24h: u64,
+    /// Quando os totais foram calculados (até `CACHE_TTL_SECS` atrás)
+    generated_at: DateTime<Utc>,
+}
+
+// GET /api/stats/summary
+pub async fn summary_stats(Extension(state): Extension<Arc<AppState>>) -> Result<Json<Summary>, AppError> {
+    // 1. Resumo recente no cache: as varreduras abaixo percorrem tabelas inteiras
+    let mut redis_conn = state.redis.get();
+    match redis_conn.get::<_, Option<String>>(CACHE_KEY).await {
+        Ok(Some(json)) => {
+            if let Ok(summary) = serde_json::from_str(&json) {
+                return Ok(Json(summary));
+            }
+        }
+        Ok(None) => {}
+        Err(e) => warn!("Redis cache error: {}", e),
+    }
+
+    // 2. Varre `urls` e `url_clicks` em paralelo
+    let (links, total_clicks) = futures::try_join!(count_links(&state), sum_clicks(&state))?;
+    let summary = Summary {
+        total_links: links.0,
+        total_clicks,
+        links_last_24h: links.1,
+        generated_at: Utc::now(),
+    };
+
+    // 3. Guarda para as próximas chamadas (de qualquer instância)
+    if let Ok(json) = serde_json::to_string(&summary) {
+        let cache_result: redis::RedisResult<()> = redis_conn.set_ex(CACHE_KEY, json, CACHE_TTL_SECS).await;
+        if let Err(e) = cache_result {
+            warn!("Redis cache error: {}", e);
+        }
+    }
+    Ok(Json(summary))
+}
+
+/// Total de links e quantos deles foram criados nas últimas 24 horas
+async fn count_links(state: &AppState) -> Result<(u64, u64), AppError> {
+    let since = Utc::now() - Duration::hours(24);
+    let mut rows = state
+        .cassandra_breaker
+        .run(state.cassandra.execute_iter(state.statements.select_created_at.clone(), &[]))
+        .await?
+        .into_typed::<(Option<Timestamp>,)>();
+    let (mut total, mut recent) = (0, 0);
+    while let Some(row) = rows.next().await {
+        let (created_at,) = row.map_err(|e| AppError::Internal(format!("urls row: {}", e)))?;
+        total += 1;
+        if created_at.map(from_cql_timestamp).is_some_and(|at| at >= since) {
+            recent += 1;
+        }
+    }
+    Ok((total, recent))
+}
+
+/// Soma dos contadores de `url_clicks`. Contadores órfãos (de links expirados
+/// ainda não limpos por `cleanup`) também entram na soma
+async fn sum_clicks(state: &AppState) -> Result<i64, AppError> {
+    let mut rows = state
+        .cassandra_breaker
+        .run(state.cassandra.execute_iter(state.statements.select_all_clicks.clone(), &[]))
+        .await?
+        .into_typed::<(String, Counter)>();
+    let mut total = 0;
+    while let Some(row) = rows.next().await {
+        let (_, Counter(hits)) = row.map_err(|e| AppError::Internal(format!("url_clicks row: {}", e)))?;
+        total += hits;
+    }
+    Ok(total)
+}
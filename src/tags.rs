@@ -0,0 +1,106 @@
+use tracing::warn;
+
+use crate::validation::FieldError;
+use crate::{AppError, AppState};
+
+/// Máximo de tags por link
+pub const MAX_TAGS: usize = 10;
+/// Tamanho máximo de cada tag
+pub const MAX_TAG_LEN: usize = 32;
+
+/// Valida as tags enviadas na criação: até 10, cada uma com até 32 letras,
+/// dígitos, '-' ou '_'. As tags são gravadas em minúsculas, sem repetições e em
+/// ordem alfabética (`Launch` e `launch` são a mesma tag)
+pub fn normalize_tags(raw: &[String]) -> Result<Vec<String>, Vec<FieldError>> {
+    let mut errors = Vec::new();
+    let mut tags: Vec<String> = Vec::with_capacity(raw.len());
+    for tag in raw {
+        let tag = tag.trim().to_lowercase();
+        if !is_valid_tag(&tag) {
+            errors.push(FieldError::new(
+                "tags",
+                format!(
+                    "'{}' is not a valid tag: use 1 to {} letters, digits, '-' or '_'",
+                    tag, MAX_TAG_LEN
+                ),
+            ));
+            continue;
+        }
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    if tags.len() > MAX_TAGS {
+        errors.push(FieldError::new("tags", format!("a link can have at most {} tags", MAX_TAGS)));
+    }
+
+    if errors.is_empty() {
+        tags.sort();
+        Ok(tags)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Tag já em minúsculas, como as gravadas por `normalize_tags`
+pub fn is_valid_tag(tag: &str) -> bool {
+    !tag.is_empty()
+        && tag.len() <= MAX_TAG_LEN
+        && tag
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_')
+}
+
+/// Grava o código em `urls_by_tag` para cada tag, com o mesmo TTL do link (0 = sem expiração)
+pub async fn store(state: &AppState, short_url: &str, tags: &[String], ttl: i32) -> Result<(), AppError> {
+    // Uma partição por tag: inserts em paralelo em vez de um batch entre nós
+    let inserts = tags.iter().map(|tag| {
+        state.cassandra_breaker.run(
+            state
+                .cassandra
+                .execute(&state.statements.insert_url_tag, (tag.clone(), short_url.to_string(), ttl)),
+        )
+    });
+    futures::future::try_join_all(inserts).await?;
+    Ok(())
+}
+
+/// Remove o código do índice de tags de um link removido. Falhas só deixam
+/// entradas órfãs, que a listagem ignora e o TTL acaba limpando
+pub async fn delete(state: &AppState, short_url: &str, tags: &[String]) {
+    let deletes = tags.iter().map(|tag| async move {
+        let result = state
+            .cassandra_breaker
+            .run(
+                state
+                    .cassandra
+                    .execute(&state.statements.delete_url_tag, (tag.clone(), short_url.to_string())),
+            )
+            .await;
+        if let Err(e) = result {
+            warn!("Cassandra urls_by_tag delete error for '{}' ({}): {}", short_url, tag, e);
+        }
+    });
+    futures::future::join_all(deletes).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_tags() {
+        let raw = vec![" Launch ".to_string(), "q3-2025".to_string(), "launch".to_string()];
+        assert_eq!(normalize_tags(&raw).unwrap(), ["launch", "q3-2025"]);
+        assert!(normalize_tags(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_tags() {
+        assert!(normalize_tags(&["".to_string()]).is_err());
+        assert!(normalize_tags(&["black friday".to_string()]).is_err());
+        assert!(normalize_tags(&["x".repeat(MAX_TAG_LEN + 1)]).is_err());
+        let too_many: Vec<String> = (0..=MAX_TAGS).map(|i| format!("tag{}", i)).collect();
+        assert!(normalize_tags(&too_many).is_err());
+    }
+}
@@ -0,0 +1,111 @@
+use std::cell::Cell;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::{extract::Request, middleware::Next, response::Response, Extension};
+use tracing::warn;
+
+use crate::AppState;
+
+tokio::task_local! {
+    /// Tempo gasto no Redis e no Cassandra pela requisição em andamento
+    static BACKENDS: BackendTimes;
+}
+
+#[derive(Default)]
+struct BackendTimes {
+    redis: Cell<Duration>,
+    cassandra: Cell<Duration>,
+}
+
+/// Backend em que a requisição passou mais tempo
+fn dominant(redis: Duration, cassandra: Duration) -> &'static str {
+    match (redis.is_zero(), cassandra.is_zero()) {
+        (true, true) => "none",
+        _ if cassandra >= redis => "cassandra",
+        _ => "redis",
+    }
+}
+
+async fn timed<F: Future>(query: F, slot: fn(&BackendTimes) -> &Cell<Duration>) -> F::Output {
+    let start = Instant::now();
+    let output = query.await;
+    let elapsed = start.elapsed();
+    // Fora de uma requisição (tarefas em segundo plano, limpeza) não há o que acumular
+    let _ = BACKENDS.try_with(|times| {
+        let slot = slot(times);
+        slot.set(slot.get() + elapsed);
+    });
+    output
+}
+
+/// Mede um comando do Redis e soma o tempo ao da requisição atual
+pub async fn redis<F: Future>(command: F) -> F::Output {
+    timed(command, |times| &times.redis).await
+}
+
+/// Mede uma query do Cassandra e soma o tempo ao da requisição atual.
+/// Queries em paralelo (`try_join!`) somam o tempo de cada uma
+pub async fn cassandra<F: Future>(query: F) -> F::Output {
+    timed(query, |times| &times.cassandra).await
+}
+
+/// Middleware que mede cada requisição e o tempo gasto em cada backend.
+///
+/// Os tempos alimentam `shortener_backend_duration_seconds`; requisições acima de
+/// `SLOW_REQUEST_MS` geram um `warn!` com o caminho e o backend que dominou.
+pub async fn track_slow_requests(
+    Extension(state): Extension<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let start = Instant::now();
+
+    let (response, redis, cassandra) = BACKENDS
+        .scope(BackendTimes::default(), async move {
+            let response = next.run(request).await;
+            let (redis, cassandra) = BACKENDS.with(|times| (times.redis.get(), times.cassandra.get()));
+            (response, redis, cassandra)
+        })
+        .await;
+    let elapsed = start.elapsed();
+
+    if !redis.is_zero() {
+        state.metrics.redis_latency.observe(redis.as_secs_f64());
+    }
+    if !cassandra.is_zero() {
+        state.metrics.cassandra_latency.observe(cassandra.as_secs_f64());
+    }
+
+    let threshold = state.config.slow_request_ms;
+    if threshold > 0 && elapsed >= Duration::from_millis(threshold) {
+        warn!(
+            method = %method,
+            path = %path,
+            status = response.status().as_u16(),
+            latency_ms = elapsed.as_secs_f64() * 1000.0,
+            redis_ms = redis.as_secs_f64() * 1000.0,
+            cassandra_ms = cassandra.as_secs_f64() * 1000.0,
+            dominant = dominant(redis, cassandra),
+            "Slow request"
+        );
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_dominant_backend() {
+        let ms = Duration::from_millis;
+        assert_eq!(dominant(ms(0), ms(0)), "none");
+        assert_eq!(dominant(ms(5), ms(400)), "cassandra");
+        assert_eq!(dominant(ms(300), ms(2)), "redis");
+        assert_eq!(dominant(ms(3), ms(0)), "redis");
+    }
+}
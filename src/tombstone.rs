@@ -0,0 +1,30 @@
+use chrono::Utc;
+use tracing::warn;
+
+use crate::{to_cql_timestamp, AppError, AppState};
+
+/// Marca um código como removido de propósito (`DELETE /:short_url`), para que
+/// o redirecionamento responda 410 em vez de 404. A marca expira depois de
+/// `TOMBSTONE_TTL` (0 = nunca) e é ignorada se o alias for criado de novo
+pub async fn record(state: &AppState, short_url: &str) {
+    let ttl = state.config.tombstone_ttl_secs.min(i32::MAX as u64) as i32;
+    if let Err(e) = state
+        .cassandra_breaker
+        .run(state.cassandra.execute(
+            &state.statements.insert_tombstone,
+            (short_url.to_string(), to_cql_timestamp(Utc::now()), ttl),
+        ))
+        .await
+    {
+        warn!("Cassandra tombstone error for '{}': {}", short_url, e);
+    }
+}
+
+/// Indica se um código inexistente foi removido (e não apenas digitado errado)
+pub async fn exists(state: &AppState, short_url: &str) -> Result<bool, AppError> {
+    let result = state
+        .cassandra_breaker
+        .run(state.cassandra.execute(&state.statements.select_tombstone, (short_url.to_string(),)))
+        .await?;
+    Ok(result.rows.is_some_and(|rows| !rows.is_empty()))
+}
@@ -0,0 +1,232 @@
+use serde::Serialize;
+use url::Url;
+
+/// Problema em um campo do corpo, devolvido na lista `errors` de um 422
+#[derive(Debug, Serialize)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &'static str, message: impl Into<String>) -> Self {
+        FieldError {
+            field,
+            message: message.into(),
+        }
+    }
+}
+
+/// Esquemas que nunca podem ser liberados: executam código ou leem dados no
+/// contexto de quem abre o link
+const FORBIDDEN_SCHEMES: &[&str] = &["javascript", "vbscript", "data", "file", "blob"];
+
+/// Esquemas aceitos como destino (`ALLOWED_SCHEMES`); por padrão só `http` e `https`
+pub struct AllowedSchemes {
+    schemes: Vec<String>,
+}
+
+impl Default for AllowedSchemes {
+    fn default() -> Self {
+        AllowedSchemes {
+            schemes: vec!["http".to_string(), "https".to_string()],
+        }
+    }
+}
+
+impl AllowedSchemes {
+    /// Lê a lista separada por vírgula (ex: `http,https,mailto,tel`)
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let mut schemes = Vec::new();
+        for scheme in raw.split(',').map(|s| s.trim().to_ascii_lowercase()) {
+            if scheme.is_empty() {
+                continue;
+            }
+            let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+            if !valid {
+                return Err(format!("Invalid scheme '{}' in ALLOWED_SCHEMES", scheme));
+            }
+            if FORBIDDEN_SCHEMES.contains(&scheme.as_str()) {
+                return Err(format!("Scheme '{}' cannot be allowed in ALLOWED_SCHEMES", scheme));
+            }
+            if !schemes.contains(&scheme) {
+                schemes.push(scheme);
+            }
+        }
+        if schemes.is_empty() {
+            return Err("ALLOWED_SCHEMES must list at least one scheme".to_string());
+        }
+        Ok(AllowedSchemes { schemes })
+    }
+
+    fn allows(&self, scheme: &str) -> bool {
+        self.schemes.iter().any(|allowed| allowed == scheme)
+    }
+}
+
+/// Valida e normaliza a URL de destino.
+///
+/// Aceita apenas os esquemas de `ALLOWED_SCHEMES` (`http`/`https` por padrão;
+/// `javascript:`, `data:` etc. nunca, pois seriam servidos pelo redirecionamento)
+/// e retorna a URL normalizada pelo parser: host em minúsculas e sem a porta
+/// padrão do esquema. URLs web precisam de host; as demais (`mailto:`, `tel:`),
+/// de algo depois do esquema.
+pub fn normalize_long_url(raw: &str, max_len: usize, schemes: &AllowedSchemes) -> Result<String, String> {
+    let raw = raw.trim();
+    if raw.len() > max_len {
+        return Err(format!("long_url exceeds {} characters", max_len));
+    }
+
+    let parsed = Url::parse(raw).map_err(|e| format!("invalid long_url: {}", e))?;
+    if !schemes.allows(parsed.scheme()) {
+        return Err(format!("scheme '{}' is not allowed", parsed.scheme()));
+    }
+    if matches!(parsed.scheme(), "http" | "https") {
+        if parsed.host_str().is_none_or(str::is_empty) {
+            return Err("long_url must have a host".to_string());
+        }
+    } else if parsed.path().is_empty() && parsed.host_str().is_none_or(str::is_empty) {
+        return Err(format!("long_url has nothing after '{}:'", parsed.scheme()));
+    }
+
+    let normalized = String::from(parsed);
+    if normalized.len() > max_len {
+        return Err(format!("long_url exceeds {} characters", max_len));
+    }
+    Ok(normalized)
+}
+
+/// Domínios bloqueados para encurtamento (ex: hosts usados em phishing).
+///
+/// Bloquear `evil.com` também bloqueia qualquer subdomínio (`x.evil.com`). A
+/// comparação ignora maiúsculas e um `.` final em ambos os lados.
+#[derive(Default)]
+pub struct DomainBlocklist {
+    domains: Vec<String>,
+}
+
+impl DomainBlocklist {
+    /// Lê a lista separada por vírgula de `BLOCKED_DOMAINS`
+    pub fn parse(raw: &str) -> Self {
+        let domains = raw
+            .split(',')
+            .map(normalize_host)
+            .filter(|domain| !domain.is_empty())
+            .collect();
+        DomainBlocklist { domains }
+    }
+
+    /// Verifica o host de uma URL já normalizada por `normalize_long_url`
+    pub fn is_blocked(&self, long_url: &str) -> bool {
+        if self.domains.is_empty() {
+            return false;
+        }
+        let host = match Url::parse(long_url) {
+            Ok(url) => match url.host_str() {
+                Some(host) => normalize_host(host),
+                None => return false,
+            },
+            Err(_) => return false,
+        };
+
+        self.domains.iter().any(|domain| {
+            host == *domain
+                || host
+                    .strip_suffix(domain.as_str())
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        })
+    }
+}
+
+/// Verifica se uma URL já normalizada aponta para o próprio encurtador
+/// (mesmo host e porta de `PUBLIC_BASE_URL`), o que criaria cadeias ou loops
+/// de redirecionamento. As portas padrão de http e https contam como a mesma: um
+/// `http://` para uma base `https://` volta ao serviço pelo redirecionamento para https
+pub fn is_self_link(long_url: &str, public_base_url: &str) -> bool {
+    let (Ok(target), Ok(base)) = (Url::parse(long_url), Url::parse(public_base_url)) else {
+        return false;
+    };
+    match (target.host_str(), base.host_str()) {
+        (Some(target_host), Some(base_host)) => {
+            normalize_host(target_host) == normalize_host(base_host)
+                && target.port() == base.port()
+        }
+        _ => false,
+    }
+}
+
+fn normalize_host(host: &str) -> String {
+    host.trim().trim_end_matches('.').to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_self_links() {
+        assert!(is_self_link("https://sho.rt/abc", "https://sho.rt"));
+        assert!(is_self_link("https://SHO.rt.:443/abc", "https://sho.rt"));
+        assert!(is_self_link("http://localhost:3000/x", "http://localhost:3000"));
+        assert!(!is_self_link("http://localhost:8080/x", "http://localhost:3000"));
+        assert!(!is_self_link("https://docs.sho.rt/", "https://sho.rt"));
+    }
+
+    #[test]
+    fn detects_scheme_downgraded_self_links() {
+        assert!(is_self_link("http://sho.rt/abc", "https://sho.rt"));
+        assert!(is_self_link("https://sho.rt/abc", "http://sho.rt"));
+        assert!(!is_self_link("http://sho.rt:8080/abc", "https://sho.rt"));
+    }
+
+    #[test]
+    fn normalizes_host_and_default_port() {
+        let schemes = AllowedSchemes::default();
+        assert_eq!(
+            normalize_long_url("HTTPS://Rust-Lang.ORG:443/Learn?q=1", 2048, &schemes).unwrap(),
+            "https://rust-lang.org/Learn?q=1"
+        );
+    }
+
+    #[test]
+    fn rejects_non_http_schemes() {
+        let schemes = AllowedSchemes::default();
+        assert!(normalize_long_url("javascript:alert(1)", 2048, &schemes).is_err());
+        assert!(normalize_long_url("data:text/html,<script>", 2048, &schemes).is_err());
+        assert!(normalize_long_url("mailto:team@example.com", 2048, &schemes).is_err());
+        assert!(normalize_long_url("not a url", 2048, &schemes).is_err());
+    }
+
+    #[test]
+    fn allows_configured_schemes() {
+        let schemes = AllowedSchemes::parse("https, MAILTO,tel").unwrap();
+        assert_eq!(
+            normalize_long_url("mailto:team@example.com", 2048, &schemes).unwrap(),
+            "mailto:team@example.com"
+        );
+        assert!(normalize_long_url("tel:+5511999999999", 2048, &schemes).is_ok());
+        assert!(normalize_long_url("http://example.com/", 2048, &schemes).is_err());
+        assert!(normalize_long_url("mailto:", 2048, &schemes).is_err());
+
+        assert!(AllowedSchemes::parse("https,javascript").is_err());
+        assert!(AllowedSchemes::parse(" , ").is_err());
+    }
+
+    #[test]
+    fn rejects_long_urls() {
+        let url = format!("https://example.com/{}", "a".repeat(100));
+        assert!(normalize_long_url(&url, 50, &AllowedSchemes::default()).is_err());
+    }
+
+    #[test]
+    fn blocks_domains_and_subdomains() {
+        let blocklist = DomainBlocklist::parse("evil.com, Phish.example. ,");
+        assert!(blocklist.is_blocked("https://evil.com/login"));
+        assert!(blocklist.is_blocked("https://x.EVIL.com./login"));
+        assert!(blocklist.is_blocked("http://a.b.phish.example/"));
+        assert!(!blocklist.is_blocked("https://notevil.com/"));
+        assert!(!blocklist.is_blocked("https://evil.com.safe.org/"));
+        assert!(!DomainBlocklist::default().is_blocked("https://evil.com/"));
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::http::{header, HeaderMap, HeaderValue};
+use futures::StreamExt;
+use rand::Rng;
+use scylla::batch::Batch;
+use scylla::frame::value::Counter;
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+
+use crate::validation::{is_self_link, normalize_long_url, AllowedSchemes, FieldError};
+use crate::{AppError, AppState, SELF_LINK_ERROR};
+
+/// Máximo de variantes por link
+const MAX_VARIANTS: usize = 10;
+/// Peso máximo de uma variante (os pesos são relativos à soma)
+const MAX_WEIGHT: u32 = 10_000;
+/// Por quanto tempo o navegador mantém a variante sorteada (30 dias, em segundos)
+const STICKY_COOKIE_MAX_AGE: u64 = 30 * 24 * 60 * 60;
+
+/// Destino de um teste A/B: recebe `weight` / (soma dos pesos) dos novos visitantes
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Variant {
+    pub name: String,
+    pub url: String,
+    pub weight: u32,
+}
+
+/// Valida as variantes enviadas na criação: de 2 a 10, nomes únicos no formato
+/// de um alias, pesos entre 1 e 10000 e URLs com as mesmas regras da `long_url`
+pub fn normalize_variants(
+    raw: &[Variant],
+    max_url_len: usize,
+    schemes: &AllowedSchemes,
+    public_base_url: &str,
+) -> Result<Vec<Variant>, Vec<FieldError>> {
+    let mut errors = Vec::new();
+    if raw.len() < 2 || raw.len() > MAX_VARIANTS {
+        errors.push(FieldError::new(
+            "variants",
+            format!("variants must list between 2 and {} destinations", MAX_VARIANTS),
+        ));
+    }
+
+    let mut variants = Vec::with_capacity(raw.len());
+    for variant in raw {
+        let name = variant.name.trim();
+        if name.is_empty() || name.len() > 32 || !name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_') {
+            errors.push(FieldError::new(
+                "variants",
+                format!("'{}' is not a valid variant name: use up to 32 letters, digits, '-' or '_'", name),
+            ));
+            continue;
+        }
+        if variants.iter().any(|v: &Variant| v.name == name) {
+            errors.push(FieldError::new("variants", format!("variant '{}' is listed twice", name)));
+            continue;
+        }
+        if variant.weight == 0 || variant.weight > MAX_WEIGHT {
+            errors.push(FieldError::new(
+                "variants",
+                format!("{}: weight must be between 1 and {}", name, MAX_WEIGHT),
+            ));
+            continue;
+        }
+        match normalize_long_url(&variant.url, max_url_len, schemes) {
+            Ok(url) if is_self_link(&url, public_base_url) => {
+                errors.push(FieldError::new("variants", format!("{}: {}", name, SELF_LINK_ERROR)));
+            }
+            Ok(url) => variants.push(Variant {
+                name: name.to_string(),
+                url,
+                weight: variant.weight,
+            }),
+            Err(message) => errors.push(FieldError::new("variants", format!("{}: {}", name, message))),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(variants)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Nome do cookie que fixa a variante de um código (`ab_{short_url}`)
+fn cookie_name(short_url: &str) -> String {
+    format!("ab_{}", short_url)
+}
+
+/// Variante já sorteada para este cliente, lida do cookie `ab_{short_url}`
+fn sticky_variant<'a>(headers: &HeaderMap, short_url: &str, variants: &'a [Variant]) -> Option<&'a Variant> {
+    let name = cookie_name(short_url);
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .and_then(|(_, value)| variants.iter().find(|v| v.name == value))
+}
+
+/// Sorteia uma variante proporcionalmente ao peso; `roll` vai de 0 à soma dos pesos (exclusive)
+fn pick_weighted(variants: &[Variant], roll: u64) -> &Variant {
+    let mut remaining = roll;
+    for variant in variants {
+        if remaining < variant.weight as u64 {
+            return variant;
+        }
+        remaining -= variant.weight as u64;
+    }
+    // Só com `roll` fora da faixa: fica com a última
+    &variants[variants.len() - 1]
+}
+
+/// Escolhe a variante do cliente: a do cookie, se ainda existir, ou uma nova
+/// sorteada. O segundo valor indica se o sorteio é novo (e precisa do cookie)
+pub fn choose<'a>(headers: &HeaderMap, short_url: &str, variants: &'a [Variant]) -> (&'a Variant, bool) {
+    if let Some(variant) = sticky_variant(headers, short_url, variants) {
+        return (variant, false);
+    }
+    let total: u64 = variants.iter().map(|v| v.weight as u64).sum();
+    let roll = rand::thread_rng().gen_range(0..total.max(1));
+    (pick_weighted(variants, roll), true)
+}
+
+/// `Set-Cookie` que fixa a variante sorteada, restrito ao caminho do código
+pub fn sticky_cookie(short_url: &str, variant: &Variant) -> Option<HeaderValue> {
+    HeaderValue::from_str(&format!(
+        "{}={}; Path=/{}; Max-Age={}; HttpOnly; SameSite=Lax",
+        cookie_name(short_url),
+        variant.name,
+        short_url,
+        STICKY_COOKIE_MAX_AGE
+    ))
+    .ok()
+}
+
+/// Lê as variantes de um link (`None` se ele não tiver nenhuma)
+pub async fn load(state: &AppState, short_url: &str) -> Result<Option<Vec<Variant>>, AppError> {
+    let mut rows = state
+        .cassandra_breaker
+        .run(
+            state
+                .cassandra
+                .execute_iter(state.statements.select_url_variants.clone(), (short_url.to_string(),)),
+        )
+        .await?
+        .into_typed::<(String, String, i32)>();
+    let mut variants = Vec::new();
+    while let Some(row) = rows.next().await {
+        let (name, url, weight) = row.map_err(|e| AppError::Internal(format!("url_variants row: {}", e)))?;
+        variants.push(Variant {
+            name,
+            url,
+            weight: weight.max(0) as u32,
+        });
+    }
+    Ok((!variants.is_empty()).then_some(variants))
+}
+
+/// Grava as variantes com o mesmo TTL do link (0 = sem expiração)
+pub async fn store(state: &AppState, short_url: &str, variants: &[Variant], ttl: i32) -> Result<(), AppError> {
+    // Todas as linhas ficam na mesma partição, como em `geo::store`
+    let mut batch = Batch::default();
+    batch.set_consistency(state.config.consistency);
+    let rows: Vec<(String, String, String, i32, i32)> = variants
+        .iter()
+        .map(|v| (short_url.to_string(), v.name.clone(), v.url.clone(), v.weight as i32, ttl))
+        .collect();
+    for _ in &rows {
+        batch.append_statement(state.statements.insert_url_variant.clone());
+    }
+    state.cassandra_breaker.run(state.cassandra.batch(&batch, &rows)).await?;
+    Ok(())
+}
+
+/// Remove as variantes e os contadores de um link removido, para que um alias
+/// recriado com o mesmo código não herde o teste antigo
+pub async fn delete(state: &AppState, short_url: &str) {
+    for statement in [&state.statements.delete_url_variants, &state.statements.delete_variant_clicks] {
+        if let Err(e) = state
+            .cassandra_breaker
+            .run(state.cassandra.execute(statement, (short_url.to_string(),)))
+            .await
+        {
+            warn!("Cassandra variants delete error for '{}': {}", short_url, e);
+        }
+    }
+}
+
+/// Incrementa o contador da variante em segundo plano, como `record_click`
+pub fn record_click(state: &Arc<AppState>, short_url: &str, variant: &str) {
+    let state = Arc::clone(state);
+    let values = (short_url.to_string(), variant.to_string());
+    let background = state.background.clone();
+    background.spawn(async move {
+        if let Err(e) = state
+            .cassandra_breaker
+            .run(state.cassandra.execute(&state.statements.increment_variant_clicks, values))
+            .await
+        {
+            error!("Cassandra variant counter error: {}", e);
+        }
+    });
+}
+
+/// Cliques de uma variante, para `GET /api/urls/:short_url/stats`
+#[derive(Serialize)]
+pub struct VariantStats {
+    name: String,
+    url: String,
+    weight: u32,
+    hits: i64,
+}
+
+/// Variantes do link com os cliques de cada uma (`None` se ele não tiver variantes)
+pub async fn stats(state: &AppState, short_url: &str) -> Result<Option<Vec<VariantStats>>, AppError> {
+    let Some(variants) = load(state, short_url).await? else {
+        return Ok(None);
+    };
+
+    let mut rows = state
+        .cassandra_breaker
+        .run(
+            state
+                .cassandra
+                .execute_iter(state.statements.select_variant_clicks.clone(), (short_url.to_string(),)),
+        )
+        .await?
+        .into_typed::<(String, Counter)>();
+    let mut hits = HashMap::new();
+    while let Some(row) = rows.next().await {
+        let (name, Counter(count)) =
+            row.map_err(|e| AppError::Internal(format!("url_variant_clicks row: {}", e)))?;
+        hits.insert(name, count);
+    }
+
+    Ok(Some(
+        variants
+            .into_iter()
+            .map(|v| VariantStats {
+                hits: hits.get(&v.name).copied().unwrap_or(0),
+                name: v.name,
+                url: v.url,
+                weight: v.weight,
+            })
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variants() -> Vec<Variant> {
+        vec![
+            Variant { name: "a".to_string(), url: "https://example.com/a".to_string(), weight: 3 },
+            Variant { name: "b".to_string(), url: "https://example.com/b".to_string(), weight: 1 },
+        ]
+    }
+
+    #[test]
+    fn picks_by_weight() {
+        let variants = variants();
+        let picked: Vec<&str> = (0..4).map(|roll| pick_weighted(&variants, roll).name.as_str()).collect();
+        assert_eq!(picked, ["a", "a", "a", "b"]);
+    }
+
+    #[test]
+    fn keeps_the_cookie_variant() {
+        let variants = variants();
+        let mut headers = HeaderMap::new();
+        headers.insert(header::COOKIE, HeaderValue::from_static("theme=dark; ab_2tx=b"));
+        let (variant, new) = choose(&headers, "2tx", &variants);
+        assert_eq!((variant.name.as_str(), new), ("b", false));
+
+        // Variante que não existe mais é sorteada de novo
+        headers.insert(header::COOKIE, HeaderValue::from_static("ab_2tx=gone"));
+        assert!(choose(&headers, "2tx", &variants).1);
+    }
+
+    #[test]
+    fn validates_variants() {
+        let normalized = normalize_variants(&variants(), 2048, &AllowedSchemes::default(), "https://sho.rt").unwrap();
+        assert_eq!(normalized.len(), 2);
+
+        let mut duplicated = variants();
+        duplicated[1].name = "a".to_string();
+        assert!(normalize_variants(&duplicated, 2048, &AllowedSchemes::default(), "https://sho.rt").is_err());
+        assert!(normalize_variants(&variants()[..1], 2048, &AllowedSchemes::default(), "https://sho.rt").is_err());
+    }
+
+    #[test]
+    fn response_schemas_match_structs() {
+        use crate::openapi::schema_fields::assert_response;
+        let stats = VariantStats {
+            name: String::new(),
+            url: String::new(),
+            weight: 1,
+            hits: 0,
+        };
+        assert_response("UrlStats/properties/variants/items", &stats);
+    }
+}